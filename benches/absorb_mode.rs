@@ -0,0 +1,70 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Compares the native cost of absorbing in add-mode and in overwrite-mode.
+//!
+//! The permutation below is a placeholder with the shape of an arithmetic
+//! permutation. Integrators replace it with their own to measure which mode
+//! is cheaper for it, see [`AbsorbMode`].
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{AbsorbMode, Call, Safe, Sponge};
+
+const W: usize = 5;
+const INPUT_LEN: usize = 64;
+const IOPATTERN: &[Call] = &[Call::Absorb(INPUT_LEN), Call::Squeeze(1)];
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct Power<const OVERWRITE: bool>();
+
+impl<const OVERWRITE: bool> Safe<BlsScalar, W> for Power<OVERWRITE> {
+    // raise every element to the fifth power and mix them with their sum
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        state.iter_mut().for_each(|s| *s = s.square().square() * *s);
+        let sum: BlsScalar = state.iter().sum();
+        state.iter_mut().for_each(|s| *s += sum);
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        BlsScalar::hash_to_scalar(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+
+    const ABSORB_MODE: AbsorbMode = match OVERWRITE {
+        true => AbsorbMode::Overwrite,
+        false => AbsorbMode::Add,
+    };
+}
+
+fn hash<const OVERWRITE: bool>(input: &[BlsScalar]) -> BlsScalar {
+    let mut sponge = Sponge::start(Power::<OVERWRITE>(), IOPATTERN, 0)
+        .expect("the io-pattern is valid");
+    sponge
+        .absorb(input.len(), input)
+        .expect("absorbing succeeds");
+    sponge.squeeze(1).expect("squeezing succeeds");
+    sponge.finish().expect("the io-pattern is followed")[0]
+}
+
+fn absorb_mode(c: &mut Criterion) {
+    let input: Vec<BlsScalar> =
+        (0..INPUT_LEN as u64).map(BlsScalar::from).collect();
+
+    let mut group = c.benchmark_group("absorb_mode");
+    group
+        .bench_function("add", |b| b.iter(|| hash::<false>(black_box(&input))));
+    group.bench_function("overwrite", |b| {
+        b.iter(|| hash::<true>(black_box(&input)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, absorb_mode);
+criterion_main!(benches);
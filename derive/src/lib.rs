@@ -0,0 +1,163 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Derive macros for the `dusk-safe` crate.
+//!
+//! This crate is not meant to be used directly, enable the `derive` feature
+//! of `dusk-safe` instead.
+
+#![deny(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Index, LitStr, Type};
+
+/// Derives `dusk_safe::Absorbable` for a struct.
+///
+/// The element type of the sponge needs to be given with the
+/// `#[absorbable(element = T)]` attribute, and needs to implement
+/// `From<u64>`.
+///
+/// The value is absorbed as a type tag, followed by the fields in declaration
+/// order. The type tag is the 64-bit FNV-1a hash of the full path of the
+/// struct, e.g. `my_crate::note::Note`, so that values of different types
+/// with the same fields are absorbed differently, even if the types share
+/// their name. Since the path changes when the struct is moved or renamed,
+/// protocols that need a stable tag should give it with the
+/// `#[absorbable(tag = "...")]` attribute, whose string is hashed instead.
+/// Fields of the element type are absorbed as one element, all other fields
+/// need to implement `Absorbable` themselves.
+#[proc_macro_derive(Absorbable, attributes(absorbable))]
+pub fn derive_absorbable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match absorbable(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn absorbable(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let (element, tag) = attributes(&input)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "Absorbable can only be derived for structs",
+            ))
+        }
+    };
+
+    let element_str = quote!(#element).to_string();
+    let mut lens = Vec::new();
+    let mut pushes = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(i);
+                quote!(#index)
+            }
+        };
+        let ty = &field.ty;
+        if is_element(ty, &element_str) {
+            lens.push(quote!(1));
+            pushes.push(quote!(elements.push(self.#member);));
+        } else {
+            lens.push(quote! {
+                ::dusk_safe::Absorbable::<#element>::elements_len(
+                    &self.#member
+                )
+            });
+            pushes.push(quote! {
+                elements.extend(
+                    ::dusk_safe::Absorbable::<#element>::to_elements(
+                        &self.#member
+                    )
+                );
+            });
+        }
+    }
+
+    let name = &input.ident;
+    let tag = match tag {
+        Some(tag) => quote!(#tag),
+        None => quote! {
+            ::core::concat!(
+                ::core::module_path!(),
+                "::",
+                ::core::stringify!(#name)
+            )
+        },
+    };
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::dusk_safe::Absorbable<#element>
+            for #name #ty_generics #where_clause
+        {
+            type Elements = ::dusk_safe::__private::Vec<#element>;
+
+            fn elements_len(&self) -> usize {
+                1 #(+ #lens)*
+            }
+
+            fn to_elements(&self) -> Self::Elements {
+                let len = ::dusk_safe::Absorbable::<#element>::elements_len(
+                    self
+                );
+                let mut elements =
+                    ::dusk_safe::__private::Vec::with_capacity(len);
+                const TYPE_TAG: u64 =
+                    ::dusk_safe::__private::fnv1a(#tag.as_bytes());
+                elements.push(<#element as ::core::convert::From<u64>>::from(
+                    TYPE_TAG
+                ));
+                #(#pushes)*
+                elements
+            }
+        }
+    })
+}
+
+/// Parses the element type from the `#[absorbable(element = T)]` attribute,
+/// and the type tag from the optional `#[absorbable(tag = "...")]` attribute.
+fn attributes(input: &DeriveInput) -> syn::Result<(Type, Option<LitStr>)> {
+    let mut element = None;
+    let mut tag = None;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("absorbable") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("element") {
+                element = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported absorbable attribute"))
+            }
+        })?;
+    }
+    let element = element.ok_or_else(|| {
+        syn::Error::new(
+            input.ident.span(),
+            "missing `#[absorbable(element = T)]` attribute",
+        )
+    })?;
+    Ok((element, tag))
+}
+
+/// Checks whether the type of a field is the element type.
+fn is_element(ty: &Type, element: &str) -> bool {
+    quote!(#ty).to_string() == element
+}
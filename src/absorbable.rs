@@ -0,0 +1,60 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Trait for composite types that know how to be absorbed into a sponge with
+/// elements of type `T`.
+///
+/// Implementing this trait once for a type, e.g. a note or the public inputs
+/// of a circuit, makes sure that every call site absorbs its fields in the
+/// same order, see [`Sponge::absorb_value`](crate::Sponge::absorb_value).
+pub trait Absorbable<T> {
+    /// The collection of elements the value is flattened into.
+    type Elements: IntoIterator<Item = T>;
+
+    /// Returns the amount of elements that [`Self::to_elements`] yields.
+    ///
+    /// This is the length of the call to absorb in the IO-pattern and needs
+    /// to be exact.
+    fn elements_len(&self) -> usize;
+
+    /// Flattens the value into the elements to absorb, in the order in which
+    /// they are absorbed.
+    fn to_elements(&self) -> Self::Elements;
+}
+
+impl<T, const N: usize> Absorbable<T> for [T; N]
+where
+    T: Copy,
+{
+    type Elements = [T; N];
+
+    fn elements_len(&self) -> usize {
+        N
+    }
+
+    fn to_elements(&self) -> Self::Elements {
+        *self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Absorbable<T> for Vec<T>
+where
+    T: Copy,
+{
+    type Elements = Vec<T>;
+
+    fn elements_len(&self) -> usize {
+        self.len()
+    }
+
+    fn to_elements(&self) -> Self::Elements {
+        self.clone()
+    }
+}
@@ -0,0 +1,85 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+use crate::domain::AUDIT_LOG;
+use crate::{tag_input, Call, Error, Safe, SpongeElement};
+
+/// An entry in the audit log of a sponge, recording one call to absorb,
+/// squeeze or ratchet.
+///
+/// Instead of the elements themselves, which may be secret, only a digest of
+/// them is recorded. The digest is computed with the permutation of the
+/// sponge under the crate-defined [`AUDIT_LOG`](crate::domain::AUDIT_LOG)
+/// domain-separator, so that anyone knowing the elements can reproduce it
+/// with [`audit_digest`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuditEntry<T> {
+    /// The call to absorb, squeeze or ratchet, together with its length.
+    pub call: Call,
+    /// The digest of the absorbed or squeezed elements, the default element
+    /// for ratchets, which don't have any elements.
+    pub digest: T,
+}
+
+/// Computes the digest of the elements of a call as recorded in an
+/// [`AuditEntry`].
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `elements`: The absorbed or squeezed elements, at least one.
+///
+/// # Returns
+///
+/// A result containing the digest of the elements, or an `Error` if there are
+/// no elements.
+pub fn audit_digest<S, T, const W: usize>(
+    safe: &mut S,
+    elements: impl IntoIterator<Item = T>,
+) -> Result<T, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let mut elements: Vec<T> = elements.into_iter().collect();
+    let iopattern = [Call::Absorb(elements.len()), Call::Squeeze(1)];
+    let mut state = safe.tagged_state(&tag_input(iopattern, AUDIT_LOG)?);
+
+    // absorb the elements into the rate of a fresh state, permuting whenever
+    // the rate is full, and extract the first element of the rate
+    for (i, element) in elements.iter().enumerate() {
+        let pos = i % (W - S::CAPACITY) + S::CAPACITY;
+        if i > 0 && pos == S::CAPACITY {
+            safe.permute(&mut state);
+        }
+        state[pos] = safe.add(&state[pos], element);
+    }
+    safe.permute(&mut state);
+    let digest = state[S::CAPACITY];
+
+    elements.zeroize();
+    state.zeroize();
+    Ok(digest)
+}
+
+/// Appends the entry of a call to the audit log.
+pub(crate) fn record<S, T, const W: usize>(
+    safe: &mut S,
+    log: &mut Vec<AuditEntry<T>>,
+    call: Call,
+    elements: impl IntoIterator<Item = T>,
+) -> Result<(), Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let digest = audit_digest(safe, elements)?;
+    log.push(AuditEntry { call, digest });
+    Ok(())
+}
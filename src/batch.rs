@@ -0,0 +1,70 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::{verify_cipher, Encryption, Error, Safe, SpongeElement};
+
+/// Tries to decrypt a batch of cipher-texts in parallel, and returns the
+/// result for every cipher-text, e.g. for scanning the chain for the notes
+/// addressed to a viewing key.
+///
+/// Every cipher-text is checked with [`verify_cipher`], so none of the
+/// plain-texts are materialized. The cipher-texts that decrypt can then be
+/// decrypted with [`decrypt`](crate::decrypt).
+///
+/// The cipher-texts are only verified in parallel, no work is shared between
+/// them: the keystream and the tag of every cipher-text depend on its own
+/// shared secret and nonce, so each costs a full run of the sponge, just as
+/// with [`verify_cipher`].
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits,
+///   cloned for every cipher-text.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `ciphers`: The cipher-texts to try.
+/// - `shared_secrets`: The shared secret for every cipher-text.
+/// - `nonce`: Returns the nonce for the cipher-text with the given index.
+///
+/// # Returns
+///
+/// The result of [`verify_cipher`] for every cipher-text in the order of the
+/// cipher-texts, or an `Error` if there isn't exactly one shared secret per
+/// cipher-text.
+pub fn try_decrypt_batch<E, T, C, const W: usize>(
+    safe: &E,
+    domain_sep: u64,
+    ciphers: &[C],
+    shared_secrets: &[[T; 2]],
+    nonce: impl Fn(usize) -> T + Sync,
+) -> Result<Vec<Result<(), Error>>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W> + Clone + Sync,
+    T: SpongeElement + Send + Sync,
+    C: AsRef<[T]> + Sync,
+{
+    if ciphers.len() != shared_secrets.len() {
+        return Err(Error::OutputLenMismatch);
+    }
+
+    Ok(ciphers
+        .par_iter()
+        .zip(shared_secrets)
+        .enumerate()
+        .map(|(i, (cipher, shared_secret))| {
+            verify_cipher(
+                safe.clone(),
+                domain_sep,
+                cipher,
+                shared_secret,
+                &nonce(i),
+            )
+        })
+        .collect())
+}
@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::domain::{construction_domain, CASCADE_ID, CASCADE_LEAF_ID};
+use crate::{Call, Error, Output, Safe, Sponge, SpongeElement};
+
+/// Hashes an input much wider than the rate in a 2-level cascade, as a
+/// cheaper alternative to absorbing it all at once with permutations whose
+/// cost grows with the length of a single call.
+///
+/// The input is split into chunks of `W - 1` elements, the rate of the
+/// sponge, and every chunk is hashed into one element by its own sponge
+/// under the [`CASCADE_LEAF_ID`](crate::domain::CASCADE_LEAF_ID). A final
+/// sponge under the [`CASCADE_ID`](crate::domain::CASCADE_ID) absorbs the
+/// sub-hashes in order and squeezes `output_len` elements. The layout of the
+/// cascade, the length of the input and the size of the chunks, is labeled
+/// in the IO-pattern of the final sponge and is therefore bound into its tag.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `input`: The input to hash, at least one element.
+/// - `output_len`: The amount of elements to squeeze.
+///
+/// # Returns
+///
+/// A result containing the hash output on success, or an `Error` if the
+/// domain-separator is invalid or the input is empty.
+pub fn hash_cascade<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    input: impl AsRef<[T]>,
+    output_len: usize,
+) -> Result<Output<T>, Error>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement,
+{
+    let input = input.as_ref();
+    let rate = Sponge::<S, T, W>::RATE;
+    let leaf_domain = construction_domain(CASCADE_LEAF_ID, domain_sep)?;
+
+    // hash every chunk of the rate into one element
+    let leaves = input
+        .chunks(rate)
+        .map(|chunk| {
+            let iopattern = vec![Call::Absorb(chunk.len()), Call::Squeeze(1)];
+            let mut sponge =
+                Sponge::start(safe.clone(), iopattern, leaf_domain)?;
+            sponge.absorb(chunk.len(), chunk)?;
+            sponge.squeeze(1)?;
+            Ok(sponge.finish()?[0])
+        })
+        .collect::<Result<Vec<T>, Error>>()?;
+
+    let iopattern = vec![
+        Call::Label(input.len() as u64),
+        Call::Label(rate as u64),
+        Call::Absorb(leaves.len()),
+        Call::Squeeze(output_len),
+    ];
+    let mut sponge = Sponge::start(
+        safe,
+        iopattern,
+        construction_domain(CASCADE_ID, domain_sep)?,
+    )?;
+    sponge.absorb(leaves.len(), &leaves)?;
+    sponge.squeeze(output_len)?;
+    sponge.finish()
+}
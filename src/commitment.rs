@@ -0,0 +1,207 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use crate::domain::{
+    VECTOR_COMMITMENT, VECTOR_COMMITMENT_LEAF, VECTOR_COMMITMENT_NODE,
+};
+use crate::{
+    Call, Error, MerkleOpening, Safe, SparseMerkleTree, Sponge, SpongeElement,
+};
+
+/// The IO-pattern used to hash an element with its position, and to bind the
+/// root to the length of the vector.
+const PAIR_IOPATTERN: &[Call] = &[Call::Absorb(2), Call::Squeeze(1)];
+
+/// Hashes a pair of elements with the given domain-separator.
+fn hash_pair<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    pair: [T; 2],
+) -> Result<T, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let mut sponge = Sponge::start_static(safe, PAIR_IOPATTERN, domain_sep)?;
+    sponge.absorb(2, pair)?;
+    sponge.squeeze(1)?;
+    Ok(sponge.finish()?[0])
+}
+
+/// Returns the depth of the smallest merkle tree with at least `len` leaves.
+fn depth(len: u64) -> usize {
+    (u64::BITS - len.saturating_sub(1).leading_zeros()) as usize
+}
+
+/// A commitment to a vector of elements that can be opened at any position.
+///
+/// Every element is hashed together with its position into the leaf of a
+/// merkle tree, and the root of the tree is hashed together with the length
+/// of the vector into the commitment. All hashes use the crate-defined
+/// domain-separators of the [`domain`](crate::domain) module, so that prover
+/// and verifier agree on the construction by design.
+#[derive(Debug, Clone)]
+pub struct VectorCommitment<S, T, const W: usize>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + PartialEq + From<u64>,
+{
+    len: u64,
+    tree: SparseMerkleTree<S, T, W>,
+    commitment: T,
+}
+
+impl<S, T, const W: usize> VectorCommitment<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + PartialEq + From<u64>,
+{
+    /// Commits to the given vector of elements.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `elements`: The elements to commit to.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the commitment, or an `Error` if the sponge failed.
+    pub fn new(safe: S, elements: impl AsRef<[T]>) -> Result<Self, Error> {
+        let elements = elements.as_ref();
+        let len = elements.len() as u64;
+
+        let mut tree = SparseMerkleTree::new(
+            safe.clone(),
+            VECTOR_COMMITMENT_NODE,
+            depth(len),
+        )?;
+        for (position, element) in (0u64..).zip(elements) {
+            let leaf = hash_pair(
+                safe.clone(),
+                VECTOR_COMMITMENT_LEAF,
+                [T::from(position), *element],
+            )?;
+            tree.insert(position, leaf)?;
+        }
+
+        let commitment =
+            hash_pair(safe, VECTOR_COMMITMENT, [tree.root(), T::from(len)])?;
+
+        Ok(Self {
+            len,
+            tree,
+            commitment,
+        })
+    }
+
+    /// Returns the commitment to the vector.
+    pub fn commitment(&self) -> T {
+        self.commitment
+    }
+
+    /// Returns the length of the committed vector.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the committed vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Opens the commitment at the given position.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the opening, or an `Error` if the position is
+    /// outside of the vector.
+    pub fn open(&self, position: u64) -> Result<VectorOpening<T>, Error> {
+        if position >= self.len {
+            return Err(Error::InvalidPosition);
+        }
+        Ok(VectorOpening {
+            len: self.len,
+            opening: self.tree.prove(position)?,
+        })
+    }
+}
+
+/// An opening of a [`VectorCommitment`] at one position, of size logarithmic
+/// in the length of the vector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorOpening<T> {
+    len: u64,
+    opening: MerkleOpening<T>,
+}
+
+impl<T> VectorOpening<T>
+where
+    T: SpongeElement + PartialEq + From<u64>,
+{
+    /// Returns the position of the opened element.
+    pub fn position(&self) -> u64 {
+        self.opening.position()
+    }
+
+    /// Returns the length of the committed vector.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the committed vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Verifies that `element` is stored at the opened position of the vector
+    /// with the given commitment.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `commitment`: The commitment to the vector.
+    /// - `element`: The element claimed to be stored at the opened position.
+    ///
+    /// # Returns
+    ///
+    /// A result containing `true` if the opening is valid, or an `Error` if
+    /// the sponge failed.
+    pub fn verify<S, const W: usize>(
+        &self,
+        safe: &S,
+        commitment: &T,
+        element: &T,
+    ) -> Result<bool, Error>
+    where
+        S: Safe<T, W> + Clone,
+    {
+        let position = self.opening.position();
+        if position >= self.len
+            || depth(self.len) != self.opening.siblings().len()
+        {
+            return Ok(false);
+        }
+
+        let leaf = hash_pair(
+            safe.clone(),
+            VECTOR_COMMITMENT_LEAF,
+            [T::from(position), *element],
+        )?;
+        let root = self.opening.root(
+            safe,
+            VECTOR_COMMITMENT_NODE,
+            depth(self.len),
+            &leaf,
+        )?;
+        let expected = hash_pair(
+            safe.clone(),
+            VECTOR_COMMITMENT,
+            [root, T::from(self.len)],
+        )?;
+
+        Ok(expected == *commitment)
+    }
+}
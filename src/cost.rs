@@ -0,0 +1,128 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use crate::{Call, Error, PatternSchedule};
+
+/// The cost of running a sponge on a backend, in a unit of the backend's
+/// choosing, e.g. native cycles or circuit constraints.
+///
+/// Backends implement this trait to let protocol designers compare
+/// IO-patterns with [`pattern_cost`] and [`optimize_pattern`].
+pub trait CostModel {
+    /// Returns the cost of one permutation of the state.
+    fn permutation_cost(&self) -> u64;
+
+    /// Returns the cost of absorbing or squeezing one element, excluding any
+    /// permutation. Defaults to zero.
+    fn element_cost(&self) -> u64 {
+        0
+    }
+}
+
+/// A change to an IO-pattern that lowers its cost, as suggested by
+/// [`optimize_pattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The index of the call to absorb that should be moved.
+    pub call_index: usize,
+    /// The index in the IO-pattern before which the call should be inserted,
+    /// so that it joins an earlier run of calls to absorb.
+    pub insert_at: usize,
+    /// The cost saved by moving the call.
+    pub saving: u64,
+}
+
+/// Computes the cost of running a sponge of width `W` along an IO-pattern.
+///
+/// # Parameters
+///
+/// - `iopattern`: The IO-pattern of the sponge.
+/// - `model`: The cost model of the backend.
+///
+/// # Returns
+///
+/// A result containing the cost on success, or an `Error` if the IO-pattern
+/// is invalid.
+pub fn pattern_cost<const W: usize>(
+    iopattern: impl AsRef<[Call]>,
+    model: &impl CostModel,
+) -> Result<u64, Error> {
+    let iopattern = iopattern.as_ref();
+    let permutations =
+        PatternSchedule::new::<W>(iopattern)?.permutation_count();
+    let elements: usize = iopattern
+        .iter()
+        .filter(|call| !matches!(call, Call::Label(_)))
+        .map(|call| *call.call_len())
+        .sum();
+
+    Ok(permutations as u64 * model.permutation_cost()
+        + elements as u64 * model.element_cost())
+}
+
+/// Suggests changes to an IO-pattern that lower its cost on a sponge of
+/// width `W`.
+///
+/// The suggested changes move a call to absorb that follows a call to
+/// squeeze up to the previous run of calls to absorb, e.g. for absorbing
+/// associated data before anything is squeezed. Since this changes the
+/// protocol, it is only sound when the absorbed values don't depend on the
+/// squeezed ones, which the designer of the protocol needs to confirm. Calls
+/// are never moved across a ratchet.
+///
+/// # Parameters
+///
+/// - `iopattern`: The IO-pattern of the sponge.
+/// - `model`: The cost model of the backend.
+///
+/// # Returns
+///
+/// A result containing the suggestions that save cost, each evaluated on its
+/// own, or an `Error` if the IO-pattern is invalid.
+pub fn optimize_pattern<const W: usize>(
+    iopattern: impl AsRef<[Call]>,
+    model: &impl CostModel,
+) -> Result<Vec<Suggestion>, Error> {
+    let iopattern = iopattern.as_ref();
+    let cost = pattern_cost::<W>(iopattern, model)?;
+
+    let mut suggestions = Vec::new();
+    for (call_index, call) in iopattern.iter().enumerate() {
+        if !matches!(call, Call::Absorb(_)) {
+            continue;
+        }
+        // find the end of the previous run of calls to absorb
+        let previous = iopattern[..call_index]
+            .iter()
+            .rposition(|call| matches!(call, Call::Absorb(_)));
+        let insert_at = match previous {
+            Some(previous) if previous + 1 < call_index => previous + 1,
+            _ => continue,
+        };
+        let skipped = &iopattern[insert_at..call_index];
+        if !skipped.iter().any(|call| matches!(call, Call::Squeeze(_)))
+            || skipped.iter().any(|call| matches!(call, Call::Ratchet))
+        {
+            continue;
+        }
+
+        let mut moved = iopattern.to_vec();
+        moved.remove(call_index);
+        moved.insert(insert_at, *call);
+        let moved_cost = pattern_cost::<W>(&moved, model)?;
+        if moved_cost < cost {
+            suggestions.push(Suggestion {
+                call_index,
+                insert_at,
+                saving: cost - moved_cost,
+            });
+        }
+    }
+
+    Ok(suggestions)
+}
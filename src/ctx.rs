@@ -0,0 +1,263 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use crate::sponge::tag_capacity;
+#[cfg(feature = "encryption")]
+use crate::Encryption;
+use crate::{AbsorbMode, Safe, Sponge, SpongeElement};
+
+/// A variant of [`Safe`] whose methods receive an external mutable context,
+/// for backends that can't own the resources they run on, e.g. a circuit
+/// composer, a hardware accelerator or a GPU queue.
+///
+/// A backend implementing this trait is run by a [`Sponge`] through the
+/// [`WithCtx`] adapter, which lends it the context.
+pub trait SafeCtx<T, const W: usize>
+where
+    T: SpongeElement,
+{
+    /// The external context the methods of the backend run on.
+    type Ctx: ?Sized;
+
+    /// Apply one permutation to the state, see [`Safe::permute`].
+    fn permute(&mut self, ctx: &mut Self::Ctx, state: &mut [T; W]);
+
+    /// Apply one permutation to each of the states, see
+    /// [`Safe::permute_batch`]. This defaults to calling [`Self::permute`] on
+    /// every state.
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: The external context.
+    /// - `states`: The states to permute.
+    fn permute_batch(&mut self, ctx: &mut Self::Ctx, states: &mut [[T; W]]) {
+        states.iter_mut().for_each(|state| self.permute(ctx, state));
+    }
+
+    /// Create the tag by hashing the tag input to an element of type `T`, see
+    /// [`Safe::tag`].
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: The external context.
+    /// - `input`: The domain-separator and IO-pattern encoded as a slice of
+    ///   bytes.
+    ///
+    /// # Returns
+    ///
+    /// A tag element as the hash of the input to a field element `T`.
+    fn tag(&mut self, ctx: &mut Self::Ctx, input: &[u8]) -> T;
+
+    /// Add two values of type `T` and return the result, see [`Safe::add`].
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: The external context.
+    /// - `right`: The right operand of type `T`.
+    /// - `left`: The left operand of type `T`.
+    ///
+    /// # Returns
+    ///
+    /// The result of the addition, of type `T`.
+    fn add(&mut self, ctx: &mut Self::Ctx, right: &T, left: &T) -> T;
+
+    /// Combine an element of the state with an element that is absorbed into
+    /// it, see [`Safe::combine`]. This defaults to [`Self::add`], or to
+    /// returning `element` in [`AbsorbMode::Overwrite`].
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: The external context.
+    /// - `state`: The element of the state.
+    /// - `element`: The element that is absorbed.
+    ///
+    /// # Returns
+    ///
+    /// The new element of the state, of type `T`.
+    fn combine(&mut self, ctx: &mut Self::Ctx, state: &T, element: &T) -> T {
+        match Self::ABSORB_MODE {
+            AbsorbMode::Add => self.add(ctx, state, element),
+            AbsorbMode::Overwrite => *element,
+        }
+    }
+
+    /// How [`Self::combine`] absorbs elements by default, see
+    /// [`Safe::ABSORB_MODE`].
+    const ABSORB_MODE: AbsorbMode = AbsorbMode::Add;
+
+    /// Create a state and initialize it with the tag, see
+    /// [`Safe::initialized_state`].
+    fn initialized_state(tag: T) -> [T; W] {
+        let mut state = [T::default(); W];
+        state[0] = tag;
+        state
+    }
+
+    /// The amount of elements that form the capacity, see
+    /// [`Safe::CAPACITY`].
+    const CAPACITY: usize = 1;
+
+    /// Create the initial state of a sponge from the tag input, see
+    /// [`Safe::tagged_state`].
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: The external context.
+    /// - `input`: The domain-separator and IO-pattern encoded as a slice of
+    ///   bytes.
+    ///
+    /// # Returns
+    ///
+    /// An array of type `[T; W]` representing the initialized state.
+    fn tagged_state(&mut self, ctx: &mut Self::Ctx, input: &[u8]) -> [T; W] {
+        let mut state = Self::initialized_state(self.tag(ctx, input));
+        tag_capacity(&mut state, Self::CAPACITY, input, |i| self.tag(ctx, i));
+        state
+    }
+}
+
+/// A variant of [`Encryption`] whose methods receive the external context of
+/// a [`SafeCtx`] backend.
+#[cfg(feature = "encryption")]
+pub trait EncryptionCtx<T, const W: usize>: SafeCtx<T, W>
+where
+    T: SpongeElement,
+{
+    /// Subtracts `subtrahend` from `minuend`, see [`Encryption::subtract`].
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: The external context.
+    /// - `minuend`: The value from which to subtract.
+    /// - `subtrahend`: The value to subtract.
+    ///
+    /// # Returns
+    ///
+    /// The difference between `minuend` and `subtrahend`.
+    fn subtract(
+        &mut self,
+        ctx: &mut Self::Ctx,
+        minuend: &T,
+        subtrahend: &T,
+    ) -> T;
+
+    /// Asserts equality between `lhs` and `rhs`, see
+    /// [`Encryption::is_equal`].
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: The external context.
+    /// - `lhs`: The left-hand side value for comparison.
+    /// - `rhs`: The right-hand side value for comparison.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if `lhs` is equal to `rhs`, otherwise `false`.
+    fn is_equal(&mut self, ctx: &mut Self::Ctx, lhs: &T, rhs: &T) -> bool;
+}
+
+/// Lends an external context to a [`SafeCtx`] backend, so that it
+/// implements [`Safe`] (and `Encryption`) and can run a [`Sponge`] or any
+/// construction of this crate.
+///
+/// The context stays borrowed for the lifetime of the sponge, while
+/// [`Sponge::ctx`] gives access to it in between calls, e.g. for adding
+/// constraints of the surrounding circuit.
+#[derive(Debug)]
+pub struct WithCtx<'a, S, C: ?Sized> {
+    /// The backend.
+    pub safe: S,
+    ctx: &'a mut C,
+}
+
+impl<'a, S, C: ?Sized> WithCtx<'a, S, C> {
+    /// Lends the context to the backend.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The backend.
+    /// - `ctx`: The external context the backend runs on.
+    ///
+    /// # Returns
+    ///
+    /// The backend together with its context.
+    pub fn new(safe: S, ctx: &'a mut C) -> Self {
+        Self { safe, ctx }
+    }
+
+    /// Returns the context.
+    pub fn ctx(&mut self) -> &mut C {
+        self.ctx
+    }
+}
+
+impl<S, C, T, const W: usize> Safe<T, W> for WithCtx<'_, S, C>
+where
+    S: SafeCtx<T, W, Ctx = C>,
+    C: ?Sized,
+    T: SpongeElement,
+{
+    fn permute(&mut self, state: &mut [T; W]) {
+        self.safe.permute(self.ctx, state);
+    }
+
+    fn permute_batch(&mut self, states: &mut [[T; W]]) {
+        self.safe.permute_batch(self.ctx, states);
+    }
+
+    fn tag(&mut self, input: &[u8]) -> T {
+        self.safe.tag(self.ctx, input)
+    }
+
+    fn add(&mut self, right: &T, left: &T) -> T {
+        self.safe.add(self.ctx, right, left)
+    }
+
+    fn combine(&mut self, state: &T, element: &T) -> T {
+        self.safe.combine(self.ctx, state, element)
+    }
+
+    const ABSORB_MODE: AbsorbMode = S::ABSORB_MODE;
+
+    fn initialized_state(tag: T) -> [T; W] {
+        S::initialized_state(tag)
+    }
+
+    const CAPACITY: usize = S::CAPACITY;
+
+    fn tagged_state(&mut self, input: &[u8]) -> [T; W] {
+        self.safe.tagged_state(self.ctx, input)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<S, C, T, const W: usize> Encryption<T, W> for WithCtx<'_, S, C>
+where
+    S: EncryptionCtx<T, W, Ctx = C>,
+    C: ?Sized,
+    T: SpongeElement,
+{
+    fn subtract(&mut self, minuend: &T, subtrahend: &T) -> T {
+        self.safe.subtract(self.ctx, minuend, subtrahend)
+    }
+
+    fn is_equal(&mut self, lhs: &T, rhs: &T) -> bool {
+        self.safe.is_equal(self.ctx, lhs, rhs)
+    }
+}
+
+impl<S, C, T, const W: usize> Sponge<WithCtx<'_, S, C>, T, W>
+where
+    S: SafeCtx<T, W, Ctx = C>,
+    C: ?Sized,
+    T: SpongeElement,
+{
+    /// Returns the external context of the backend, for using it in between
+    /// the calls to the sponge.
+    pub fn ctx(&mut self) -> &mut C {
+        self.safe.ctx()
+    }
+}
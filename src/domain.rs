@@ -0,0 +1,396 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Domain-separators reserved for the constructions defined by this crate.
+//!
+//! The most significant byte of a domain-separator identifies the
+//! construction it is used for. Constructions with a fixed domain-separator
+//! have the most significant byte set to `0xff`, constructions that take a
+//! domain-separator from the user encode their identifier into the most
+//! significant byte of it (see [`construction_domain`]). Users of the crate
+//! should therefore pick their own domain-separators with the most
+//! significant byte set to zero. The values are part of the public API and
+//! will not change, so that implementations of the same constructions in
+//! other languages or in circuits can reproduce the exact tags.
+
+use crate::Error;
+
+/// The most significant byte that marks a domain-separator as reserved by
+/// this crate.
+pub const RESERVED_MASK: u64 = 0xff << 56;
+
+/// Identifier of the one-padding scheme, see [`Padding::One`].
+///
+/// [`Padding::One`]: crate::Padding::One
+pub const PADDING_ONE_ID: u8 = 0x01;
+
+/// Identifier of the length-padding scheme, see [`Padding::Length`].
+///
+/// [`Padding::Length`]: crate::Padding::Length
+pub const PADDING_LENGTH_ID: u8 = 0x02;
+
+/// Identifier of the pseudo-random function, see [`prf`](fn@crate::prf).
+pub const PRF_ID: u8 = 0x03;
+
+/// Identifier of the password hashing, see
+/// [`hash_password`](crate::hash_password).
+pub const PASSWORD_HASH_ID: u8 = 0x04;
+
+/// Identifier of the extract phase of the key derivation, see
+/// [`kdf_extract`](crate::kdf_extract).
+pub const KDF_EXTRACT_ID: u8 = 0x05;
+
+/// Identifier of the expand phase of the key derivation, see
+/// [`kdf_expand`](crate::kdf_expand).
+pub const KDF_EXPAND_ID: u8 = 0x06;
+
+/// Identifier of the deterministic signing nonces, see
+/// [`signing_nonce`](crate::signing_nonce).
+pub const SIGNING_NONCE_ID: u8 = 0x07;
+
+/// Identifier of the ratcheting key schedule, see
+/// [`KeyChain`](crate::KeyChain).
+pub const KEY_CHAIN_ID: u8 = 0x08;
+
+/// Identifier of the chunks of a stream encrypted with `encrypt_stream`,
+/// except for the last one.
+pub const STREAM_ID: u8 = 0x09;
+
+/// Identifier of the last chunk of a stream encrypted with `encrypt_stream`.
+pub const STREAM_LAST_ID: u8 = 0x0a;
+
+/// Identifier of the nonces derived from a counter, see
+/// [`derive_nonce`](crate::derive_nonce).
+pub const COUNTER_NONCE_ID: u8 = 0x0c;
+
+/// Identifier of the sponges without a fixed IO-pattern, see
+/// [`Sponge::start_unbounded`](crate::Sponge::start_unbounded).
+pub const UNBOUNDED_ID: u8 = 0x0d;
+
+/// Identifier of the sub-hashes of the cascade hashing, see
+/// [`hash_cascade`](crate::hash_cascade).
+pub const CASCADE_LEAF_ID: u8 = 0x0e;
+
+/// Identifier of the final sponge of the cascade hashing, see
+/// [`hash_cascade`](crate::hash_cascade).
+pub const CASCADE_ID: u8 = 0x0f;
+
+/// Identifier of the shares of a keystream, see `keystream_share`.
+pub const KEYSTREAM_SHARE_ID: u8 = 0x10;
+
+/// Identifier of the authentication of a message encrypted under a shared
+/// keystream, see `encrypt_shared`.
+pub const SHARED_TAG_ID: u8 = 0x11;
+
+/// Identifier of the derivation of identifiers, see
+/// [`derive_id`](crate::derive_id).
+pub const DERIVE_ID_ID: u8 = 0x12;
+
+/// Identifier of the sponges in full duplex mode, see
+/// [`Sponge::start_duplex`](crate::Sponge::start_duplex).
+pub const DUPLEX_ID: u8 = 0x13;
+
+/// Identifier of the domain-separators wider than 64 bits, see
+/// [`Sponge::start_with_domain_bytes`](crate::Sponge::start_with_domain_bytes).
+pub const WIDE_DOMAIN_ID: u8 = 0x14;
+
+/// Identifier of the shares of the key authenticating a message encrypted
+/// under a shared keystream, see `tag_key_share`.
+pub const TAG_KEY_SHARE_ID: u8 = 0x15;
+
+/// Encodes the identifier of a construction into the most significant byte
+/// of a user-chosen domain-separator.
+///
+/// # Parameters
+///
+/// - `id`: The identifier of the construction.
+/// - `domain_sep`: The domain separator chosen by the user.
+///
+/// # Returns
+///
+/// The domain-separator of the construction, or an `Error` if `domain_sep`
+/// already uses any of the bits of [`RESERVED_MASK`].
+pub const fn construction_domain(
+    id: u8,
+    domain_sep: u64,
+) -> Result<u64, Error> {
+    match domain_sep & RESERVED_MASK {
+        0 => Ok(domain_sep | ((id as u64) << 56)),
+        _ => Err(Error::InvalidDomain),
+    }
+}
+
+/// Returns the reserved domain-separator with the given identifier.
+const fn reserved(id: u64) -> u64 {
+    RESERVED_MASK | id
+}
+
+/// Domain-separator for hashing an element together with its position into
+/// a leaf of a vector commitment.
+pub const VECTOR_COMMITMENT_LEAF: u64 = reserved(0x01);
+
+/// Domain-separator for compressing the nodes of the merkle tree of a vector
+/// commitment.
+pub const VECTOR_COMMITMENT_NODE: u64 = reserved(0x02);
+
+/// Domain-separator for binding the root of the merkle tree of a vector
+/// commitment to the length of the vector.
+pub const VECTOR_COMMITMENT: u64 = reserved(0x03);
+
+/// Domain-separator for encrypting a key with `wrap_key`.
+pub const KEY_WRAP: u64 = reserved(0x04);
+
+/// Domain-separator for committing to the wrapping key in `wrap_key`.
+pub const KEY_COMMITMENT: u64 = reserved(0x05);
+
+/// Domain-separator for the child sponge of
+/// [`Sponge::session_hash`](crate::Sponge::session_hash).
+pub const SESSION_HASH: u64 = reserved(0x06);
+
+/// Domain-separator for the digests of the audit log of a sponge.
+pub const AUDIT_LOG: u64 = reserved(0x07);
+
+/// Domain-separator for the child sponge of
+/// [`Sponge::finish_with_commitment`](crate::Sponge::finish_with_commitment).
+pub const TRANSCRIPT_COMMITMENT: u64 = reserved(0x08);
+
+/// Domain-separator for compressing a foreign shared secret into the two
+/// elements of the key of an encryption, see
+/// [`embed_shared_secret`](crate::embed_shared_secret).
+pub const SHARED_SECRET_EMBEDDING: u64 = reserved(0x09);
+
+/// Domain-separator for the sponges of the deterministic generator
+/// [`SpongeRng`](crate::SpongeRng).
+pub const SPONGE_RNG: u64 = reserved(0x0a);
+
+/// A domain-separator chosen by a user of the crate, with the most
+/// significant byte set to zero.
+///
+/// Large applications can organize their domain space hierarchically by
+/// deriving the domain-separators of their components from a parent with
+/// [`DomainSeparator::child`] and [`DomainSeparator::derive`] instead of
+/// keeping track of them by hand.
+///
+/// The derivation uses a fixed mixing function, so that the derived
+/// domain-separators can be computed at compile time and reproduced in other
+/// languages: starting from `h = parent`, every value `v` is mixed in as
+/// `h = mix(h ^ v)` with the finalizer of SplitMix64 as `mix`. A child mixes
+/// in `0x01` followed by its index, a derived domain mixes in `0x02`
+/// followed by every byte of its label and the length of the label. The
+/// most significant byte of the result is cleared. Since only 56 bits
+/// remain, two derived domain-separators collide with a probability of about
+/// `2^-56`, which is negligible for the amount of domains of an application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DomainSeparator(u64);
+
+impl DomainSeparator {
+    /// Creates a domain-separator, checking that the most significant byte is
+    /// zero.
+    ///
+    /// # Parameters
+    ///
+    /// - `domain_sep`: The domain separator chosen by the user.
+    ///
+    /// # Returns
+    ///
+    /// The domain-separator, or an `Error` if `domain_sep` uses any of the
+    /// bits of [`RESERVED_MASK`].
+    pub const fn new(domain_sep: u64) -> Result<Self, Error> {
+        match domain_sep & RESERVED_MASK {
+            0 => Ok(Self(domain_sep)),
+            _ => Err(Error::InvalidDomain),
+        }
+    }
+
+    /// Derives a domain-separator from a human-readable label, like
+    /// `"dusk:phoenix-note-encryption:v1"`, instead of picking a number by
+    /// hand.
+    ///
+    /// The label is hashed with SHAKE256 and the first 8 bytes of the digest,
+    /// read big endian with the most significant byte cleared, are the
+    /// domain-separator. Since it is a `const fn`, the domain-separator can
+    /// be computed at compile time, and the result can be the root of a
+    /// hierarchy of domains.
+    ///
+    /// SHAKE256 is collision-resistant, but only 56 bits of it remain: two
+    /// given labels collide with a probability of `2^-56`, and among `n`
+    /// labels a collision occurs with a probability of about `n^2 / 2^57`,
+    /// which is negligible for the labels of an application. An adversary
+    /// choosing labels, however, finds a collision after about `2^28`
+    /// evaluations of SHAKE256. Protocols accepting labels from untrusted
+    /// parties should therefore pass the label itself as domain with
+    /// [`Sponge::start_with_domain_bytes`](crate::Sponge::start_with_domain_bytes),
+    /// which never collides.
+    ///
+    /// # Parameters
+    ///
+    /// - `label`: The label of the domain.
+    ///
+    /// # Returns
+    ///
+    /// The derived domain-separator.
+    pub const fn from_label(label: &str) -> Self {
+        Self(
+            u64::from_be_bytes(shake256_prefix(label.as_bytes()))
+                & !RESERVED_MASK,
+        )
+    }
+
+    /// Returns the value of the domain-separator.
+    pub const fn get(&self) -> u64 {
+        self.0
+    }
+
+    /// Derives the domain-separator of the child with the given index.
+    ///
+    /// # Parameters
+    ///
+    /// - `index`: The index of the child.
+    ///
+    /// # Returns
+    ///
+    /// The domain-separator of the child.
+    pub const fn child(&self, index: u64) -> Self {
+        let h = mix(self.0 ^ 0x01);
+        Self(mix(h ^ index) & !RESERVED_MASK)
+    }
+
+    /// Derives the domain-separator for the given label.
+    ///
+    /// # Parameters
+    ///
+    /// - `label`: The label of the derived domain.
+    ///
+    /// # Returns
+    ///
+    /// The derived domain-separator.
+    pub const fn derive(&self, label: &[u8]) -> Self {
+        let mut h = mix(self.0 ^ 0x02);
+        let mut i = 0;
+        while i < label.len() {
+            h = mix(h ^ label[i] as u64);
+            i += 1;
+        }
+        Self(mix(h ^ label.len() as u64) & !RESERVED_MASK)
+    }
+}
+
+impl From<DomainSeparator> for u64 {
+    fn from(domain_sep: DomainSeparator) -> Self {
+        domain_sep.0
+    }
+}
+
+/// The round constants of Keccak-f\[1600\].
+const KECCAK_RC: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// The rotation offsets of Keccak-f\[1600\], indexed by `x + 5 * y`.
+const KECCAK_RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8,
+    18, 2, 61, 56, 14,
+];
+
+/// The amount of bytes absorbed by SHAKE256 at once.
+const SHAKE256_RATE: usize = 136;
+
+/// Applies Keccak-f\[1600\] to the lanes, as a `const fn` so that
+/// [`DomainSeparator::from_label`] can be evaluated at compile time.
+const fn keccak_f(mut a: [u64; 25]) -> [u64; 25] {
+    let mut round = 0;
+    while round < 24 {
+        // theta
+        let mut c = [0u64; 5];
+        let mut x = 0;
+        while x < 5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+            x += 1;
+        }
+        let mut i = 0;
+        while i < 25 {
+            a[i] ^= c[(i + 4) % 5] ^ c[(i + 1) % 5].rotate_left(1);
+            i += 1;
+        }
+
+        // rho and pi
+        let mut b = [0u64; 25];
+        let mut i = 0;
+        while i < 25 {
+            let (x, y) = (i % 5, i / 5);
+            b[y + 5 * ((2 * x + 3 * y) % 5)] = a[i].rotate_left(KECCAK_RHO[i]);
+            i += 1;
+        }
+
+        // chi
+        let mut i = 0;
+        while i < 25 {
+            let (x, y) = (i % 5, i / 5);
+            a[i] = b[i] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            i += 1;
+        }
+
+        // iota
+        a[0] ^= KECCAK_RC[round];
+        round += 1;
+    }
+    a
+}
+
+/// Returns the first 8 bytes of the SHAKE256 digest of the input.
+const fn shake256_prefix(input: &[u8]) -> [u8; 8] {
+    let mut lanes = [0u64; 25];
+    let mut i = 0;
+    // absorb the full blocks and the remaining bytes, padded with the SHAKE
+    // domain bits and pad10*1
+    while i <= input.len() {
+        let pos = i % SHAKE256_RATE;
+        let byte = match i < input.len() {
+            true => input[i],
+            false => 0x1f,
+        };
+        lanes[pos / 8] ^= (byte as u64) << (8 * (pos % 8));
+        if i == input.len() {
+            lanes[(SHAKE256_RATE - 1) / 8] ^= 0x80 << 56;
+            lanes = keccak_f(lanes);
+        } else if pos == SHAKE256_RATE - 1 {
+            lanes = keccak_f(lanes);
+        }
+        i += 1;
+    }
+    lanes[0].to_le_bytes()
+}
+
+/// The finalizer of SplitMix64, a bijection on 64-bit integers.
+const fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,131 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+use crate::domain::SHARED_SECRET_EMBEDDING;
+use crate::{Call, Error, Safe, Sponge, SpongeElement};
+
+/// The number of bytes of the encoding embedded into each element by
+/// [`embed_bytes`].
+pub const EMBED_LIMB_BYTES: usize = 7;
+
+/// Trait for elements of another field that can be absorbed into a sponge
+/// with elements of type `T`, see
+/// [`Sponge::absorb_foreign`](crate::Sponge::absorb_foreign).
+///
+/// Implementations should go through [`embed_bytes`] with the canonical
+/// encoding of the foreign element, e.g. the 32 little endian bytes of a
+/// JubJub scalar, so that every transcript embeds the same element into the
+/// same elements of `T`. With the `dusk-jubjub` feature enabled, JubJub
+/// scalars implement the trait this way.
+pub trait Embed<T> {
+    /// Embeds the value into elements of `T`.
+    fn embed(&self) -> Vec<T>;
+}
+
+impl<T, const N: usize> Embed<T> for [u8; N]
+where
+    T: From<u64>,
+{
+    fn embed(&self) -> Vec<T> {
+        embed_bytes(self)
+    }
+}
+
+#[cfg(feature = "dusk-jubjub")]
+impl<T> Embed<T> for dusk_jubjub::JubJubScalar
+where
+    T: From<u64>,
+{
+    fn embed(&self) -> Vec<T> {
+        embed_bytes(&self.to_bytes())
+    }
+}
+
+/// The canonical embedding of the encoding of a foreign element into
+/// elements of `T`.
+///
+/// The bytes are split into limbs of [`EMBED_LIMB_BYTES`] bytes, the last one
+/// padded with zeros, and every limb is read as a little endian integer.
+/// Since every limb is smaller than `2^56`, it is a distinct element of any
+/// field with more than 56 bits, including 64 bit fields such as Goldilocks,
+/// so the embedding is injective for encodings of the same length, which the
+/// IO-pattern fixes.
+///
+/// # Parameters
+///
+/// - `bytes`: The canonical encoding of the foreign element.
+///
+/// # Returns
+///
+/// The `bytes.len().div_ceil(EMBED_LIMB_BYTES)` elements embedding the bytes.
+pub fn embed_bytes<T>(bytes: &[u8]) -> Vec<T>
+where
+    T: From<u64>,
+{
+    bytes
+        .chunks(EMBED_LIMB_BYTES)
+        .map(|chunk| {
+            let mut limb = [0u8; 8];
+            limb[..chunk.len()].copy_from_slice(chunk);
+            T::from(u64::from_le_bytes(limb))
+        })
+        .collect()
+}
+
+/// Maps a shared secret from a group over another field, e.g. the point of a
+/// Diffie-Hellman exchange or a scalar, into the two elements of `T` that
+/// the encryption takes as key.
+///
+/// The secret is embedded as per [`Embed`], absorbed into a sponge under the
+/// crate-defined
+/// [`SHARED_SECRET_EMBEDDING`](crate::domain::SHARED_SECRET_EMBEDDING)
+/// domain-separator, and two elements are squeezed. Points should be passed
+/// as the canonical encoding of their coordinates, so that both parties of
+/// the exchange derive the same key.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `secret`: The foreign shared secret.
+///
+/// # Returns
+///
+/// A result containing the key for the encryption on success, or an `Error`
+/// if the secret embeds into no elements.
+pub fn embed_shared_secret<S, T, const W: usize>(
+    safe: S,
+    secret: &impl Embed<T>,
+) -> Result<[T; 2], Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let mut elements = secret.embed();
+    let key = compress_secret(safe, &elements);
+    elements.zeroize();
+    key
+}
+
+/// Compresses the embedded shared secret into two elements.
+fn compress_secret<S, T, const W: usize>(
+    safe: S,
+    elements: &[T],
+) -> Result<[T; 2], Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let len = elements.len();
+    let iopattern = vec![Call::Absorb(len), Call::Squeeze(2)];
+    let mut sponge = Sponge::start(safe, iopattern, SHARED_SECRET_EMBEDDING)?;
+    sponge.absorb(len, elements)?;
+    sponge.squeeze(2)?;
+    sponge.finish_array()
+}
@@ -4,10 +4,14 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use alloc::borrow::Cow;
+use alloc::vec;
 use alloc::vec::Vec;
 
-use crate::{Call, Error, Safe, Sponge};
-use zeroize::Zeroize;
+use crate::{
+    critical_check, tag_input, Call, Error, Safe, Sponge, SpongeElement,
+};
+use zeroize::{Zeroize, Zeroizing};
 
 /// Trait defining encryption operations along with the [`Safe`] trait,
 /// facilitating encryption using the SAFE framework.
@@ -40,24 +44,111 @@ pub trait Encryption<T, const W: usize> {
     fn is_equal(&mut self, lhs: &T, rhs: &T) -> bool;
 }
 
-/// Prepares the sponge for encryption or decryption.
+/// A cipher-text of a message with a length of `N` elements that is known at
+/// compile time, see [`encrypt_sized`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CipherText<T, const N: usize> {
+    /// The encrypted elements of the message.
+    pub payload: [T; N],
+    /// The element authenticating the message.
+    pub tag: T,
+}
+
+impl<T, const N: usize> CipherText<T, N>
+where
+    T: SpongeElement,
+{
+    /// Creates a cipher-text from the `N + 1` elements returned by
+    /// [`encrypt`].
+    ///
+    /// # Parameters
+    ///
+    /// - `cipher`: The elements of the cipher-text.
+    ///
+    /// # Returns
+    ///
+    /// The cipher-text, or `None` if `cipher` doesn't have `N + 1` elements.
+    pub fn from_slice(cipher: &[T]) -> Option<Self> {
+        if cipher.len() != N + 1 {
+            return None;
+        }
+        let mut payload = [T::default(); N];
+        payload.copy_from_slice(&cipher[..N]);
+        Some(Self {
+            payload,
+            tag: cipher[N],
+        })
+    }
+
+    /// Returns the `N + 1` elements of the cipher-text as accepted by
+    /// [`decrypt`].
+    pub fn to_vec(&self) -> Vec<T> {
+        let mut cipher = Vec::with_capacity(N + 1);
+        cipher.extend_from_slice(&self.payload);
+        cipher.push(self.tag);
+        cipher
+    }
+}
+
+/// Everything besides the message that an encryption is bound to.
+struct Binding<'a, T> {
+    shared_secret: &'a [T; 2],
+    nonce: &'a [T],
+    position: Option<[T; 2]>,
+    associated_data: &'a [&'a [T]],
+}
+
+impl<'a, T> Binding<'a, T> {
+    /// Binds the encryption to the shared secret and the nonce only.
+    fn new(shared_secret: &'a [T; 2], nonce: &'a T) -> Self {
+        Self::with_wide_nonce(shared_secret, core::slice::from_ref(nonce))
+    }
+
+    /// Binds the encryption to the shared secret and a nonce of several
+    /// elements.
+    fn with_wide_nonce(shared_secret: &'a [T; 2], nonce: &'a [T]) -> Self {
+        Self {
+            shared_secret,
+            nonce,
+            position: None,
+            associated_data: &[],
+        }
+    }
+
+    /// Returns the IO-pattern of the encryption of a message with
+    /// `message_len` elements under this binding.
+    fn io_pattern(&self, message_len: usize) -> Vec<Call> {
+        binding_io_pattern(
+            message_len,
+            self.nonce.len(),
+            self.position.is_some(),
+            self.associated_data.iter().map(|data| data.len()),
+        )
+    }
+}
+
+/// The IO-pattern of the encryption of `N` elements, as a constant so that
+/// [`encrypt_sized`] and [`decrypt_sized`] borrow it instead of allocating.
+struct SizedPattern<const N: usize>;
+
+impl<const N: usize> SizedPattern<N> {
+    const IOPATTERN: &'static [Call] = &encryption_io_pattern(N);
+}
+
+/// Prepares the sponge for encryption or decryption, absorbing the position
+/// and the segments of associated data after the nonce if there are any.
 fn prepare_sponge<E, T, const W: usize>(
     safe: E,
     domain_sep: u64,
     message_len: usize,
-    shared_secret: &[T; 2],
-    nonce: &T,
+    binding: &Binding<T>,
 ) -> Result<Sponge<E, T, W>, Error>
 where
     E: Safe<T, W> + Encryption<T, W>,
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
 {
-    // start sponge initialization
-    let mut sponge = Sponge::start(safe, io_pattern(message_len), domain_sep)?;
-
-    // absorb shared secret and nonce
-    sponge.absorb(2, shared_secret)?;
-    sponge.absorb(1, [*nonce])?;
+    let iopattern = binding.io_pattern(message_len);
+    let mut sponge = start_bound(safe, domain_sep, iopattern, binding)?;
 
     // squeeze message_len elements
     sponge.squeeze(message_len)?;
@@ -65,6 +156,78 @@ where
     Ok(sponge)
 }
 
+/// Starts the sponge for encryption or decryption and absorbs the shared
+/// secret, the nonce, the position and the segments of associated data.
+fn start_bound<E, T, const W: usize>(
+    safe: E,
+    domain_sep: u64,
+    iopattern: impl Into<Cow<'static, [Call]>>,
+    binding: &Binding<T>,
+) -> Result<Sponge<E, T, W>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let mut sponge = match iopattern.into() {
+        Cow::Borrowed(iopattern) => {
+            Sponge::start_static(safe, iopattern, domain_sep)?
+        }
+        Cow::Owned(iopattern) => Sponge::start(safe, iopattern, domain_sep)?,
+    };
+
+    // absorb shared secret, nonce, position and associated data
+    sponge.absorb(2, binding.shared_secret)?;
+    sponge.absorb(binding.nonce.len(), binding.nonce)?;
+    if let Some(position) = binding.position {
+        sponge.absorb(2, position)?;
+    }
+    for data in binding.associated_data.iter().filter(|d| !d.is_empty()) {
+        sponge.absorb(data.len(), data)?;
+    }
+
+    Ok(sponge)
+}
+
+/// Returns the IO-pattern of an encryption with a nonce of `nonce_len`
+/// elements that is bound to a position and segments of associated data with
+/// the given lengths, if any.
+///
+/// Every segment is preceded by a label with its index, so that the
+/// boundaries between the segments are part of the tag. Empty segments are
+/// only labeled.
+///
+/// An empty message is only allowed with associated data that isn't empty,
+/// in which case the key-stream and the message are omitted and the pattern
+/// ends with the tag, see [`mac`]. Otherwise the pattern of an empty message
+/// is invalid.
+fn binding_io_pattern(
+    message_len: usize,
+    nonce_len: usize,
+    position: bool,
+    ad_lens: impl ExactSizeIterator<Item = usize>,
+) -> Vec<Call> {
+    let mut iopattern = Vec::with_capacity(6 + 2 * ad_lens.len());
+    iopattern.push(Call::Absorb(2));
+    iopattern.push(Call::Absorb(nonce_len));
+    if position {
+        iopattern.push(Call::Absorb(2));
+    }
+    let mut authenticated = false;
+    for (i, len) in ad_lens.enumerate() {
+        iopattern.push(Call::Label(i as u64));
+        if len > 0 {
+            iopattern.push(Call::Absorb(len));
+            authenticated = true;
+        }
+    }
+    if message_len > 0 || !authenticated {
+        iopattern.push(Call::Squeeze(message_len));
+        iopattern.push(Call::Absorb(message_len));
+    }
+    iopattern.push(Call::Squeeze(1));
+    iopattern
+}
+
 /// Encrypts a message using a shared secret and nonce, and returns the
 /// cipher-text.
 ///
@@ -90,45 +253,184 @@ pub fn encrypt<E, T, const W: usize>(
 ) -> Result<Vec<T>, Error>
 where
     E: Safe<T, W> + Encryption<T, W>,
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
 {
     let message = message.as_ref();
-    let message_len = message.len();
-
-    let mut sponge = prepare_sponge(
+    let mut cipher = vec![T::default(); message.len() + 1];
+    let (tag, payload) =
+        cipher.split_last_mut().ok_or(Error::EncryptionFailed)?;
+    encrypt_inner(
         safe,
         domain_sep.into(),
-        message_len,
-        shared_secret,
-        nonce,
+        message,
+        payload,
+        tag,
+        &Binding::new(shared_secret, nonce),
     )?;
+    Ok(cipher)
+}
 
-    // absorb message
-    sponge.absorb(message_len, message)?;
-
-    // squeeze one last element
-    sponge.squeeze(1)?;
+/// Encrypts a message like [`encrypt`], but writes the cipher-text into a
+/// buffer provided by the caller instead of allocating one.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `message`: The message to be encrypted.
+/// - `cipher`: The buffer for the cipher-text, with exactly one element more
+///   than the message.
+/// - `shared_secret`: The shared secret key used for encryption.
+/// - `nonce`: A unique value for encryption.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the cipher-text was written into the buffer, or an
+/// `Error` if the buffer has the wrong length or the encryption failed.
+pub fn encrypt_into<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    message: impl AsRef<[T]>,
+    cipher: &mut [T],
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let (tag, payload) =
+        cipher.split_last_mut().ok_or(Error::OutputLenMismatch)?;
+    encrypt_inner(
+        safe,
+        domain_sep.into(),
+        message.as_ref(),
+        payload,
+        tag,
+        &Binding::new(shared_secret, nonce),
+    )
+}
 
-    // encryption cipher is the sponge.output with the message elements added
-    // to the first message_len elements
-    let mut cipher = Vec::from(&sponge.output[..]);
-    for i in 0..message_len {
-        cipher[i] = sponge.safe.add(&cipher[i], &message[i]);
-    }
+/// Encrypts a message bound to its position in a rollup, given by the height
+/// of the block and the index of the output within it, and returns the
+/// cipher-text.
+///
+/// The position is absorbed as two elements right after the nonce, see
+/// [`position_encryption_io_pattern`], so that circuits verifying the
+/// encryption of notes all constrain the same layout. A cipher-text only
+/// decrypts with [`decrypt_at`] at the same position.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `message`: The message to be encrypted.
+/// - `shared_secret`: The shared secret key used for encryption.
+/// - `nonce`: A unique value for encryption.
+/// - `block_height`: The height of the block containing the output.
+/// - `output_index`: The index of the output within the block.
+///
+/// # Returns
+///
+/// Returns the cipher-text as a vector of elements on success, or an `Error` if
+/// the encryption failed.
+pub fn encrypt_at<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    message: impl AsRef<[T]>,
+    shared_secret: &[T; 2],
+    nonce: &T,
+    block_height: u64,
+    output_index: u64,
+) -> Result<Vec<T>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let message = message.as_ref();
+    let position = [T::from(block_height), T::from(output_index)];
+    let mut cipher = vec![T::default(); message.len() + 1];
+    let (tag, payload) =
+        cipher.split_last_mut().ok_or(Error::EncryptionFailed)?;
+    encrypt_inner(
+        safe,
+        domain_sep.into(),
+        message,
+        payload,
+        tag,
+        &Binding {
+            position: Some(position),
+            ..Binding::new(shared_secret, nonce)
+        },
+    )?;
+    Ok(cipher)
+}
 
+fn encrypt_inner<E, T, const W: usize>(
+    safe: E,
+    domain_sep: u64,
+    message: &[T],
+    payload: &mut [T],
+    tag: &mut T,
+    binding: &Binding<T>,
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let message_len = message.len();
     // cipher must yield exactly message_len + 1 elements
-    if cipher.len() != message_len + 1 {
-        return Err(Error::EncryptionFailed);
+    if payload.len() != message_len {
+        return Err(Error::OutputLenMismatch);
     }
+    #[cfg(feature = "tracing")]
+    tracing::debug!(domain_sep, message_len, "encrypting");
+
+    let iopattern = binding.io_pattern(message_len);
+    let sponge = start_bound(safe, domain_sep, iopattern, binding)?;
+    encrypt_bound(sponge, message, payload, tag)
+}
+
+/// Encrypts the message with a sponge returned by [`start_bound`], writing
+/// the key-stream straight into the payload so that it is never stored
+/// anywhere else.
+fn encrypt_bound<E, T, const W: usize>(
+    mut sponge: Sponge<E, T, W>,
+    message: &[T],
+    payload: &mut [T],
+    tag: &mut T,
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let message_len = message.len();
+
+    // squeeze the key-stream, absorb the message and squeeze the tag
+    let mut encrypt = || {
+        if message_len > 0 {
+            sponge.squeeze_into(message_len, payload)?;
+            sponge.absorb(message_len, message)?;
+        }
+        sponge.squeeze_into(1, core::slice::from_mut(tag))?;
+
+        // encryption cipher is the key-stream with the message elements
+        // added to it
+        for i in 0..message_len {
+            payload[i] = sponge.safe.add(&payload[i], &message[i]);
+        }
+        Ok(())
+    };
 
     // finish the sponge, erase cipher upon error
-    match sponge.finish() {
+    match encrypt().and_then(|_| sponge.finish()) {
         Ok(mut output) => {
             output.zeroize();
-            Ok(cipher)
+            Ok(())
         }
         Err(e) => {
-            cipher.zeroize();
+            payload.iter_mut().for_each(Zeroize::zeroize);
+            tag.zeroize();
             Err(e)
         }
     }
@@ -148,38 +450,444 @@ where
 ///
 /// # Returns
 ///
-/// Returns the decrypted message as a vector of elements, or an `Error` if
-/// the decryption failed.
+/// Returns the decrypted message as a vector of elements that is erased from
+/// memory when dropped, or an `Error` if the decryption failed.
 pub fn decrypt<E, T, const W: usize>(
     safe: E,
     domain_sep: impl Into<u64>,
     cipher: impl AsRef<[T]>,
     shared_secret: &[T; 2],
     nonce: &T,
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let cipher = cipher.as_ref();
+    let mut message =
+        Zeroizing::new(vec![T::default(); cipher.len().saturating_sub(1)]);
+    let (tag, payload) = cipher.split_last().ok_or(Error::DecryptionFailed)?;
+    decrypt_inner(
+        safe,
+        domain_sep.into(),
+        payload,
+        tag,
+        &mut message,
+        &Binding::new(shared_secret, nonce),
+    )?;
+    Ok(message)
+}
+
+/// Decrypts a cipher-text like [`decrypt`], but writes the decrypted message
+/// into a buffer provided by the caller instead of allocating one, e.g. for
+/// trying to decrypt many cipher-texts.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `cipher`: The cipher-text to be decrypted.
+/// - `message`: The buffer for the decrypted message, with exactly one element
+///   less than the cipher-text.
+/// - `shared_secret`: The shared secret key used for decryption.
+/// - `nonce`: A unique value for decryption.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the message was decrypted into the buffer, or an
+/// `Error` if the buffer has the wrong length or the decryption failed, in
+/// which case the buffer is erased.
+pub fn decrypt_into<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    cipher: impl AsRef<[T]>,
+    message: &mut [T],
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let (tag, payload) = cipher
+        .as_ref()
+        .split_last()
+        .ok_or(Error::DecryptionFailed)?;
+    decrypt_inner(
+        safe,
+        domain_sep.into(),
+        payload,
+        tag,
+        message,
+        &Binding::new(shared_secret, nonce),
+    )
+}
+
+/// Decrypts a cipher-text bound to its position in a rollup, as created by
+/// [`encrypt_at`], and returns the decrypted message upon success.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `cipher`: The cipher-text to be decrypted.
+/// - `shared_secret`: The shared secret key used for decryption.
+/// - `nonce`: A unique value for decryption.
+/// - `block_height`: The height of the block containing the output.
+/// - `output_index`: The index of the output within the block.
+///
+/// # Returns
+///
+/// Returns the decrypted message as a vector of elements that is erased from
+/// memory when dropped, or an `Error` if the decryption failed.
+pub fn decrypt_at<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    cipher: impl AsRef<[T]>,
+    shared_secret: &[T; 2],
+    nonce: &T,
+    block_height: u64,
+    output_index: u64,
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let cipher = cipher.as_ref();
+    let position = [T::from(block_height), T::from(output_index)];
+    let mut message =
+        Zeroizing::new(vec![T::default(); cipher.len().saturating_sub(1)]);
+    let (tag, payload) = cipher.split_last().ok_or(Error::DecryptionFailed)?;
+    decrypt_inner(
+        safe,
+        domain_sep.into(),
+        payload,
+        tag,
+        &mut message,
+        &Binding {
+            position: Some(position),
+            ..Binding::new(shared_secret, nonce)
+        },
+    )?;
+    Ok(message)
+}
+
+fn decrypt_inner<E, T, const W: usize>(
+    safe: E,
+    domain_sep: u64,
+    payload: &[T],
+    tag: &T,
+    message: &mut [T],
+    binding: &Binding<T>,
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let message_len = payload.len();
+    if message.len() != message_len {
+        return Err(Error::OutputLenMismatch);
+    }
+    #[cfg(feature = "tracing")]
+    tracing::debug!(domain_sep, message_len, "decrypting");
+
+    let iopattern = binding.io_pattern(message_len);
+    let sponge = start_bound(safe, domain_sep, iopattern, binding)?;
+    decrypt_bound(sponge, payload, tag, message)
+}
+
+/// Decrypts the payload with a sponge returned by [`start_bound`], writing
+/// the key-stream straight into the message so that it is never stored
+/// anywhere else.
+fn decrypt_bound<E, T, const W: usize>(
+    mut sponge: Sponge<E, T, W>,
+    payload: &[T],
+    tag: &T,
+    message: &mut [T],
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let message_len = payload.len();
+
+    // construct the message by subtracting the key-stream from the cipher,
+    // absorb it and squeeze 1 element, erase the decrypted message upon
+    // error
+    let mut verify = || {
+        if message_len > 0 {
+            sponge.squeeze_into(message_len, message)?;
+            for i in 0..message_len {
+                message[i] = sponge.safe.subtract(&payload[i], &message[i]);
+            }
+
+            sponge.absorb(message_len, &*message)?;
+        }
+        let mut s = T::default();
+        sponge.squeeze_into(1, core::slice::from_mut(&mut s))?;
+
+        // assert that the last element of the cipher is equal to the last
+        // element squeezed
+        if !critical_check(|| sponge.safe.is_equal(&s, tag)) {
+            // failing to authenticate is expected when trial-decrypting, so
+            // it isn't worth a warning
+            #[cfg(feature = "tracing")]
+            tracing::debug!(message_len, "decryption failed");
+            sponge.zeroize();
+            return Err(Error::DecryptionFailed);
+        };
+        Ok(())
+    };
+    let verified = verify().and_then(|_| sponge.finish());
+
+    match verified {
+        Ok(mut output) => {
+            output.zeroize();
+            Ok(())
+        }
+        Err(e) => {
+            message.iter_mut().for_each(Zeroize::zeroize);
+            Err(e)
+        }
+    }
+}
+
+/// Encrypts a message like [`encrypt`], additionally binding the cipher-text
+/// to segments of associated data that are authenticated but not encrypted,
+/// e.g. the sender, the epoch and a memo.
+///
+/// Every segment is absorbed separately after the nonce, preceded by a label
+/// with its index, see [`encryption_io_pattern_with_ad`]. Splitting the
+/// associated data differently into segments therefore results in a
+/// different cipher-text, without the need to encode the segments into one
+/// unambiguous blob. Without any segments, the cipher-text is the same as the
+/// one of [`encrypt`].
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `message`: The message to be encrypted.
+/// - `associated_data`: The segments of associated data, possibly empty.
+/// - `shared_secret`: The shared secret key used for encryption.
+/// - `nonce`: A unique value for encryption.
+///
+/// # Returns
+///
+/// Returns the cipher-text as a vector of elements on success, or an `Error` if
+/// the encryption failed.
+pub fn encrypt_with_ad<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    message: impl AsRef<[T]>,
+    associated_data: &[&[T]],
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<Vec<T>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let message = message.as_ref();
+    let mut cipher = vec![T::default(); message.len() + 1];
+    let (tag, payload) =
+        cipher.split_last_mut().ok_or(Error::EncryptionFailed)?;
+    encrypt_inner(
+        safe,
+        domain_sep.into(),
+        message,
+        payload,
+        tag,
+        &Binding {
+            associated_data,
+            ..Binding::new(shared_secret, nonce)
+        },
+    )?;
+    Ok(cipher)
+}
+
+/// Decrypts a cipher-text created with [`encrypt_with_ad`], and returns the
+/// decrypted message upon success.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `cipher`: The cipher-text to be decrypted.
+/// - `associated_data`: The segments of associated data the cipher-text is
+///   bound to.
+/// - `shared_secret`: The shared secret key used for decryption.
+/// - `nonce`: A unique value for decryption.
+///
+/// # Returns
+///
+/// Returns the decrypted message as a vector of elements that is erased from
+/// memory when dropped, or an `Error` if the decryption failed.
+pub fn decrypt_with_ad<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    cipher: impl AsRef<[T]>,
+    associated_data: &[&[T]],
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let cipher = cipher.as_ref();
+    let mut message =
+        Zeroizing::new(vec![T::default(); cipher.len().saturating_sub(1)]);
+    let (tag, payload) = cipher.split_last().ok_or(Error::DecryptionFailed)?;
+    decrypt_inner(
+        safe,
+        domain_sep.into(),
+        payload,
+        tag,
+        &mut message,
+        &Binding {
+            associated_data,
+            ..Binding::new(shared_secret, nonce)
+        },
+    )?;
+    Ok(message)
+}
+
+/// Encrypts a message like [`encrypt`], but with a nonce of several elements,
+/// e.g. when a single element of a small field can't hold enough entropy for
+/// a nonce.
+///
+/// The nonce is absorbed with one call of its length, so the IO-pattern is
+/// the one of [`encryption_io_pattern`] with `Call::Absorb(nonce.len())` as
+/// its second call. With a single element, the cipher-text is the same as
+/// the one of [`encrypt`].
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `message`: The message to be encrypted.
+/// - `shared_secret`: The shared secret key used for encryption.
+/// - `nonce`: A unique value for encryption, at least one element.
+///
+/// # Returns
+///
+/// Returns the cipher-text as a vector of elements on success, or an `Error` if
+/// the nonce is empty or the encryption failed.
+pub fn encrypt_with_wide_nonce<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    message: impl AsRef<[T]>,
+    shared_secret: &[T; 2],
+    nonce: &[T],
 ) -> Result<Vec<T>, Error>
 where
     E: Safe<T, W> + Encryption<T, W>,
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
+{
+    let message = message.as_ref();
+    let mut cipher = vec![T::default(); message.len() + 1];
+    let (tag, payload) =
+        cipher.split_last_mut().ok_or(Error::EncryptionFailed)?;
+    encrypt_inner(
+        safe,
+        domain_sep.into(),
+        message,
+        payload,
+        tag,
+        &Binding::with_wide_nonce(shared_secret, nonce),
+    )?;
+    Ok(cipher)
+}
+
+/// Decrypts a cipher-text that was encrypted with
+/// [`encrypt_with_wide_nonce`], and returns the message.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `cipher`: The cipher-text to be decrypted.
+/// - `shared_secret`: The shared secret key used for decryption.
+/// - `nonce`: The nonce used for the encryption.
+///
+/// # Returns
+///
+/// Returns the message, which is erased from memory when dropped, on success,
+/// or an `Error` if the decryption failed.
+pub fn decrypt_with_wide_nonce<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    cipher: impl AsRef<[T]>,
+    shared_secret: &[T; 2],
+    nonce: &[T],
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let cipher = cipher.as_ref();
+    let mut message =
+        Zeroizing::new(vec![T::default(); cipher.len().saturating_sub(1)]);
+    let (tag, payload) = cipher.split_last().ok_or(Error::DecryptionFailed)?;
+    decrypt_inner(
+        safe,
+        domain_sep.into(),
+        payload,
+        tag,
+        &mut message,
+        &Binding::with_wide_nonce(shared_secret, nonce),
+    )?;
+    Ok(message)
+}
+
+/// Verifies that a cipher-text decrypts under the shared secret and nonce,
+/// without ever materializing the plain-text, e.g. for testing whether a
+/// cipher-text is addressed to a key.
+///
+/// The elements of the plain-text are computed and absorbed one at a time,
+/// and erased right after.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `cipher`: The cipher-text to be verified.
+/// - `shared_secret`: The shared secret key used for decryption.
+/// - `nonce`: The nonce used for the encryption.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the cipher-text decrypts, or an `Error` if it doesn't.
+pub fn verify_cipher<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    cipher: impl AsRef<[T]>,
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
 {
     let cipher = cipher.as_ref();
-    let message_len = cipher.len() - 1;
+    let message_len = match cipher.len() {
+        0 => return Err(Error::DecryptionFailed),
+        len => len - 1,
+    };
 
     let mut sponge = prepare_sponge(
         safe,
         domain_sep.into(),
         message_len,
-        shared_secret,
-        nonce,
+        &Binding::new(shared_secret, nonce),
     )?;
 
-    // construct the message by subtracting sponge.output from the cipher
-    let mut message = Vec::from(&sponge.output[..]);
-    for i in 0..message_len {
-        message[i] = sponge.safe.subtract(&cipher[i], &message[i]);
-    }
-
-    // absorb the obtained message
-    sponge.absorb(message_len, &message)?;
+    // absorb the message, subtracting the output from the cipher one element
+    // at a time
+    sponge.absorb_with(message_len, |safe, output, i| {
+        safe.subtract(&cipher[i], &output[i])
+    })?;
 
     // squeeze 1 element
     sponge.squeeze(1)?;
@@ -187,32 +895,353 @@ where
     // assert that the last element of the cipher is equal to the last element
     // of the sponge output
     let s = sponge.output[message_len];
-    if !sponge.safe.is_equal(&s, &cipher[message_len]) {
-        message.zeroize();
+    if !critical_check(|| sponge.safe.is_equal(&s, &cipher[message_len])) {
         sponge.zeroize();
         return Err(Error::DecryptionFailed);
     };
 
-    // finish sponge, erase decrypted message upon error
-    match sponge.finish() {
-        Ok(mut output) => {
-            output.zeroize();
-            Ok(message)
+    sponge.finish()?.zeroize();
+    Ok(())
+}
+
+/// Returns the public inputs of a circuit verifying an encryption with the
+/// given IO-pattern, in the order such a circuit expects them:
+/// - the elements of the cipher-text, in order,
+/// - the elements absorbed after the shared secret and before the key-stream,
+///   one slice per call to absorb in the IO-pattern: the nonce, followed by the
+///   position or the segments of associated data that aren't empty, if any,
+/// - the [`Safe::CAPACITY`] elements of the capacity of the initial state, as
+///   set by [`Safe::tagged_state`] from the domain-separator and the
+///   IO-pattern, which is the tag alone for a capacity of one element.
+///
+/// The IO-pattern is the one the cipher-text was created with, i.e.
+/// [`encryption_io_pattern`] for [`encrypt`] and [`encrypt_sized`],
+/// [`position_encryption_io_pattern`] for [`encrypt_at`],
+/// [`encryption_io_pattern_with_ad`] for [`encrypt_with_ad`] and [`mac`], and
+/// the one of [`encryption_io_pattern`] with `Call::Absorb(nonce.len())` as
+/// its second call for [`encrypt_with_wide_nonce`].
+///
+/// The circuit is expected to start the rate of the state from the default
+/// value of `T`, like [`Safe::initialized_state`] does.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator used for the encryption.
+/// - `iopattern`: The IO-pattern used for the encryption.
+/// - `cipher`: The cipher-text.
+/// - `absorbed`: The elements absorbed after the shared secret, starting with
+///   the nonce.
+///
+/// # Returns
+///
+/// The public inputs, or an `Error` if the cipher-text and the absorbed
+/// elements don't match the IO-pattern of an encryption.
+pub fn encryption_public_inputs<E, T, const W: usize>(
+    mut safe: E,
+    domain_sep: impl Into<u64>,
+    iopattern: impl AsRef<[Call]>,
+    cipher: impl AsRef<[T]>,
+    absorbed: &[&[T]],
+) -> Result<Vec<T>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let iopattern = iopattern.as_ref();
+    let cipher = cipher.as_ref();
+    let message_len = match cipher.len() {
+        0 => return Err(Error::InvalidInput),
+        len => len - 1,
+    };
+
+    // the shared secret, the absorbed elements and the encryption of the
+    // message, labels aside
+    let mut calls = iopattern
+        .iter()
+        .filter(|call| !matches!(call, Call::Label(_)))
+        .peekable();
+    if calls.next() != Some(&Call::Absorb(2)) {
+        return Err(Error::InvalidInput);
+    }
+    let mut absorbed_len = 0;
+    while let Some(Call::Absorb(len)) = calls.peek() {
+        match absorbed.get(absorbed_len) {
+            Some(elements) if elements.len() == *len => absorbed_len += 1,
+            _ => return Err(Error::InvalidInput),
         }
-        Err(e) => {
-            message.zeroize();
-            Err(e)
+        calls.next();
+    }
+    let encryption = match message_len {
+        0 => calls.eq([Call::Squeeze(1)].iter()),
+        len => {
+            calls.eq([Call::Squeeze(len), Call::Absorb(len), Call::Squeeze(1)]
+                .iter())
         }
+    };
+    if absorbed_len == 0 || absorbed_len != absorbed.len() || !encryption {
+        return Err(Error::InvalidInput);
     }
+
+    let state = safe.tagged_state(&tag_input(iopattern, domain_sep.into())?);
+    let capacity = &state[..<E as Safe<T, W>>::CAPACITY];
+
+    let absorbed = absorbed.iter().flat_map(|elements| elements.iter());
+    let mut public_inputs = Vec::with_capacity(
+        cipher.len() + absorbed.clone().count() + capacity.len(),
+    );
+    public_inputs.extend_from_slice(cipher);
+    public_inputs.extend(absorbed);
+    public_inputs.extend_from_slice(capacity);
+    Ok(public_inputs)
+}
+
+/// Encrypts a message of `N` elements like [`encrypt`], and returns the
+/// cipher-text with its length in the type.
+///
+/// The IO-pattern is a constant for every `N` and the key-stream is written
+/// straight into the cipher-text, so that the encryption never allocates.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `message`: The message to be encrypted.
+/// - `shared_secret`: The shared secret key used for encryption.
+/// - `nonce`: A unique value for encryption.
+///
+/// # Returns
+///
+/// Returns the cipher-text on success, or an `Error` if the encryption
+/// failed.
+pub fn encrypt_sized<E, T, const W: usize, const N: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    message: &[T; N],
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<CipherText<T, N>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let mut cipher = CipherText {
+        payload: [T::default(); N],
+        tag: T::default(),
+    };
+    let sponge = start_bound(
+        safe,
+        domain_sep.into(),
+        SizedPattern::<N>::IOPATTERN,
+        &Binding::new(shared_secret, nonce),
+    )?;
+    encrypt_bound(sponge, message, &mut cipher.payload, &mut cipher.tag)?;
+    Ok(cipher)
+}
+
+/// Decrypts a cipher-text created with [`encrypt_sized`], and returns the
+/// message of `N` elements upon success.
+///
+/// Like the encryption, the decryption never allocates.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `cipher`: The cipher-text to be decrypted.
+/// - `shared_secret`: The shared secret key used for decryption.
+/// - `nonce`: A unique value for decryption.
+///
+/// # Returns
+///
+/// Returns the decrypted message that is erased from memory when dropped, or
+/// an `Error` if the decryption failed.
+pub fn decrypt_sized<E, T, const W: usize, const N: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    cipher: &CipherText<T, N>,
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<Zeroizing<[T; N]>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let mut message = Zeroizing::new([T::default(); N]);
+    let sponge = start_bound(
+        safe,
+        domain_sep.into(),
+        SizedPattern::<N>::IOPATTERN,
+        &Binding::new(shared_secret, nonce),
+    )?;
+    decrypt_bound(sponge, &cipher.payload, &cipher.tag, &mut message[..])?;
+    Ok(message)
+}
+
+/// Authenticates segments of associated data without encrypting anything,
+/// and returns the tag.
+///
+/// This is [`encrypt_with_ad`] of an empty message, whose cipher-text is
+/// only the tag. The IO-pattern is the one of
+/// [`encryption_io_pattern_with_ad`] with a `message_len` of zero, which
+/// omits the key-stream and the message. This allows protocols to alternate
+/// between records that are encrypted and records that are only
+/// authenticated under the same key and nonce schedule, and circuits to
+/// verify both with the same gadget. Since the IO-pattern is part of the
+/// tag input, a tag differs from the last element of a cipher-text under the
+/// same key and nonce with overwhelming probability.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `associated_data`: The segments of data to authenticate, at least one of
+///   them not empty.
+/// - `shared_secret`: The shared secret key used for authentication.
+/// - `nonce`: A unique value for authentication.
+///
+/// # Returns
+///
+/// Returns the tag on success, or an `Error` if all of the associated data
+/// is empty.
+pub fn mac<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    associated_data: &[&[T]],
+    shared_secret: &[T; 2],
+    nonce: &T,
+) -> Result<T, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let mut tag = T::default();
+    encrypt_inner(
+        safe,
+        domain_sep.into(),
+        &[],
+        &mut [],
+        &mut tag,
+        &Binding {
+            associated_data,
+            ..Binding::new(shared_secret, nonce)
+        },
+    )?;
+    Ok(tag)
+}
+
+/// Verifies a tag created with [`mac`] over the segments of associated data.
+///
+/// This is [`decrypt_with_ad`] of a cipher-text that is only the tag.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator to be used for the tag input.
+/// - `associated_data`: The authenticated segments of data.
+/// - `shared_secret`: The shared secret key used for authentication.
+/// - `nonce`: The nonce used for authentication.
+/// - `tag`: The tag to verify.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the tag is valid, or an `Error` if it isn't, in which
+/// case the error is [`Error::DecryptionFailed`].
+pub fn verify_mac<E, T, const W: usize>(
+    safe: E,
+    domain_sep: impl Into<u64>,
+    associated_data: &[&[T]],
+    shared_secret: &[T; 2],
+    nonce: &T,
+    tag: &T,
+) -> Result<(), Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    decrypt_inner(
+        safe,
+        domain_sep.into(),
+        &[],
+        tag,
+        &mut [],
+        &Binding {
+            associated_data,
+            ..Binding::new(shared_secret, nonce)
+        },
+    )
+}
+
+/// Defines the input-output pattern for the encryption and decryption of a
+/// message with `message_len` elements.
+///
+/// Together with the domain-separator, this pattern defines the tag of the
+/// sponge used by [`encrypt`] and [`decrypt`], so circuits verifying an
+/// encryption need to follow the exact same pattern.
+///
+/// # Parameters
+///
+/// - `message_len`: The amount of elements of the message.
+///
+/// # Returns
+///
+/// The IO-pattern used for the encryption and decryption.
+pub const fn encryption_io_pattern(message_len: usize) -> [Call; 5] {
+    [
+        Call::Absorb(2),
+        Call::Absorb(1),
+        Call::Squeeze(message_len),
+        Call::Absorb(message_len),
+        Call::Squeeze(1),
+    ]
 }
 
-/// Defines the input-output pattern for the encryption and decryption.
-const fn io_pattern(message_len: usize) -> [Call; 5] {
+/// Defines the input-output pattern for the encryption and decryption of a
+/// message with `message_len` elements at a position in a rollup, see
+/// [`encrypt_at`].
+///
+/// The block height and the output index are absorbed as two elements right
+/// after the nonce, in this order.
+///
+/// # Parameters
+///
+/// - `message_len`: The amount of elements of the message.
+///
+/// # Returns
+///
+/// The IO-pattern used for the encryption and decryption at a position.
+pub const fn position_encryption_io_pattern(message_len: usize) -> [Call; 6] {
     [
         Call::Absorb(2),
         Call::Absorb(1),
+        Call::Absorb(2),
         Call::Squeeze(message_len),
         Call::Absorb(message_len),
         Call::Squeeze(1),
     ]
 }
+
+/// Defines the input-output pattern for the encryption and decryption of a
+/// message with `message_len` elements that is bound to segments of
+/// associated data with the given lengths, see [`encrypt_with_ad`].
+///
+/// After the nonce, every segment is labeled with its index and absorbed
+/// with its own call, empty segments are only labeled. With a `message_len`
+/// of zero and at least one segment that isn't empty, the key-stream and the
+/// message are omitted, which is the pattern of the [`mac`].
+///
+/// # Parameters
+///
+/// - `message_len`: The amount of elements of the message.
+/// - `ad_lens`: The amount of elements of every segment of associated data.
+///
+/// # Returns
+///
+/// The IO-pattern used for the encryption and decryption with associated
+/// data.
+pub fn encryption_io_pattern_with_ad(
+    message_len: usize,
+    ad_lens: &[usize],
+) -> Vec<Call> {
+    binding_io_pattern(message_len, 1, false, ad_lens.iter().copied())
+}
@@ -4,8 +4,18 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+#[cfg(feature = "ufmt")]
+use ufmt_write::uWrite;
+
+#[cfg(feature = "error-hook")]
+use crate::Call;
+
 /// Defines all possible error variants for the SAFE library.
+///
+/// More variants may be added in the future, so matches on an `Error` outside
+/// of this crate need a wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
     /// This error occurs when the expected IO-pattern sequence wasn't followed
     /// during the usage of the sponge algorithm.
@@ -16,15 +26,291 @@ pub enum Error {
     /// - It doesn't start with a call to squeeze
     /// - It doesn't end with a call to absorb
     /// - Every call to absorb or squeeze has a length between 0 < len < 2^31
+    /// - Every run of contiguous calls to absorb or squeeze has a total length
+    ///   below 2^31
     InvalidIOPattern,
 
+    /// This error occurs when the domain-separator uses bits that are
+    /// reserved by the crate, e.g. for encoding the padding scheme.
+    InvalidDomain,
+
     /// This error occurs when the input elements provided to the
-    /// [`Sponge::absorb`] are less than the amount that should be absorbed.
+    /// [`Sponge::absorb`](crate::Sponge::absorb) are less than the amount that
+    /// should be absorbed.
     TooFewInputElements,
 
+    /// This error occurs when the amount of elements squeezed by the sponge
+    /// doesn't match the amount of elements requested from its output, or
+    /// when a buffer for the output has the wrong length.
+    OutputLenMismatch,
+
+    /// This error occurs when a position lies outside of a merkle tree.
+    InvalidPosition,
+
+    /// This error occurs when a merkle tree is created with a depth that
+    /// exceeds the maximal depth of 64.
+    InvalidDepth,
+
+    /// This error occurs when deserializing bytes that are not a valid
+    /// encoding.
+    InvalidEncoding,
+
+    /// This error occurs when the capacity of the sponge is too small for the
+    /// requested [`SecurityLevel`](crate::SecurityLevel).
+    InsufficientCapacity,
+
     /// This error indicates a failure during the encryption process.
     EncryptionFailed,
 
     /// This error indicates a failure during the decryption process.
     DecryptionFailed,
+
+    /// This error occurs when a backend doesn't pass the
+    /// [`self_test`](fn@crate::self_test).
+    SelfTestFailed,
+
+    /// This error occurs when the size of the field elements passed to a
+    /// [`SecurityLevel`](crate::SecurityLevel) is zero.
+    InvalidFieldSize,
+
+    /// This error occurs when the input to a function is malformed, e.g. an
+    /// empty set of shares.
+    InvalidInput,
+}
+
+impl Error {
+    /// All error variants, in the order of their codes.
+    const ALL: [Error; 14] = [
+        Error::IOPatternViolation,
+        Error::InvalidIOPattern,
+        Error::InvalidDomain,
+        Error::TooFewInputElements,
+        Error::OutputLenMismatch,
+        Error::InvalidPosition,
+        Error::InvalidDepth,
+        Error::InvalidEncoding,
+        Error::InsufficientCapacity,
+        Error::EncryptionFailed,
+        Error::DecryptionFailed,
+        Error::SelfTestFailed,
+        Error::InvalidFieldSize,
+        Error::InvalidInput,
+    ];
+
+    /// Returns the stable code of the error, e.g. for passing it across an
+    /// FFI boundary.
+    ///
+    /// The codes are part of the public API and will not change, new variants
+    /// get new codes. Zero is never used, so that it can signal success.
+    ///
+    /// # Returns
+    ///
+    /// The code of the error.
+    pub const fn code(&self) -> i32 {
+        match self {
+            Error::IOPatternViolation => 1,
+            Error::InvalidIOPattern => 2,
+            Error::InvalidDomain => 3,
+            Error::TooFewInputElements => 4,
+            Error::OutputLenMismatch => 5,
+            Error::InvalidPosition => 6,
+            Error::InvalidDepth => 7,
+            Error::InvalidEncoding => 8,
+            Error::InsufficientCapacity => 9,
+            Error::EncryptionFailed => 10,
+            Error::DecryptionFailed => 11,
+            Error::SelfTestFailed => 12,
+            Error::InvalidFieldSize => 13,
+            Error::InvalidInput => 14,
+        }
+    }
+
+    /// Returns a short description of the error, which needs no formatting
+    /// machinery to be rendered, e.g. on constrained `no_std` targets.
+    pub const fn message(&self) -> &'static str {
+        match self {
+            Error::IOPatternViolation => "the IO-pattern wasn't followed",
+            Error::InvalidIOPattern => "invalid IO-pattern",
+            Error::InvalidDomain => "the domain-separator uses reserved bits",
+            Error::TooFewInputElements => "too few input elements",
+            Error::OutputLenMismatch => "the output length doesn't match",
+            Error::InvalidPosition => "the position lies outside of the tree",
+            Error::InvalidDepth => "the depth of the tree exceeds 64",
+            Error::InvalidEncoding => "invalid encoding",
+            Error::InsufficientCapacity => "insufficient capacity",
+            Error::EncryptionFailed => "encryption failed",
+            Error::DecryptionFailed => "decryption failed",
+            Error::SelfTestFailed => "the backend failed the self-test",
+            Error::InvalidFieldSize => "the size of the field elements is zero",
+            Error::InvalidInput => "invalid input",
+        }
+    }
+
+    /// Writes the [`Error::message`] into a `ufmt` writer.
+    ///
+    /// # Parameters
+    ///
+    /// - `writer`: The destination of the message.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the writer accepted the message.
+    #[cfg(feature = "ufmt")]
+    pub fn write_to<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        writer.write_str(self.message())
+    }
+
+    /// Returns the error with the given code, see [`Error::code`].
+    ///
+    /// # Parameters
+    ///
+    /// - `code`: The code of the error.
+    ///
+    /// # Returns
+    ///
+    /// The error with the given code, or `None` if no error has this code.
+    pub const fn from_code(code: i32) -> Option<Self> {
+        match code >= 1 && code as usize <= Self::ALL.len() {
+            true => Some(Self::ALL[code as usize - 1]),
+            false => None,
+        }
+    }
+}
+
+/// The context of an error that made a [`Sponge`](crate::Sponge) erase its
+/// state, as passed to the hook set with [`set_error_hook`].
+#[cfg(feature = "error-hook")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorContext {
+    /// The error that occurred.
+    pub error: Error,
+    /// The call to absorb or squeeze that failed, or `None` if the sponge
+    /// failed when finishing.
+    pub call: Option<Call>,
+    /// The call that was expected next as per the IO-pattern, if any.
+    pub expected: Option<Call>,
+    /// The amount of calls of the IO-pattern that succeeded before the error.
+    pub io_count: usize,
+    /// The domain-separator of the sponge.
+    pub domain_sep: u64,
+}
+
+#[cfg(all(feature = "error-hook", feature = "ufmt"))]
+impl ErrorContext {
+    /// Writes a description of the error and where it occurred into a `ufmt`
+    /// writer.
+    ///
+    /// # Parameters
+    ///
+    /// - `writer`: The destination of the description.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the writer accepted the description.
+    pub fn write_to<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        self.error.write_to(writer)?;
+        writer.write_str(" after ")?;
+        write_decimal(writer, self.io_count as u64)?;
+        writer.write_str(" calls in domain ")?;
+        write_decimal(writer, self.domain_sep)
+    }
+}
+
+/// Writes an integer in decimal into a `ufmt` writer.
+#[cfg(feature = "ufmt")]
+pub(crate) fn write_decimal<W>(writer: &mut W, n: u64) -> Result<(), W::Error>
+where
+    W: uWrite + ?Sized,
+{
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    let mut n = n;
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    for digit in &digits[start..] {
+        writer.write_char(*digit as char)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "error-hook")]
+static ERROR_HOOK: std::sync::RwLock<Option<fn(&ErrorContext)>> =
+    std::sync::RwLock::new(None);
+
+/// Sets a global hook that is called whenever a [`Sponge`](crate::Sponge)
+/// erases its state because of an error, e.g. for emitting metrics or alerts
+/// on IO-pattern violations. Passing `None` removes the hook.
+///
+/// The hook is called for the sponges of all constructions of this crate as
+/// well, and may be called from several threads at once.
+#[cfg(feature = "error-hook")]
+pub fn set_error_hook(hook: Option<fn(&ErrorContext)>) {
+    let mut error_hook = match ERROR_HOOK.write() {
+        Ok(error_hook) => error_hook,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    *error_hook = hook;
+}
+
+/// Reports the error to the hook, if one is set.
+#[cfg(feature = "error-hook")]
+pub(crate) fn report(context: &ErrorContext) {
+    let hook = match ERROR_HOOK.read() {
+        Ok(error_hook) => *error_hook,
+        Err(poisoned) => *poisoned.into_inner(),
+    };
+    if let Some(hook) = hook {
+        hook(context);
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            std::format!("{err:?}"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        for code in 1..=Error::ALL.len() as i32 {
+            let error = Error::from_code(code).expect("code should be valid");
+            assert_eq!(error.code(), code);
+        }
+        assert_eq!(Error::IOPatternViolation.code(), 1);
+        assert_eq!(Error::DecryptionFailed.code(), 11);
+        assert_eq!(Error::SelfTestFailed.code(), 12);
+        assert_eq!(Error::InvalidFieldSize.code(), 13);
+        assert_eq!(Error::InvalidInput.code(), 14);
+        assert_eq!(Error::from_code(0), None);
+        assert_eq!(Error::from_code(Error::ALL.len() as i32 + 1), None);
+    }
+
+    #[test]
+    fn test_error_message() {
+        for code in 1..=Error::ALL.len() as i32 {
+            let error = Error::from_code(code).expect("code should be valid");
+            assert!(!error.message().is_empty());
+        }
+        assert_eq!(Error::DecryptionFailed.message(), "decryption failed");
+    }
 }
@@ -0,0 +1,154 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{pattern_digest, Call, Error, Safe, SpongeElement, TagVersion};
+
+/// The parameters of a sponge that the parties of an interactive protocol
+/// exchange before any protocol message, to confirm that they are about to
+/// run the same sponge.
+///
+/// The IO-pattern is only represented by its [`pattern_digest`], so the
+/// handshake stays small no matter the length of the pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Handshake<T> {
+    /// The digest of the IO-pattern and the domain-separator.
+    pub pattern_digest: T,
+    /// The domain-separator of the sponge.
+    pub domain_sep: u64,
+    /// The version of the encoding of the tag input.
+    pub version: TagVersion,
+}
+
+/// The part of a [`Handshake`] in which two parties disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The parties use different versions of the encoding of the tag input.
+    Version,
+    /// The parties use different domain-separators.
+    Domain,
+    /// The parties use different IO-patterns.
+    Pattern,
+}
+
+impl<T> Handshake<T> {
+    /// Creates the handshake for a sponge.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern of the sponge.
+    /// - `domain_sep`: The domain-separator of the sponge.
+    /// - `version`: The version of the encoding of the tag input.
+    ///
+    /// # Returns
+    ///
+    /// The handshake, or an `Error` if the IO-pattern is not valid.
+    pub fn new<S, const W: usize>(
+        safe: S,
+        iopattern: impl AsRef<[Call]>,
+        domain_sep: u64,
+        version: TagVersion,
+    ) -> Result<Self, Error>
+    where
+        S: Safe<T, W>,
+        T: SpongeElement,
+    {
+        Ok(Self {
+            pattern_digest: pattern_digest(safe, iopattern, domain_sep)?,
+            domain_sep,
+            version,
+        })
+    }
+
+    /// Checks the handshake of a peer against this one.
+    ///
+    /// The version is checked first and the domain-separator second, so that
+    /// a mismatch of the IO-pattern is only reported when the parties agree
+    /// on everything else. Since the digest of the IO-pattern also depends on
+    /// the domain-separator, it would differ for different domains too.
+    ///
+    /// # Parameters
+    ///
+    /// - `peer`: The handshake received from the peer.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if both handshakes agree, or the
+    /// [`Mismatch`] otherwise.
+    pub fn check(&self, peer: &Self) -> Result<(), Mismatch>
+    where
+        T: PartialEq,
+    {
+        if self.version != peer.version {
+            return Err(Mismatch::Version);
+        }
+        if self.domain_sep != peer.domain_sep {
+            return Err(Mismatch::Domain);
+        }
+        if self.pattern_digest != peer.pattern_digest {
+            return Err(Mismatch::Pattern);
+        }
+        Ok(())
+    }
+
+    /// Serializes the handshake into `9 + N` bytes: the version byte and the
+    /// domain-separator as 8 bytes little endian, followed by the encoded
+    /// digest of the IO-pattern.
+    ///
+    /// # Parameters
+    ///
+    /// - `encode`: Serializes an element into `N` bytes.
+    ///
+    /// # Returns
+    ///
+    /// The serialized handshake.
+    #[cfg(feature = "alloc")]
+    pub fn to_bytes<const N: usize>(
+        &self,
+        encode: impl Fn(&T) -> [u8; N],
+    ) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + N);
+        bytes.push(self.version.to_byte());
+        bytes.extend_from_slice(&self.domain_sep.to_le_bytes());
+        bytes.extend_from_slice(&encode(&self.pattern_digest));
+        bytes
+    }
+
+    /// Deserializes a handshake serialized with [`Handshake::to_bytes`].
+    ///
+    /// # Parameters
+    ///
+    /// - `bytes`: The serialized handshake.
+    /// - `decode`: Deserializes `N` bytes into an element.
+    ///
+    /// # Returns
+    ///
+    /// The handshake, or an `Error` if the bytes are not a valid encoding.
+    pub fn from_bytes<const N: usize>(
+        bytes: &[u8],
+        decode: impl Fn(&[u8; N]) -> Option<T>,
+    ) -> Result<Self, Error> {
+        if bytes.len() != 9 + N {
+            return Err(Error::InvalidEncoding);
+        }
+        let version =
+            TagVersion::from_byte(bytes[0]).ok_or(Error::InvalidEncoding)?;
+        let mut domain_sep = [0u8; 8];
+        domain_sep.copy_from_slice(&bytes[1..9]);
+        let mut pattern_digest = [0u8; N];
+        pattern_digest.copy_from_slice(&bytes[9..]);
+
+        Ok(Self {
+            pattern_digest: decode(&pattern_digest)
+                .ok_or(Error::InvalidEncoding)?,
+            domain_sep: u64::from_le_bytes(domain_sep),
+            version,
+        })
+    }
+}
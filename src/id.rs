@@ -0,0 +1,52 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use crate::domain::{construction_domain, DERIVE_ID_ID};
+use crate::{Absorbable, Call, Error, Safe, Sponge, SpongeElement};
+
+/// Derives a stable identifier from structured inputs, e.g. the identifier of
+/// a note, the preimage of a nullifier or a cache key, so that every service
+/// derives it with the same hashing convention.
+///
+/// The sponge absorbs the elements of the inputs in a single call and
+/// squeezes one element, following the IO-pattern
+/// `[Call::Absorb(inputs.elements_len()), Call::Squeeze(1)]`. The
+/// construction is bound to the
+/// [`DERIVE_ID_ID`](crate::domain::DERIVE_ID_ID) in the most significant
+/// byte of the domain-separator.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `inputs`: The inputs to derive the identifier from, at least one element.
+///
+/// # Returns
+///
+/// A result containing the identifier on success, or an `Error` if the
+/// domain-separator is invalid or the inputs are empty.
+pub fn derive_id<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    inputs: &impl Absorbable<T>,
+) -> Result<T, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let iopattern = [Call::Absorb(inputs.elements_len()), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(
+        safe,
+        Vec::from(iopattern),
+        construction_domain(DERIVE_ID_ID, domain_sep)?,
+    )?;
+    sponge.absorb_value(inputs)?;
+    sponge.squeeze(1)?;
+    Ok(sponge.finish()?[0])
+}
@@ -0,0 +1,125 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::domain::{construction_domain, KDF_EXPAND_ID, KDF_EXTRACT_ID};
+use crate::{Call, Error, Output, Safe, Sponge, SpongeElement};
+
+/// Extracts a pseudo-random key from input keying material, mirroring the
+/// extract phase of HKDF.
+///
+/// The sponge absorbs the length of the salt, the salt and the input keying
+/// material, and squeezes the pseudo-random key. The construction is bound to
+/// the [`KDF_EXTRACT_ID`](crate::domain::KDF_EXTRACT_ID) in the most
+/// significant byte of the domain-separator.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `salt`: An optional, public salt, possibly empty.
+/// - `ikm`: The input keying material, at least one element.
+///
+/// # Returns
+///
+/// A result containing the pseudo-random key, which is erased from memory
+/// when dropped, on success, or an `Error` if the domain-separator or the
+/// lengths are invalid.
+pub fn kdf_extract<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    salt: impl AsRef<[T]>,
+    ikm: impl AsRef<[T]>,
+) -> Result<Zeroizing<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let salt = salt.as_ref();
+    let ikm = ikm.as_ref();
+
+    let mut iopattern = Vec::with_capacity(4);
+    iopattern.push(Call::Absorb(1));
+    if !salt.is_empty() {
+        iopattern.push(Call::Absorb(salt.len()));
+    }
+    iopattern.push(Call::Absorb(ikm.len()));
+    iopattern.push(Call::Squeeze(1));
+
+    let mut sponge = Sponge::start(
+        safe,
+        iopattern,
+        construction_domain(KDF_EXTRACT_ID, domain_sep)?,
+    )?;
+    sponge.absorb(1, [T::from(salt.len() as u64)])?;
+    if !salt.is_empty() {
+        sponge.absorb(salt.len(), salt)?;
+    }
+    sponge.absorb(ikm.len(), ikm)?;
+    sponge.squeeze(1)?;
+    let mut output = sponge.finish()?;
+    let prk = Zeroizing::new(output[0]);
+    output.zeroize();
+    Ok(prk)
+}
+
+/// Expands a pseudo-random key into `len` elements of output keying
+/// material, mirroring the expand phase of HKDF.
+///
+/// The sponge absorbs the pseudo-random key and the context information and
+/// squeezes the output. The construction is bound to the
+/// [`KDF_EXPAND_ID`](crate::domain::KDF_EXPAND_ID) in the most significant
+/// byte of the domain-separator.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `prk`: The pseudo-random key, as returned by [`kdf_extract`].
+/// - `info`: The context information, possibly empty.
+/// - `len`: The amount of elements to derive.
+///
+/// # Returns
+///
+/// A result containing the output keying material, which is erased from
+/// memory when dropped, on success, or an `Error` if the domain-separator or
+/// the lengths are invalid.
+pub fn kdf_expand<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    prk: &T,
+    info: impl AsRef<[T]>,
+    len: usize,
+) -> Result<Zeroizing<Output<T>>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let info = info.as_ref();
+
+    let mut iopattern = Vec::with_capacity(3);
+    iopattern.push(Call::Absorb(1));
+    if !info.is_empty() {
+        iopattern.push(Call::Absorb(info.len()));
+    }
+    iopattern.push(Call::Squeeze(len));
+
+    let mut sponge = Sponge::start(
+        safe,
+        iopattern,
+        construction_domain(KDF_EXPAND_ID, domain_sep)?,
+    )?;
+    sponge.absorb(1, [*prk])?;
+    if !info.is_empty() {
+        sponge.absorb(info.len(), info)?;
+    }
+    sponge.squeeze(len)?;
+    sponge.finish().map(Zeroizing::new)
+}
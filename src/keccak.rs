@@ -0,0 +1,113 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use zeroize::Zeroize;
+
+use crate::Safe;
+
+/// The amount of bytes of the Keccak-f\[1600\] state.
+const STATE_BYTES: usize = 200;
+
+/// The amount of bytes absorbed into the Keccak-f\[1600\] state at once, the
+/// same as for SHAKE256.
+const KECCAK_RATE: usize = 136;
+
+/// The amount of bytes of the capacity, the same as for SHAKE256.
+pub const KECCAK_CAPACITY: usize = STATE_BYTES - KECCAK_RATE;
+
+/// The width of the state of a [`Sponge`](crate::Sponge) running on the
+/// [`Keccak`] backend: the capacity of 64 bytes followed by the rate of 136
+/// bytes.
+pub const KECCAK_W: usize = STATE_BYTES;
+
+/// A byte-mode backend running the sponge on the Keccak-f\[1600\] permutation,
+/// with bytes as elements and XOR as addition.
+///
+/// The whole Keccak-f\[1600\] state is part of the state of the sponge, whose
+/// capacity are the first [`KECCAK_CAPACITY`] bytes. This results in a
+/// capacity of 512 bits, the same as for SHAKE256.
+///
+/// The tag input is hashed with SHAKE256 into 64 bytes, which initialize the
+/// capacity, so that the whole tag input separates the sponges. The
+/// [`Safe::tag`] is the first of these bytes.
+///
+/// The Keccak-f\[1600\] state is the rate of the sponge followed by its
+/// capacity, read into lanes as little endian 64-bit integers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keccak;
+
+impl Keccak {
+    /// Creates a new backend.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Safe<u8, KECCAK_W> for Keccak {
+    fn permute(&mut self, state: &mut [u8; KECCAK_W]) {
+        let mut bytes = [0u8; STATE_BYTES];
+        bytes[..KECCAK_RATE].copy_from_slice(&state[KECCAK_CAPACITY..]);
+        bytes[KECCAK_RATE..].copy_from_slice(&state[..KECCAK_CAPACITY]);
+        permute_bytes(&mut bytes);
+        state[KECCAK_CAPACITY..].copy_from_slice(&bytes[..KECCAK_RATE]);
+        state[..KECCAK_CAPACITY].copy_from_slice(&bytes[KECCAK_RATE..]);
+        bytes.zeroize();
+    }
+
+    fn tag(&mut self, input: &[u8]) -> u8 {
+        self.tagged_state(input)[0]
+    }
+
+    fn add(&mut self, right: &u8, left: &u8) -> u8 {
+        right ^ left
+    }
+
+    const CAPACITY: usize = KECCAK_CAPACITY;
+
+    fn tagged_state(&mut self, input: &[u8]) -> [u8; KECCAK_W] {
+        // hash the input with SHAKE256, absorbing the blocks of the rate and
+        // padding the last one with the SHAKE domain bits and pad10*1
+        let mut bytes = [0u8; STATE_BYTES];
+        let mut blocks = input.chunks_exact(KECCAK_RATE);
+        for block in &mut blocks {
+            xor_into(&mut bytes, block);
+            permute_bytes(&mut bytes);
+        }
+        let rest = blocks.remainder();
+        xor_into(&mut bytes, rest);
+        bytes[rest.len()] ^= 0x1f;
+        bytes[KECCAK_RATE - 1] ^= 0x80;
+        permute_bytes(&mut bytes);
+
+        let mut state = [0u8; KECCAK_W];
+        state[..KECCAK_CAPACITY].copy_from_slice(&bytes[..KECCAK_CAPACITY]);
+        bytes.zeroize();
+        state
+    }
+}
+
+/// Adds the bytes into the beginning of the state with XOR.
+fn xor_into(state: &mut [u8; STATE_BYTES], bytes: &[u8]) {
+    state.iter_mut().zip(bytes).for_each(|(s, b)| *s ^= b);
+}
+/// Applies Keccak-f\[1600\] to the state, read as little endian lanes.
+fn permute_bytes(state: &mut [u8; STATE_BYTES]) {
+    let mut lanes = [0u64; 25];
+    lanes
+        .iter_mut()
+        .zip(state.chunks_exact(8))
+        .for_each(|(lane, bytes)| {
+            let mut lane_bytes = [0u8; 8];
+            lane_bytes.copy_from_slice(bytes);
+            *lane = u64::from_le_bytes(lane_bytes);
+        });
+    keccak::f1600(&mut lanes);
+    lanes
+        .iter()
+        .zip(state.chunks_exact_mut(8))
+        .for_each(|(lane, bytes)| bytes.copy_from_slice(&lane.to_le_bytes()));
+    lanes.zeroize();
+}
@@ -0,0 +1,124 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::domain::{construction_domain, KEY_CHAIN_ID};
+use crate::{Call, Error, Safe, Sponge, SpongeElement};
+
+/// The IO-pattern of one step of the key chain: absorb the chain key and the
+/// epoch, squeeze the next chain key and the epoch key.
+const STEP_IOPATTERN: &[Call] = &[Call::Absorb(2), Call::Squeeze(2)];
+
+/// A ratcheting key schedule that derives one key per epoch, e.g. for
+/// rotating encryption keys in long-lived services.
+///
+/// Every call to [`KeyChain::advance`] hashes the current chain key together
+/// with the epoch into the next chain key and the key of the epoch, and
+/// erases the previous chain key. Since the sponge is one-way, a compromised
+/// chain key doesn't reveal the keys of earlier epochs (forward secrecy).
+pub struct KeyChain<S, T, const W: usize>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    safe: S,
+    domain_sep: u64,
+    chain_key: T,
+    epoch: u64,
+    _marker: PhantomData<[T; W]>,
+}
+
+impl<S, T, const W: usize> KeyChain<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    /// Creates a new key chain from an initial secret.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator chosen by the user, with the most
+    ///   significant byte set to zero.
+    /// - `initial_key`: The secret the chain starts from.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the key chain at epoch zero, or an `Error` if the
+    /// domain-separator is invalid.
+    pub fn new(
+        safe: S,
+        domain_sep: u64,
+        initial_key: T,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            safe,
+            domain_sep: construction_domain(KEY_CHAIN_ID, domain_sep)?,
+            chain_key: initial_key,
+            epoch: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Returns the epoch of the key that is returned by the next call to
+    /// [`KeyChain::advance`].
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Ratchets the chain forward and returns the key of the current epoch.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the key of the epoch, which is erased from memory
+    /// when dropped, or an `Error` if the sponge failed.
+    pub fn advance(&mut self) -> Result<Zeroizing<T>, Error> {
+        let mut sponge = Sponge::start_static(
+            self.safe.clone(),
+            STEP_IOPATTERN,
+            self.domain_sep,
+        )?;
+        sponge.absorb(2, [self.chain_key, T::from(self.epoch)])?;
+        sponge.squeeze(2)?;
+        let mut output = sponge.finish()?;
+
+        // the previous chain key is overwritten and therefore erased
+        self.chain_key = output[0];
+        let key = Zeroizing::new(output[1]);
+        output.zeroize();
+        self.epoch += 1;
+
+        Ok(key)
+    }
+}
+
+// the chain key is secret and therefore omitted
+impl<S, T, const W: usize> fmt::Debug for KeyChain<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyChain")
+            .field("domain_sep", &self.domain_sep)
+            .field("epoch", &self.epoch)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, T, const W: usize> Drop for KeyChain<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    fn drop(&mut self) {
+        self.chain_key.zeroize();
+    }
+}
@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+use crate::domain::{KEY_COMMITMENT, KEY_WRAP};
+use crate::{
+    critical_check, decrypt, encrypt, Call, Encryption, Error, Safe, Sponge,
+    SpongeElement,
+};
+
+/// The IO-pattern used to commit to the wrapping key and nonce.
+const COMMITMENT_IOPATTERN: &[Call] = &[Call::Absorb(3), Call::Squeeze(1)];
+
+/// Computes the commitment to the wrapping key and the nonce.
+fn key_commitment<E, T, const W: usize>(
+    safe: E,
+    wrapping_key: &[T; 2],
+    nonce: &T,
+) -> Result<T, Error>
+where
+    E: Safe<T, W>,
+    T: SpongeElement,
+{
+    let mut sponge =
+        Sponge::start_static(safe, COMMITMENT_IOPATTERN, KEY_COMMITMENT)?;
+    sponge.absorb(3, [wrapping_key[0], wrapping_key[1], *nonce])?;
+    sponge.squeeze(1)?;
+    Ok(sponge.finish()?[0])
+}
+
+/// Wraps a symmetric key under a wrapping key.
+///
+/// The key is encrypted with [`encrypt`] under the crate-defined
+/// [`KEY_WRAP`](crate::domain::KEY_WRAP) domain-separator, and the resulting
+/// cipher-text is prefixed with a commitment to the wrapping key and nonce.
+/// The commitment makes the wrapped key committing: it can only be unwrapped
+/// with the exact wrapping key it was created with.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `key`: The key to wrap, at least one element.
+/// - `wrapping_key`: The key used to wrap `key`.
+/// - `nonce`: A unique value for this wrapping.
+///
+/// # Returns
+///
+/// Returns the wrapped key, `key.len() + 2` elements, on success, or an
+/// `Error` if the wrapping failed.
+pub fn wrap_key<E, T, const W: usize>(
+    safe: E,
+    key: impl AsRef<[T]>,
+    wrapping_key: &[T; 2],
+    nonce: &T,
+) -> Result<Vec<T>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W> + Clone,
+    T: SpongeElement,
+{
+    let commitment = key_commitment(safe.clone(), wrapping_key, nonce)?;
+    let cipher = encrypt(safe, KEY_WRAP, key, wrapping_key, nonce)?;
+
+    let mut wrapped = Vec::with_capacity(cipher.len() + 1);
+    wrapped.push(commitment);
+    wrapped.extend(cipher);
+    Ok(wrapped)
+}
+
+/// Unwraps a key that was wrapped with [`wrap_key`].
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `wrapped`: The wrapped key.
+/// - `wrapping_key`: The key used to wrap the key.
+/// - `nonce`: The nonce used to wrap the key.
+///
+/// # Returns
+///
+/// Returns the unwrapped key, which is erased from memory when dropped, on
+/// success, or an `Error` if the wrapped key wasn't created with the given
+/// wrapping key and nonce, or was tampered with.
+pub fn unwrap_key<E, T, const W: usize>(
+    safe: E,
+    wrapped: impl AsRef<[T]>,
+    wrapping_key: &[T; 2],
+    nonce: &T,
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W> + Clone,
+    T: SpongeElement,
+{
+    let wrapped = wrapped.as_ref();
+    if wrapped.len() < 3 {
+        return Err(Error::DecryptionFailed);
+    }
+
+    // check the key commitment before attempting to decrypt
+    let mut safe = safe;
+    let commitment = key_commitment(safe.clone(), wrapping_key, nonce)?;
+    if !critical_check(|| safe.is_equal(&commitment, &wrapped[0])) {
+        return Err(Error::DecryptionFailed);
+    }
+
+    decrypt(safe, KEY_WRAP, &wrapped[1..], wrapping_key, nonce)
+}
@@ -9,19 +9,191 @@
 #![deny(missing_docs)]
 #![deny(rustdoc::broken_intra_doc_links)]
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
+pub mod domain;
+
+mod absorbable;
+#[cfg(feature = "alloc")]
+mod cascade;
+#[cfg(feature = "alloc")]
+mod cost;
+mod ctx;
+#[cfg(feature = "alloc")]
+mod embed;
 mod error;
+mod handshake;
+#[cfg(feature = "alloc")]
+mod id;
+#[cfg(feature = "alloc")]
+mod kdf;
+mod keychain;
+#[cfg(feature = "alloc")]
+mod nonce;
+mod output;
+#[cfg(feature = "alloc")]
+mod padding;
+#[cfg(feature = "alloc")]
+mod password;
+#[cfg(feature = "alloc")]
+mod prf;
+#[cfg(feature = "alloc")]
+mod rng;
+#[cfg(feature = "alloc")]
+mod schedule;
+mod security;
 mod sponge;
+mod squeeze;
+mod sync;
+mod transcript;
+#[cfg(feature = "alloc")]
+mod typed;
 
+pub use absorbable::Absorbable;
+#[cfg(feature = "alloc")]
+pub use cascade::hash_cascade;
+#[cfg(feature = "alloc")]
+pub use cost::{optimize_pattern, pattern_cost, CostModel, Suggestion};
+#[cfg(feature = "encryption")]
+pub use ctx::EncryptionCtx;
+pub use ctx::{SafeCtx, WithCtx};
+#[cfg(feature = "alloc")]
+pub use embed::{embed_bytes, embed_shared_secret, Embed, EMBED_LIMB_BYTES};
 pub use error::Error;
-pub use sponge::{Safe, Sponge};
+#[cfg(feature = "error-hook")]
+pub use error::{set_error_hook, ErrorContext};
+pub use handshake::{Handshake, Mismatch};
+#[cfg(feature = "alloc")]
+pub use id::derive_id;
+#[cfg(feature = "alloc")]
+pub use kdf::{kdf_expand, kdf_extract};
+pub use keychain::KeyChain;
+#[cfg(feature = "alloc")]
+pub use nonce::{derive_nonce, signing_nonce, Nonce};
+pub use output::{LenMismatch, Output, OutputMode};
+#[cfg(feature = "alloc")]
+pub use padding::{hash_padded, Padding, PaddingScheme};
+#[cfg(feature = "alloc")]
+pub use password::hash_password;
+#[cfg(feature = "alloc")]
+pub use prf::{prf, prf_io_pattern};
+#[cfg(feature = "alloc")]
+pub use rng::SpongeRng;
+#[cfg(feature = "alloc")]
+pub use schedule::{PatternSchedule, ScheduledPermutation};
+pub use security::SecurityLevel;
+pub use sponge::{AbsorbMode, Safe, Sponge, SpongeElement};
+pub use squeeze::{FieldWriter, SqueezeInto};
+pub use sync::SyncPoint;
+#[cfg(feature = "alloc")]
+pub use typed::{Absorbing, Finished, Squeezing, TypedPattern, TypedSponge};
+pub use zeroize;
+
+#[cfg(feature = "ufmt")]
+pub use ufmt_write;
+
+#[cfg(feature = "derive")]
+pub use dusk_safe_derive::Absorbable;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::transcript::ArrayLen;
+    #[cfg(feature = "derive")]
+    pub use alloc::vec::Vec;
+
+    /// The 64-bit FNV-1a hash of the given bytes, computing the type tags of
+    /// the `Absorbable` derive macro at compile time.
+    #[cfg(feature = "derive")]
+    pub const fn fnv1a(bytes: &[u8]) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let mut hash = OFFSET;
+        let mut i = 0;
+        while i < bytes.len() {
+            hash = (hash ^ bytes[i] as u64).wrapping_mul(PRIME);
+            i += 1;
+        }
+        hash
+    }
+}
+
+#[cfg(feature = "audit")]
+mod audit;
+#[cfg(feature = "audit")]
+pub use audit::{audit_digest, AuditEntry};
+
+#[cfg(feature = "digest")]
+mod tag;
+#[cfg(feature = "digest")]
+pub use tag::{spec_tag_from_digest, tag_from_digest, DigestTag};
+
+mod self_test;
+pub use self_test::self_test;
+#[cfg(feature = "encryption")]
+pub use self_test::self_test_encryption;
+
+#[cfg(feature = "test-utils")]
+mod snapshot;
+#[cfg(feature = "test-utils")]
+pub use snapshot::{first_divergence, Divergence, Snapshot};
+
+#[cfg(feature = "keccak")]
+mod keccak;
+#[cfg(feature = "keccak")]
+pub use self::keccak::{Keccak, KECCAK_CAPACITY, KECCAK_W};
 
 #[cfg(feature = "encryption")]
 mod encryption;
 #[cfg(feature = "encryption")]
-pub use encryption::{decrypt, encrypt, Encryption};
+pub use encryption::{
+    decrypt, decrypt_at, decrypt_into, decrypt_sized, decrypt_with_ad,
+    decrypt_with_wide_nonce, encrypt, encrypt_at, encrypt_into, encrypt_sized,
+    encrypt_with_ad, encrypt_with_wide_nonce, encryption_io_pattern,
+    encryption_io_pattern_with_ad, encryption_public_inputs, mac,
+    position_encryption_io_pattern, verify_cipher, verify_mac, CipherText,
+    Encryption,
+};
+
+#[cfg(all(feature = "parallel", feature = "encryption"))]
+mod batch;
+#[cfg(all(feature = "parallel", feature = "encryption"))]
+pub use batch::try_decrypt_batch;
+
+#[cfg(all(feature = "std", feature = "encryption"))]
+mod stream;
+#[cfg(all(feature = "std", feature = "encryption"))]
+pub use stream::{decrypt_stream, encrypt_stream, STREAM_CHUNK_LEN};
+
+#[cfg(feature = "encryption")]
+mod threshold;
+#[cfg(feature = "encryption")]
+pub use threshold::{
+    combine_keystream, combine_tag_key, decrypt_shared, encrypt_shared,
+    keystream_share, tag_key_share,
+};
+
+#[cfg(feature = "encryption")]
+mod keywrap;
+#[cfg(feature = "encryption")]
+pub use keywrap::{unwrap_key, wrap_key};
+
+#[cfg(feature = "merkle")]
+mod merkle;
+#[cfg(feature = "merkle")]
+pub use merkle::{
+    merkle_hash, merkle_hash_batch, Frontier, IncrementalMerkleTree,
+    MerkleMultiOpening, MerkleOpening, SparseMerkleTree,
+};
+
+#[cfg(feature = "merkle")]
+mod commitment;
+#[cfg(feature = "merkle")]
+pub use commitment::{VectorCommitment, VectorOpening};
 
 /// Enum to encode the calls to [`Sponge::absorb`] and [`Sponge::squeeze`] that
 /// make the IO-pattern.
@@ -29,27 +201,221 @@ pub use encryption::{decrypt, encrypt, Encryption};
 /// An implementation must forbid any further usage of the sponge and any of
 /// its internal data if this pattern is not followed. In particular, the output
 /// from any previous calls to [`Sponge::squeeze`] must not be used.
+///
+/// More kinds of calls may be added in the future, so matches on a `Call`
+/// outside of this crate need a wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum Call {
     /// Absorb the specified amount of elements into the state.
     Absorb(usize),
     /// Squeeze the specified amount of elements from the state.
     Squeeze(usize),
+    /// Label the following calls, e.g. to give two protocols with the same
+    /// calls different tags. Labels are only part of the tag, they don't
+    /// correspond to any call to the sponge, and prevent the calls around
+    /// them from being aggregated.
+    Label(u64),
+    /// Permute the state and erase its rate, so that a later compromise of
+    /// the state doesn't reveal the elements absorbed and squeezed before,
+    /// see [`Sponge::ratchet`].
+    Ratchet,
 }
 
 impl Call {
-    /// Returns the length of the call.
-    pub fn call_len(&self) -> &usize {
+    /// Returns the length of the call, which is zero for labels.
+    pub const fn call_len(&self) -> &usize {
         match self {
             Call::Absorb(len) => len,
             Call::Squeeze(len) => len,
+            Call::Label(_) | Call::Ratchet => &0,
+        }
+    }
+}
+
+/// Versions of the encoding of the tag input, see
+/// [`Sponge::start_with_version`].
+///
+/// Every version but the original one prefixes the encoding with its version
+/// byte, so that the encoding can evolve while tags of older versions remain
+/// reproducible, and implementations can agree on the version they support.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TagVersion {
+    /// The original encoding of the SAFE specification, without a version
+    /// byte.
+    #[default]
+    V0,
+    /// The original encoding, prefixed with the version byte `0x01`.
+    V1,
+}
+
+impl TagVersion {
+    /// The most recent version of the encoding.
+    pub const LATEST: Self = TagVersion::V1;
+
+    /// Returns the version byte.
+    pub const fn to_byte(&self) -> u8 {
+        match self {
+            TagVersion::V0 => 0x00,
+            TagVersion::V1 => 0x01,
+        }
+    }
+
+    /// Returns the version with the given version byte, or `None` if the
+    /// version is unknown.
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x00 => Some(TagVersion::V0),
+            0x01 => Some(TagVersion::V1),
+            _ => None,
         }
     }
 }
 
+/// The byte order in which an [`EncodingProfile`] encodes integers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// The most significant byte comes first, as in the SAFE specification.
+    #[default]
+    BigEndian,
+    /// The least significant byte comes first.
+    LittleEndian,
+}
+
+/// The layout of the encoding of the tag input, for reproducing the tags of
+/// deployments that deviate from the SAFE specification byte-for-byte, see
+/// [`Sponge::start_with_profile`].
+///
+/// The profile only changes how the 32-bit words encoding the IO-pattern and
+/// the domain-separator are laid out, not which words are encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncodingProfile {
+    /// The byte order of the words encoding the IO-pattern and of the
+    /// domain-separator.
+    pub byte_order: ByteOrder,
+    /// Whether the domain-separator is encoded before the IO-pattern instead
+    /// of after it.
+    pub domain_first: bool,
+}
+
+impl EncodingProfile {
+    /// The encoding of the SAFE specification: big endian words followed by
+    /// the domain-separator.
+    pub const SAFE: Self = Self {
+        byte_order: ByteOrder::BigEndian,
+        domain_first: false,
+    };
+}
+
+/// Hashes the encoded IO-pattern and domain-separator into an element, so
+/// that two parties can cheaply confirm that they are about to run the same
+/// pattern before exchanging any protocol messages.
+///
+/// The digest is the tag that a [`Sponge`] started with the IO-pattern and
+/// domain-separator is initialized with, so it is equal for two parties if
+/// and only if they encode the same IO-pattern under the same
+/// domain-separator, up to collisions of [`Safe::tag`].
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `iopattern`: A slice of `Call` enum representing the IO-pattern.
+/// - `domain_sep`: The domain separator to be used.
+///
+/// # Returns
+///
+/// A `Result` containing the digest on success, or an `Error` if the
+/// IO-pattern is not valid.
+pub fn pattern_digest<S, T, const W: usize>(
+    mut safe: S,
+    iopattern: impl AsRef<[Call]>,
+    domain_sep: u64,
+) -> Result<T, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    with_tag_input(
+        iopattern.as_ref(),
+        domain_sep,
+        TagVersion::V0,
+        EncodingProfile::SAFE,
+        |input| safe.tag(input),
+    )
+}
+
+/// Returns the total amount of elements squeezed by the given IO-pattern.
+///
+/// Since this is a `const fn`, it can be used to compute the length of the
+/// output of a fixed IO-pattern at compile time, e.g. for
+/// [`Sponge::finish_array`].
+///
+/// # Parameters
+///
+/// - `iopattern`: A slice of `Call` enum representing the IO-pattern.
+///
+/// # Returns
+///
+/// The sum of the lengths of all calls to squeeze in the IO-pattern.
+pub const fn squeeze_len(iopattern: &[Call]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < iopattern.len() {
+        if let Call::Squeeze(call_len) = iopattern[i] {
+            len += call_len;
+        }
+        i += 1;
+    }
+    len
+}
+
+/// Returns the normalized form of the IO-pattern, in which contiguous calls
+/// to absorb and contiguous calls to squeeze are merged into one call each,
+/// as done when encoding the tag input.
+///
+/// Labels and ratchets are kept and prevent the calls around them from being
+/// merged. Two valid IO-patterns therefore result in the same tag under the
+/// same domain-separator if and only if their normalized forms are equal.
+///
+/// # Parameters
+///
+/// - `iopattern`: A slice of `Call` enum representing the IO-pattern.
+///
+/// # Returns
+///
+/// The normalized IO-pattern.
+#[cfg(feature = "alloc")]
+pub fn aggregate(iopattern: impl AsRef<[Call]>) -> Vec<Call> {
+    let mut aggregated: Vec<Call> =
+        Vec::with_capacity(iopattern.as_ref().len());
+    for call in iopattern.as_ref() {
+        match (aggregated.last_mut(), call) {
+            (Some(Call::Absorb(prev)), Call::Absorb(len))
+            | (Some(Call::Squeeze(prev)), Call::Squeeze(len)) => {
+                *prev = prev.saturating_add(*len)
+            }
+            _ => aggregated.push(*call),
+        }
+    }
+    aggregated
+}
+
 /// Encode the input for the tag for the sponge instance, using the
 /// domain-separator and IO-pattern.
 ///
+/// This is the exact preimage that [`Safe::tag`] receives when a sponge is
+/// started with [`Sponge::start`], so that circuits and implementations in
+/// other languages can recompute the tag. The encoding is stable and will
+/// not change:
+/// - The IO-pattern is normalized as per [`aggregate`].
+/// - Every call to absorb `len` elements is encoded as the 32-bit word
+///   `0x8000_0000 + len`, every call to squeeze as the word `len`.
+/// - Every label is encoded as the word zero followed by its most and least
+///   significant 32 bits.
+/// - Every ratchet is encoded as the word `0x8000_0000`.
+/// - The words are serialized big endian, followed by the 8 bytes big endian of
+///   the domain-separator.
+///
 /// This function returns an error if the IO-pattern is not sensible.
 ///
 /// # Parameters
@@ -61,61 +427,397 @@ impl Call {
 ///
 /// A `Result` containing a vector of `u8` on success, or an `Error` if the
 /// IO-pattern is not valid.
-fn tag_input(
+#[cfg(feature = "alloc")]
+pub fn tag_input(
     iopattern: impl AsRef<[Call]>,
     domain_sep: u64,
 ) -> Result<Vec<u8>, Error> {
+    // Convert hash input to an array of u8, using big endian conversion
+    let mut input = Vec::new();
+    encode_io_pattern(iopattern.as_ref(), |word| {
+        input.extend(word.to_be_bytes())
+    })?;
+
+    // Add the domain separator to the hash input
+    input.extend(domain_sep.to_be_bytes());
+
+    Ok(input)
+}
+
+/// Encode the input for the tag for a sponge instance with a byte-string
+/// domain-separator.
+///
+/// The encoding is the one of [`tag_input`] with the domain-separator
+/// [`WIDE_DOMAIN_ID`](domain::WIDE_DOMAIN_ID) encoded into its most
+/// significant byte, with the byte-string and its length as 8 bytes big
+/// endian inserted right before the domain-separator. Since the last 8 bytes
+/// identify the encoding and the length precedes them, the encoding can't
+/// collide with the one of any other IO-pattern and domain-separator that
+/// doesn't use the reserved byte.
+///
+/// # Parameters
+///
+/// - `iopattern`: A slice of `Call` enum representing the IO-pattern.
+/// - `domain`: The byte-string domain-separator.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of `u8` on success, or an `Error` if the
+/// IO-pattern is not valid.
+#[cfg(feature = "alloc")]
+fn wide_tag_input(
+    iopattern: impl AsRef<[Call]>,
+    domain: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let domain_sep = domain::construction_domain(domain::WIDE_DOMAIN_ID, 0)?;
+    let mut input = tag_input(iopattern, domain_sep)?;
+    let domain_sep = input.split_off(input.len() - 8);
+    input.extend_from_slice(domain);
+    input.extend((domain.len() as u64).to_be_bytes());
+    input.extend(domain_sep);
+    Ok(input)
+}
+
+/// Encode the input for the tag like [`tag_input`] with the layout of the
+/// given encoding profile, but into the given buffer instead of a vector, so
+/// that no memory is allocated.
+///
+/// # Parameters
+///
+/// - `iopattern`: A slice of `Call` enum representing the IO-pattern.
+/// - `domain_sep`: The domain separator to be used for encoding.
+/// - `profile`: The layout of the encoding of the tag input.
+/// - `buf`: The buffer to write the tag input into.
+///
+/// # Returns
+///
+/// A `Result` containing the length of the tag input on success, or an
+/// `Error` if the IO-pattern is not valid or the buffer is too small.
+fn tag_input_into(
+    iopattern: &[Call],
+    domain_sep: u64,
+    profile: EncodingProfile,
+    buf: &mut [u8],
+) -> Result<usize, Error> {
+    let mut len = 0;
+    let mut fits = true;
+    let mut write = |bytes: &[u8]| match buf.get_mut(len..len + bytes.len()) {
+        Some(dest) => {
+            dest.copy_from_slice(bytes);
+            len += bytes.len();
+        }
+        None => fits = false,
+    };
+    let big_endian = profile.byte_order == ByteOrder::BigEndian;
+    let domain = match big_endian {
+        true => domain_sep.to_be_bytes(),
+        false => domain_sep.to_le_bytes(),
+    };
+    if profile.domain_first {
+        write(&domain);
+    }
+    encode_io_pattern(iopattern, |word| match big_endian {
+        true => write(&word.to_be_bytes()),
+        false => write(&word.to_le_bytes()),
+    })?;
+    if !profile.domain_first {
+        write(&domain);
+    }
+
+    match fits {
+        true => Ok(len),
+        false => Err(Error::OutputLenMismatch),
+    }
+}
+
+/// Encodes the IO-pattern into 32-bit words, aggregating contiguous calls to
+/// absorb or squeeze, and passes every word to `word` in order.
+///
+/// This function returns an error if the IO-pattern is not sensible.
+fn encode_io_pattern(
+    iopattern: &[Call],
+    mut word: impl FnMut(u32),
+) -> Result<(), Error> {
     // make sure the IO-pattern is valid: start with absorb, end with squeeze
     // and none of the calls have a len == 0
-    validate_io_pattern(iopattern.as_ref())?;
+    validate_io_pattern(iopattern)?;
 
     // ABSORB_MASK = 0b10000000_00000000_00000000_00000000
     const ABSORB_MASK: u32 = 0x8000_0000;
 
-    // we know that the first call needs to be to absorb so we can initialize
-    // the vec
-    let mut input_u32 = Vec::new();
-    input_u32.push(ABSORB_MASK);
+    // the encoded call that may still be aggregated with the following calls
+    let mut pending: Option<u32> = None;
+    // whether the call may be aggregated with the previously encoded call
+    let mut aggregate = false;
 
     // Aggregate and encode calls to absorb and squeeze
-    iopattern.as_ref().iter().for_each(|call| {
-        // get a mutable ref to the previously encoded call
-        // Note: This is safe since we initialized the vector with one element
-        let l = input_u32.len();
-        let prev = &mut input_u32[l - 1];
-        match call {
+    for call in iopattern {
+        match (call, pending.as_mut()) {
             // if both this and the previous calls are to absorb, aggregate them
-            Call::Absorb(len) if *prev & ABSORB_MASK != 0 => {
-                *prev += *len as u32
+            (Call::Absorb(len), Some(prev))
+                if aggregate && *prev & ABSORB_MASK != 0 =>
+            {
+                aggregate_call(prev, *len)
             }
             // else add an encoded call to absorb
-            Call::Absorb(len) => input_u32.push(ABSORB_MASK + *len as u32),
+            (Call::Absorb(len), _) => {
+                if let Some(prev) = pending.replace(ABSORB_MASK + *len as u32) {
+                    word(prev);
+                }
+            }
             // if both this and the previous calls are to squeeze, aggregate
             // them
-            Call::Squeeze(len) if *prev & ABSORB_MASK == 0 => {
-                *prev += *len as u32
+            (Call::Squeeze(len), Some(prev))
+                if aggregate && *prev & ABSORB_MASK == 0 =>
+            {
+                aggregate_call(prev, *len)
             }
             // else add an encoded call to squeeze
-            Call::Squeeze(len) => input_u32.push(*len as u32),
+            (Call::Squeeze(len), _) => {
+                if let Some(prev) = pending.replace(*len as u32) {
+                    word(prev);
+                }
+            }
+            // encode labels as a zero, which doesn't encode any call, followed
+            // by the label
+            (Call::Label(label), _) => {
+                if let Some(prev) = pending.take() {
+                    word(prev);
+                }
+                word(0);
+                word((label >> 32) as u32);
+                word(*label as u32);
+            }
+            // encode ratchets as a call to absorb zero elements, which no
+            // other call encodes to
+            (Call::Ratchet, _) => {
+                if let Some(prev) = pending.take() {
+                    word(prev);
+                }
+                word(ABSORB_MASK);
+            }
         }
-    });
+        aggregate = !matches!(call, Call::Label(_) | Call::Ratchet);
+    }
+    if let Some(prev) = pending {
+        word(prev);
+    }
 
-    // Convert hash input to an array of u8, using big endian conversion
-    let mut input: Vec<u8> = input_u32
-        .iter()
-        .flat_map(|u32_int| u32_int.to_be_bytes().into_iter())
+    Ok(())
+}
+
+/// Encode the input for the tag for the sponge instance as field elements, so
+/// that a circuit can recompute the tag from the IO-pattern instead of taking
+/// it as a free witness.
+///
+/// The elements carry the same information as the byte encoding of the tag
+/// input, with a fixed packing: every 32-bit word encoding the IO-pattern
+/// becomes one element, followed by one element holding the
+/// domain-separator. An IO-pattern with `n` encoded words is therefore
+/// packed into `n + 1` elements.
+///
+/// # Parameters
+///
+/// - `iopattern`: A slice of `Call` enum representing the IO-pattern.
+/// - `domain_sep`: The domain separator to be used for encoding.
+///
+/// # Returns
+///
+/// A `Result` containing a vector of elements on success, or an `Error` if
+/// the IO-pattern is not valid.
+#[cfg(feature = "alloc")]
+pub fn tag_input_elements<T>(
+    iopattern: impl AsRef<[Call]>,
+    domain_sep: u64,
+) -> Result<Vec<T>, Error>
+where
+    T: From<u64>,
+{
+    let input = tag_input(iopattern, domain_sep)?;
+    let (words, _) = input.split_at(input.len() - 8);
+
+    let mut elements: Vec<T> = words
+        .chunks_exact(4)
+        .map(|word| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(word);
+            T::from(u32::from_be_bytes(bytes) as u64)
+        })
         .collect();
+    elements.push(T::from(domain_sep));
 
-    // Add the domain separator to the hash input
-    input.extend(domain_sep.to_be_bytes());
+    Ok(elements)
+}
 
-    Ok(input)
+/// Encode the input for the tag for the sponge instance like [`tag_input`],
+/// but with the layout of the given encoding profile.
+#[cfg(feature = "alloc")]
+fn profiled_tag_input(
+    iopattern: impl AsRef<[Call]>,
+    domain_sep: u64,
+    profile: EncodingProfile,
+) -> Result<Vec<u8>, Error> {
+    let input = tag_input(iopattern, domain_sep)?;
+    if profile == EncodingProfile::SAFE {
+        return Ok(input);
+    }
+
+    // re-encode the big endian words of the standard encoding
+    let (words, _) = input.split_at(input.len() - 8);
+    let domain = match profile.byte_order {
+        ByteOrder::BigEndian => domain_sep.to_be_bytes(),
+        ByteOrder::LittleEndian => domain_sep.to_le_bytes(),
+    };
+    let mut profiled = Vec::with_capacity(input.len());
+    if profile.domain_first {
+        profiled.extend(domain);
+    }
+    for word in words.chunks_exact(4) {
+        match profile.byte_order {
+            ByteOrder::BigEndian => profiled.extend_from_slice(word),
+            ByteOrder::LittleEndian => profiled.extend(word.iter().rev()),
+        }
+    }
+    if !profile.domain_first {
+        profiled.extend(domain);
+    }
+
+    Ok(profiled)
+}
+
+/// Encode the input for the tag for the sponge instance like
+/// [`profiled_tag_input`], using the given version of the encoding.
+#[cfg(feature = "alloc")]
+fn versioned_tag_input(
+    iopattern: impl AsRef<[Call]>,
+    domain_sep: u64,
+    version: TagVersion,
+    profile: EncodingProfile,
+) -> Result<Vec<u8>, Error> {
+    let input = profiled_tag_input(iopattern, domain_sep, profile)?;
+    match version {
+        TagVersion::V0 => Ok(input),
+        _ => {
+            let mut versioned = Vec::with_capacity(input.len() + 1);
+            versioned.push(version.to_byte());
+            versioned.extend(input);
+            Ok(versioned)
+        }
+    }
+}
+
+/// The length of the buffer on the stack that [`with_tag_input`] encodes the
+/// tag input into, before falling back to the heap.
+///
+/// Without the `alloc` feature there is no heap to fall back to, so the
+/// tag input of an IO-pattern needs to fit into this many bytes.
+pub(crate) const TAG_INPUT_STACK_LEN: usize = 256;
+
+/// Calls `f` with the tag input as encoded by `versioned_tag_input`.
+///
+/// The tag input is encoded into a buffer on the stack whenever it fits, so
+/// that starting a sponge doesn't allocate. Without the `alloc` feature, an
+/// IO-pattern whose tag input doesn't fit is rejected as invalid.
+fn with_tag_input<R>(
+    iopattern: &[Call],
+    domain_sep: u64,
+    version: TagVersion,
+    profile: EncodingProfile,
+    f: impl FnOnce(&[u8]) -> R,
+) -> Result<R, Error> {
+    let mut buf = [0u8; TAG_INPUT_STACK_LEN];
+    let offset = match version {
+        TagVersion::V0 => 0,
+        _ => {
+            buf[0] = version.to_byte();
+            1
+        }
+    };
+    match tag_input_into(iopattern, domain_sep, profile, &mut buf[offset..]) {
+        Ok(len) => return Ok(f(&buf[..offset + len])),
+        // the tag input doesn't fit on the stack
+        Err(Error::OutputLenMismatch) => {}
+        Err(err) => return Err(err),
+    }
+    #[cfg(feature = "alloc")]
+    {
+        let input =
+            versioned_tag_input(iopattern, domain_sep, version, profile)?;
+        Ok(f(&input))
+    }
+    #[cfg(not(feature = "alloc"))]
+    Err(Error::InvalidIOPattern)
+}
+
+/// Adds `len` to the length of the encoded call `prev`.
+///
+/// The aggregated length can't overflow into the bit that encodes the kind of
+/// the call, since [`validate_io_pattern`] bounds the total length of every
+/// run of contiguous calls.
+fn aggregate_call(prev: &mut u32, len: usize) {
+    *prev += len as u32;
+}
+
+/// Returns whether the IO-pattern is valid, i.e. whether a [`Sponge`] can be
+/// started with it.
+///
+/// Since this is a `const fn`, fixed IO-patterns can be checked at compile
+/// time, so that an invalid pattern fails the build instead of erroring at
+/// runtime:
+///
+/// ```
+/// use dusk_safe::{is_valid_io_pattern, Call};
+///
+/// const IO_PATTERN: [Call; 2] = [Call::Absorb(2), Call::Squeeze(1)];
+/// const _: () = assert!(is_valid_io_pattern(&IO_PATTERN));
+/// ```
+///
+/// # Parameters
+///
+/// - `iopattern`: A slice of `Call` enum representing the IO-pattern.
+///
+/// # Returns
+///
+/// `true` if the IO-pattern starts with a call to absorb, ends with a call to
+/// squeeze and the length of every call lies between 0 < len < 2^31, `false`
+/// otherwise.
+pub const fn is_valid_io_pattern(iopattern: &[Call]) -> bool {
+    validate_io_pattern(iopattern).is_ok()
+}
+
+/// Evaluates a security-critical condition, like the comparison of a tag.
+///
+/// With the `hardened` feature the condition is evaluated twice and only
+/// holds if both evaluations agree, so that a single injected fault can't
+/// turn a failing check into a passing one.
+///
+/// # Parameters
+///
+/// - `condition`: The condition to evaluate.
+///
+/// # Returns
+///
+/// `true` if the condition holds, `false` otherwise.
+#[inline(always)]
+pub(crate) fn critical_check(mut condition: impl FnMut() -> bool) -> bool {
+    let holds = core::hint::black_box(condition());
+    #[cfg(feature = "hardened")]
+    let holds_again = {
+        // keep the compiler from merging the two evaluations
+        let condition = core::hint::black_box(&mut condition);
+        core::hint::black_box(condition())
+    };
+    #[cfg(not(feature = "hardened"))]
+    let holds_again = true;
+    holds & holds_again
 }
 
 /// Check that the IO-pattern is sensible. This means that:
-/// - It doesn't start with a call to squeeze
-/// - It doesn't end with a call to absorb
+/// - It doesn't start with a call to squeeze or a ratchet, ignoring labels
+/// - It doesn't end with a call to absorb, a label or a ratchet
 /// - Every call to absorb or squeeze has a length between 0 < len < 2^31
+/// - Every run of contiguous calls to absorb or squeeze, which are aggregated
+///   in the tag input, has a total length below 2^31
 ///
 /// # Parameters
 ///
@@ -125,28 +827,57 @@ fn tag_input(
 ///
 /// A `Result` indicating success if the IO-pattern is valid, otherwise an
 /// `Error`.
-fn validate_io_pattern(iopattern: impl AsRef<[Call]>) -> Result<(), Error> {
+const fn validate_io_pattern(iopattern: &[Call]) -> Result<(), Error> {
     // make sure the IO-pattern starts with a call to absorb and ends with a
     // call to squeeze
-    match (iopattern.as_ref().first(), iopattern.as_ref().last()) {
-        (Some(Call::Absorb(_)), Some(Call::Squeeze(_))) => {}
-        _ => return Err(Error::InvalidIOPattern),
+    let mut first = 0;
+    while first < iopattern.len() && matches!(iopattern[first], Call::Label(_))
+    {
+        first += 1;
+    }
+    if first == iopattern.len()
+        || !matches!(iopattern[first], Call::Absorb(_))
+        || !matches!(iopattern[iopattern.len() - 1], Call::Squeeze(_))
+    {
+        return Err(Error::InvalidIOPattern);
     }
 
     // check that every call to absorb or squeeze has a length between:
     // 0 < len < 2^31
+    // and that the aggregated length of contiguous calls of the same kind
+    // doesn't overflow into the bit that encodes the kind of the call
     const MAX_LEN: usize = u32::MAX as usize >> 1;
-    if iopattern.as_ref().iter().any(|call| {
-        let call_len = *call.call_len();
-        call_len == 0 || call_len > MAX_LEN
-    }) {
-        Err(Error::InvalidIOPattern)
-    } else {
-        Ok(())
+    let mut run_len = 0;
+    let mut i = 0;
+    while i < iopattern.len() {
+        let call_len = *iopattern[i].call_len();
+        match iopattern[i] {
+            Call::Label(_) | Call::Ratchet => run_len = 0,
+            _ => {
+                if call_len == 0 || call_len > MAX_LEN {
+                    return Err(Error::InvalidIOPattern);
+                }
+                let same_kind = i > 0
+                    && matches!(
+                        (iopattern[i - 1], iopattern[i]),
+                        (Call::Absorb(_), Call::Absorb(_))
+                            | (Call::Squeeze(_), Call::Squeeze(_))
+                    );
+                run_len = match same_kind {
+                    true => run_len + call_len,
+                    false => call_len,
+                };
+                if run_len > MAX_LEN {
+                    return Err(Error::InvalidIOPattern);
+                }
+            }
+        }
+        i += 1;
     }
+    Ok(())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     extern crate std;
     use std::vec;
@@ -229,6 +960,27 @@ mod tests {
         let iopattern =
             vec![Call::Absorb(3), Call::Absorb(1 << 31), Call::Squeeze(1)];
         assert!(validate_io_pattern(&iopattern).is_err());
+
+        // test labels
+        let iopattern = vec![Call::Label(1), Call::Absorb(3), Call::Squeeze(1)];
+        assert!(validate_io_pattern(&iopattern).is_ok());
+
+        let iopattern =
+            vec![Call::Label(1), Call::Squeeze(3), Call::Squeeze(1)];
+        assert!(validate_io_pattern(&iopattern).is_err());
+
+        let iopattern = vec![Call::Absorb(3), Call::Squeeze(1), Call::Label(1)];
+        assert!(validate_io_pattern(&iopattern).is_err());
+
+        // test ratchets
+        let iopattern = vec![Call::Absorb(3), Call::Ratchet, Call::Squeeze(1)];
+        assert!(validate_io_pattern(&iopattern).is_ok());
+
+        let iopattern = vec![Call::Ratchet, Call::Absorb(3), Call::Squeeze(1)];
+        assert!(validate_io_pattern(&iopattern).is_err());
+
+        let iopattern = vec![Call::Absorb(3), Call::Squeeze(1), Call::Ratchet];
+        assert!(validate_io_pattern(&iopattern).is_err());
     }
 
     #[test]
@@ -239,23 +991,23 @@ mod tests {
         let pattern1 = vec![Call::Absorb(2), Call::Squeeze(10)];
         let pattern2 = vec![Call::Absorb(2), Call::Squeeze(1)];
         assert_ne!(
-            tag_input(&pattern1, domain_sep)?,
-            tag_input(&pattern2, domain_sep)?
+            tag_input(pattern1, domain_sep)?,
+            tag_input(pattern2, domain_sep)?
         );
 
         // check patterns whose aggregate are equal
         let pattern1 = vec![Call::Absorb(2), Call::Squeeze(1)];
         let pattern2 = vec![Call::Absorb(2), Call::Squeeze(1)];
         assert_eq!(
-            tag_input(&pattern1, domain_sep)?,
-            tag_input(&pattern2, domain_sep)?
+            tag_input(pattern1, domain_sep)?,
+            tag_input(pattern2, domain_sep)?
         );
 
         let pattern1 = vec![Call::Absorb(1), Call::Absorb(1), Call::Squeeze(1)];
         let pattern2 = vec![Call::Absorb(2), Call::Squeeze(1)];
         assert_eq!(
-            tag_input(&pattern1, domain_sep)?,
-            tag_input(&pattern2, domain_sep)?
+            tag_input(pattern1, domain_sep)?,
+            tag_input(pattern2, domain_sep)?
         );
 
         let pattern1 = vec![Call::Absorb(2), Call::Squeeze(10)];
@@ -266,8 +1018,8 @@ mod tests {
             Call::Squeeze(8),
         ];
         assert_eq!(
-            tag_input(&pattern1, domain_sep)?,
-            tag_input(&pattern2, domain_sep)?
+            tag_input(pattern1, domain_sep)?,
+            tag_input(pattern2, domain_sep)?
         );
 
         let pattern1 = vec![
@@ -292,9 +1044,402 @@ mod tests {
             Call::Squeeze(4),
         ];
         assert_eq!(
-            tag_input(&pattern1, domain_sep)?,
+            tag_input(pattern1, domain_sep)?,
+            tag_input(pattern2, domain_sep)?
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "ufmt")]
+    #[test]
+    fn test_ufmt_write() {
+        use std::string::String;
+
+        struct Buffer(String);
+
+        impl ufmt_write::uWrite for Buffer {
+            type Error = core::convert::Infallible;
+
+            fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+                self.0.push_str(s);
+                Ok(())
+            }
+        }
+
+        let mut buffer = Buffer(String::new());
+        Error::InvalidDomain.write_to(&mut buffer).unwrap();
+        assert_eq!(buffer.0, Error::InvalidDomain.message());
+
+        let mut buffer = Buffer(String::new());
+        let mismatch = LenMismatch {
+            expected: 10,
+            actual: 0,
+        };
+        mismatch.write_to(&mut buffer).unwrap();
+        assert_eq!(buffer.0, std::format!("{mismatch}"));
+
+        #[cfg(feature = "error-hook")]
+        {
+            let mut buffer = Buffer(String::new());
+            let context = ErrorContext {
+                error: Error::IOPatternViolation,
+                call: None,
+                expected: None,
+                io_count: 3,
+                domain_sep: 42,
+            };
+            context.write_to(&mut buffer).unwrap();
+            assert_eq!(
+                buffer.0,
+                "the IO-pattern wasn't followed after 3 calls in domain 42"
+            );
+        }
+    }
+
+    #[test]
+    fn test_aggregate() -> Result<(), Error> {
+        let iopattern = [
+            Call::Absorb(2),
+            Call::Absorb(3),
+            Call::Squeeze(1),
+            Call::Label(7),
+            Call::Squeeze(1),
+            Call::Squeeze(2),
+            Call::Absorb(1),
+            Call::Ratchet,
+            Call::Absorb(1),
+            Call::Squeeze(1),
+        ];
+        let aggregated = aggregate(iopattern);
+        assert_eq!(
+            aggregated,
+            vec![
+                Call::Absorb(5),
+                Call::Squeeze(1),
+                Call::Label(7),
+                Call::Squeeze(3),
+                Call::Absorb(1),
+                Call::Ratchet,
+                Call::Absorb(1),
+                Call::Squeeze(1),
+            ]
+        );
+        // the normalized form is stable
+        assert_eq!(aggregate(&aggregated), aggregated);
+
+        // patterns with the same normalized form have the same tag input
+        assert_eq!(tag_input(iopattern, 1)?, tag_input(&aggregated, 1)?);
+        let other = [Call::Absorb(5), Call::Squeeze(4)];
+        assert_ne!(aggregate(other), aggregated);
+        assert_ne!(tag_input(other, 1)?, tag_input(&aggregated, 1)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wide_tag_input() -> Result<(), Error> {
+        let iopattern = [Call::Absorb(2), Call::Squeeze(1)];
+
+        assert_eq!(
+            wide_tag_input(iopattern, b"net")?,
+            vec![
+                0x80, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, b'n', b'e',
+                b't', 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x14,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ]
+        );
+
+        // the empty domain doesn't collide with the domain-separator zero
+        assert_ne!(wide_tag_input(iopattern, &[])?, tag_input(iopattern, 0)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_input_ratchet() -> Result<(), Error> {
+        // ratchets are encoded as a call to absorb zero elements and prevent
+        // the calls around them from being aggregated
+        let iopattern = vec![
+            Call::Absorb(2),
+            Call::Ratchet,
+            Call::Absorb(1),
+            Call::Squeeze(1),
+        ];
+        assert_eq!(
+            tag_input_elements::<u64>(&iopattern, 42)?,
+            vec![0x8000_0002, 0x8000_0000, 0x8000_0001, 1, 42]
+        );
+
+        let aggregated = vec![Call::Absorb(3), Call::Squeeze(1)];
+        assert_ne!(tag_input(iopattern, 42)?, tag_input(aggregated, 42)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_input_elements() {
+        let iopattern = vec![
+            Call::Absorb(2),
+            Call::Absorb(1),
+            Call::Label(0x1_0000_0002),
+            Call::Squeeze(3),
+        ];
+        let domain_sep = 42;
+
+        assert_eq!(
+            tag_input_elements::<u64>(&iopattern, domain_sep),
+            Ok(vec![0x8000_0003, 0, 1, 2, 3, domain_sep])
+        );
+
+        // the elements carry the same information as the bytes
+        let input = tag_input(&iopattern, domain_sep).unwrap();
+        assert_eq!(input.len(), 5 * 4 + 8);
+
+        let iopattern = vec![Call::Squeeze(1)];
+        assert_eq!(
+            tag_input_elements::<u64>(&iopattern, domain_sep),
+            Err(Error::InvalidIOPattern)
+        );
+    }
+
+    #[test]
+    fn test_critical_check() {
+        let mut evaluations = 0;
+        assert!(critical_check(|| {
+            evaluations += 1;
+            true
+        }));
+        let expected = if cfg!(feature = "hardened") { 2 } else { 1 };
+        assert_eq!(evaluations, expected);
+
+        // with the hardened feature, a fault in one of the evaluations makes
+        // the check fail
+        let mut results = [true, false].into_iter();
+        assert_eq!(
+            critical_check(|| results.next().unwrap_or(false)),
+            !cfg!(feature = "hardened")
+        );
+    }
+
+    #[test]
+    fn test_aggregated_len_overflow() {
+        let max_len = u32::MAX as usize >> 1;
+
+        let iopattern = vec![Call::Absorb(max_len), Call::Squeeze(1)];
+        assert!(tag_input(iopattern, 0).is_ok());
+
+        let iopattern =
+            vec![Call::Absorb(max_len), Call::Absorb(1), Call::Squeeze(1)];
+        assert_eq!(tag_input(iopattern, 0), Err(Error::InvalidIOPattern));
+
+        let iopattern = vec![
+            Call::Absorb(1),
+            Call::Squeeze(max_len),
+            Call::Squeeze(max_len),
+        ];
+        assert_eq!(tag_input(&iopattern, 0), Err(Error::InvalidIOPattern));
+
+        // the const validation agrees with the encoding of the tag input
+        assert!(!is_valid_io_pattern(&iopattern));
+
+        // calls separated by a label or a ratchet are not aggregated
+        let iopattern = vec![
+            Call::Absorb(max_len),
+            Call::Label(0),
+            Call::Absorb(max_len),
+            Call::Ratchet,
+            Call::Absorb(max_len),
+            Call::Squeeze(1),
+        ];
+        assert!(is_valid_io_pattern(&iopattern));
+        assert!(tag_input(&iopattern, 0).is_ok());
+    }
+
+    #[test]
+    fn test_const_io_pattern() {
+        const IO_PATTERN: [Call; 3] =
+            [Call::Label(1), Call::Absorb(2), Call::Squeeze(1)];
+        const ABSORB_LEN: usize = *IO_PATTERN[1].call_len();
+        const VALIDATED: Result<(), Error> = validate_io_pattern(&IO_PATTERN);
+
+        assert_eq!(ABSORB_LEN, 2);
+        assert_eq!(VALIDATED, Ok(()));
+
+        let invalid = [Call::Squeeze(1), Call::Absorb(1)];
+        assert!(is_valid_io_pattern(&IO_PATTERN));
+        assert!(!is_valid_io_pattern(&invalid));
+        assert!(!is_valid_io_pattern(&[]));
+    }
+
+    #[test]
+    fn test_labeled_tag_input() -> Result<(), Error> {
+        let domain_sep = 42;
+
+        // labels separate otherwise equal patterns
+        let pattern1 = vec![Call::Absorb(2), Call::Squeeze(1)];
+        let pattern2 = vec![Call::Label(1), Call::Absorb(2), Call::Squeeze(1)];
+        let pattern3 = vec![Call::Label(2), Call::Absorb(2), Call::Squeeze(1)];
+        assert_ne!(
+            tag_input(pattern1, domain_sep)?,
             tag_input(&pattern2, domain_sep)?
         );
+        assert_ne!(
+            tag_input(&pattern2, domain_sep)?,
+            tag_input(pattern3, domain_sep)?
+        );
+
+        // labels prevent the aggregation of calls
+        let pattern1 = vec![Call::Absorb(2), Call::Label(1), Call::Squeeze(1)];
+        let pattern2 = vec![
+            Call::Absorb(1),
+            Call::Absorb(1),
+            Call::Label(1),
+            Call::Squeeze(1),
+        ];
+        let pattern3 = vec![
+            Call::Absorb(1),
+            Call::Label(1),
+            Call::Absorb(1),
+            Call::Squeeze(1),
+        ];
+        assert_eq!(
+            tag_input(&pattern1, domain_sep)?,
+            tag_input(pattern2, domain_sep)?
+        );
+        assert_ne!(
+            tag_input(&pattern1, domain_sep)?,
+            tag_input(pattern3, domain_sep)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_versioned_tag_input() -> Result<(), Error> {
+        let domain_sep = 42;
+        let pattern = vec![Call::Absorb(2), Call::Squeeze(1)];
+        let input = tag_input(&pattern, domain_sep)?;
+
+        // the original version is not prefixed
+        assert_eq!(
+            versioned_tag_input(
+                &pattern,
+                domain_sep,
+                TagVersion::V0,
+                EncodingProfile::SAFE
+            )?,
+            input
+        );
+
+        // later versions are prefixed with the version byte
+        let versioned = versioned_tag_input(
+            &pattern,
+            domain_sep,
+            TagVersion::V1,
+            EncodingProfile::SAFE,
+        )?;
+        assert_eq!(versioned[0], 0x01);
+        assert_eq!(versioned[1..], input[..]);
+
+        for version in [TagVersion::V0, TagVersion::V1] {
+            assert_eq!(TagVersion::from_byte(version.to_byte()), Some(version));
+        }
+        assert_eq!(TagVersion::from_byte(0x02), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_input_into() -> Result<(), Error> {
+        const SAFE: EncodingProfile = EncodingProfile::SAFE;
+        let domain_sep = 42;
+        let pattern = [
+            Call::Label(7),
+            Call::Absorb(3),
+            Call::Absorb(2),
+            Call::Squeeze(1),
+            Call::Squeeze(4),
+        ];
+        let expected = tag_input(pattern, domain_sep)?;
+
+        let mut buf = [0u8; 64];
+        let len = tag_input_into(&pattern, domain_sep, SAFE, &mut buf)?;
+        assert_eq!(&buf[..len], &expected[..]);
+
+        // the layout of every profile is the one of the profiled tag input
+        for (byte_order, domain_first) in [
+            (ByteOrder::BigEndian, true),
+            (ByteOrder::LittleEndian, false),
+            (ByteOrder::LittleEndian, true),
+        ] {
+            let profile = EncodingProfile {
+                byte_order,
+                domain_first,
+            };
+            let expected = profiled_tag_input(pattern, domain_sep, profile)?;
+            let len = tag_input_into(&pattern, domain_sep, profile, &mut buf)?;
+            assert_eq!(&buf[..len], &expected[..]);
+        }
+
+        // the buffer is too small to hold the tag input
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            tag_input_into(&pattern, domain_sep, SAFE, &mut buf),
+            Err(Error::OutputLenMismatch)
+        );
+
+        // invalid IO-patterns are still rejected
+        assert_eq!(
+            tag_input_into(&[Call::Squeeze(1)], domain_sep, SAFE, &mut buf),
+            Err(Error::InvalidIOPattern)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profiled_tag_input() -> Result<(), Error> {
+        let domain_sep = 0x0102;
+        let pattern = vec![Call::Absorb(2), Call::Squeeze(1)];
+
+        assert_eq!(
+            profiled_tag_input(
+                &pattern,
+                domain_sep,
+                EncodingProfile::default()
+            )?,
+            tag_input(&pattern, domain_sep)?
+        );
+
+        let profile = EncodingProfile {
+            byte_order: ByteOrder::LittleEndian,
+            domain_first: false,
+        };
+        assert_eq!(
+            profiled_tag_input(&pattern, domain_sep, profile)?,
+            vec![
+                0x02, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ]
+        );
+
+        let profile = EncodingProfile {
+            byte_order: ByteOrder::BigEndian,
+            domain_first: true,
+        };
+        assert_eq!(
+            profiled_tag_input(&pattern, domain_sep, profile)?,
+            vec![
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x80, 0x00,
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x01,
+            ]
+        );
+
+        assert_eq!(
+            profiled_tag_input([Call::Squeeze(1)], domain_sep, profile),
+            Err(Error::InvalidIOPattern)
+        );
 
         Ok(())
     }
@@ -0,0 +1,274 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+use crate::{tag_input, Call, Error, Safe, Sponge, SpongeElement};
+
+mod incremental;
+mod multi;
+mod sparse;
+
+pub use incremental::{Frontier, IncrementalMerkleTree};
+pub use multi::MerkleMultiOpening;
+pub use sparse::SparseMerkleTree;
+
+/// The IO-pattern used to compress two leaves of a merkle tree into their
+/// parent.
+const LEAF_IOPATTERN: &[Call] =
+    &[Call::Label(0), Call::Absorb(2), Call::Squeeze(1)];
+
+/// The IO-pattern used to compress two internal nodes of a merkle tree into
+/// their parent.
+const NODE_IOPATTERN: &[Call] =
+    &[Call::Label(1), Call::Absorb(2), Call::Squeeze(1)];
+
+/// The maximal depth of the merkle trees, bounded by the positions of the
+/// leaves being encoded as `u64`.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+/// Compresses two sibling nodes of a merkle tree into their parent node.
+///
+/// The leaves, at height zero, are compressed with the IO-pattern
+/// `[Label(0), Absorb(2), Squeeze(1)]` and the internal nodes with
+/// `[Label(1), Absorb(2), Squeeze(1)]`. The tags of both differ, so that an
+/// internal node can't be passed off as a leaf.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator of the merkle tree.
+/// - `height`: The height of the children, zero for the leaves.
+/// - `left`: The left child node.
+/// - `right`: The right child node.
+///
+/// # Returns
+///
+/// A result containing the parent node on success, or an `Error` if the
+/// sponge failed.
+pub fn merkle_hash<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    height: usize,
+    left: &T,
+    right: &T,
+) -> Result<T, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let mut sponge =
+        Sponge::start_static(safe, node_iopattern(height), domain_sep)?;
+    sponge.absorb(2, [*left, *right])?;
+    sponge.squeeze(1)?;
+    Ok(sponge.finish()?[0])
+}
+
+/// Compresses many pairs of sibling nodes at the same height into their
+/// parents, with the same result as calling [`merkle_hash`] on every pair.
+///
+/// The sponges of all pairs run in lockstep, so that their states are
+/// permuted at once with [`Safe::permute_batch`].
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator of the merkle tree.
+/// - `height`: The height of the children, zero for the leaves.
+/// - `pairs`: The left and right children of every parent.
+///
+/// # Returns
+///
+/// A result containing the parent nodes in the order of the pairs, or an
+/// `Error` if the domain-separator is invalid.
+pub fn merkle_hash_batch<S, T, const W: usize>(
+    safe: &mut S,
+    domain_sep: u64,
+    height: usize,
+    pairs: &[(T, T)],
+) -> Result<Vec<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let capacity = Sponge::<S, T, W>::CAPACITY;
+    let rate = Sponge::<S, T, W>::RATE;
+    let initial =
+        safe.tagged_state(&tag_input(node_iopattern(height), domain_sep)?);
+    let mut states = vec![initial; pairs.len()];
+
+    // absorb both children like the sponge does, permuting whenever the rate
+    // is full, and squeeze the first element of the rate
+    let mut pos = 0;
+    for child in 0..2 {
+        if pos == rate {
+            safe.permute_batch(&mut states);
+            pos = 0;
+        }
+        for (state, (left, right)) in states.iter_mut().zip(pairs) {
+            let element = match child {
+                0 => left,
+                _ => right,
+            };
+            let previous = state[capacity + pos];
+            state[capacity + pos] = safe.combine(&previous, element);
+        }
+        pos += 1;
+    }
+    safe.permute_batch(&mut states);
+    let parents = states.iter().map(|state| state[capacity]).collect();
+
+    states.zeroize();
+    Ok(parents)
+}
+
+/// Returns the IO-pattern compressing the children at the given height.
+const fn node_iopattern(height: usize) -> &'static [Call] {
+    match height {
+        0 => LEAF_IOPATTERN,
+        _ => NODE_IOPATTERN,
+    }
+}
+
+/// Computes the roots of the empty subtrees of every height up to `depth`,
+/// where the empty leaf is the default value of `T`.
+pub(crate) fn empty_subtrees<S, T, const W: usize>(
+    safe: &S,
+    domain_sep: u64,
+    depth: usize,
+) -> Result<Vec<T>, Error>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement,
+{
+    let mut empty = Vec::with_capacity(depth + 1);
+    empty.push(T::default());
+    for height in 0..depth {
+        let node = &empty[height];
+        empty.push(merkle_hash(safe.clone(), domain_sep, height, node, node)?);
+    }
+    Ok(empty)
+}
+
+/// An opening of a leaf in a merkle tree, proving that the leaf is stored at
+/// a given position of a tree with a given root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleOpening<T> {
+    position: u64,
+    siblings: Vec<T>,
+}
+
+impl<T> MerkleOpening<T>
+where
+    T: SpongeElement + PartialEq,
+{
+    /// Creates a new opening from the position of the leaf and the siblings
+    /// on the path from the leaf to the root, starting at the leaf level.
+    pub fn new(position: u64, siblings: Vec<T>) -> Self {
+        Self { position, siblings }
+    }
+
+    /// Returns the position of the opened leaf.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns the siblings on the path from the leaf to the root, starting
+    /// at the leaf level.
+    pub fn siblings(&self) -> &[T] {
+        &self.siblings
+    }
+
+    /// Computes the root of the tree from the opened leaf.
+    ///
+    /// The depth of the tree is given by the verifier rather than taken from
+    /// the opening, which is untrusted.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator of the merkle tree.
+    /// - `depth`: The depth of the tree, at most 64.
+    /// - `leaf`: The value of the opened leaf.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the computed root on success, or an `Error` if the
+    /// depth is invalid, the opening doesn't have one sibling per level, the
+    /// position lies outside of the tree or the sponge failed.
+    pub fn root<S, const W: usize>(
+        &self,
+        safe: &S,
+        domain_sep: u64,
+        depth: usize,
+        leaf: &T,
+    ) -> Result<T, Error>
+    where
+        S: Safe<T, W> + Clone,
+    {
+        check_depth(depth)?;
+        check_position(self.position, depth)?;
+        if self.siblings.len() != depth {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let mut node = *leaf;
+        for (height, sibling) in self.siblings.iter().enumerate() {
+            let (left, right) = match (self.position >> height) & 1 {
+                0 => (&node, sibling),
+                _ => (sibling, &node),
+            };
+            node = merkle_hash(safe.clone(), domain_sep, height, left, right)?;
+        }
+        Ok(node)
+    }
+
+    /// Verifies that the opened leaf is part of the tree with the given root.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator of the merkle tree.
+    /// - `depth`: The depth of the tree, at most 64.
+    /// - `root`: The root of the merkle tree.
+    /// - `leaf`: The value of the opened leaf.
+    ///
+    /// # Returns
+    ///
+    /// A result containing `true` if the opening is valid, or an `Error` if
+    /// the opening is malformed or the sponge failed.
+    pub fn verify<S, const W: usize>(
+        &self,
+        safe: &S,
+        domain_sep: u64,
+        depth: usize,
+        root: &T,
+        leaf: &T,
+    ) -> Result<bool, Error>
+    where
+        S: Safe<T, W> + Clone,
+    {
+        Ok(self.root(safe, domain_sep, depth, leaf)? == *root)
+    }
+}
+
+/// Checks that the depth of a tree doesn't exceed the maximal depth.
+pub(crate) fn check_depth(depth: usize) -> Result<(), Error> {
+    match depth <= MAX_DEPTH {
+        true => Ok(()),
+        false => Err(Error::InvalidDepth),
+    }
+}
+
+/// Checks that the position lies within a tree of the given depth.
+pub(crate) fn check_position(position: u64, depth: usize) -> Result<(), Error> {
+    match depth == MAX_DEPTH || position >> depth == 0 {
+        true => Ok(()),
+        false => Err(Error::InvalidPosition),
+    }
+}
@@ -0,0 +1,268 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use super::{check_depth, empty_subtrees, merkle_hash, MAX_DEPTH};
+use crate::{Error, Safe, SpongeElement};
+
+/// An append-only merkle tree of fixed depth, with the nodes being compressed
+/// by [`merkle_hash`].
+///
+/// The tree doesn't store its leaves, only the [`Frontier`]: the root, the
+/// position of the next leaf and, for every height, the last left node. This
+/// results in `O(depth)` memory and `depth` compressions per appended leaf.
+#[derive(Debug, Clone)]
+pub struct IncrementalMerkleTree<S, T, const W: usize>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement,
+{
+    safe: S,
+    domain_sep: u64,
+    empty: Vec<T>,
+    frontier: Frontier<T>,
+}
+
+impl<S, T, const W: usize> IncrementalMerkleTree<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement,
+{
+    /// Creates a new, empty incremental merkle tree.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator used to compress the nodes.
+    /// - `depth`: The depth of the tree, at most 64.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the empty tree, or an `Error` if the depth is
+    /// invalid or the sponge failed.
+    pub fn new(safe: S, domain_sep: u64, depth: usize) -> Result<Self, Error> {
+        check_depth(depth)?;
+        let empty = empty_subtrees(&safe, domain_sep, depth)?;
+        let frontier = Frontier {
+            next_position: 0,
+            root: empty[depth],
+            nodes: empty[..depth].to_vec(),
+        };
+        Ok(Self {
+            safe,
+            domain_sep,
+            empty,
+            frontier,
+        })
+    }
+
+    /// Resumes a tree from a previously stored [`Frontier`].
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator used to compress the nodes.
+    /// - `frontier`: The frontier of the tree to resume.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the resumed tree, or an `Error` if the frontier is
+    /// invalid or the sponge failed.
+    pub fn from_frontier(
+        safe: S,
+        domain_sep: u64,
+        frontier: Frontier<T>,
+    ) -> Result<Self, Error> {
+        let depth = frontier.nodes.len();
+        check_depth(depth)?;
+        if depth < MAX_DEPTH && frontier.next_position >> depth > 1 {
+            return Err(Error::InvalidPosition);
+        }
+        let empty = empty_subtrees(&safe, domain_sep, depth)?;
+        Ok(Self {
+            safe,
+            domain_sep,
+            empty,
+            frontier,
+        })
+    }
+
+    /// Returns the depth of the tree.
+    pub fn depth(&self) -> usize {
+        self.frontier.nodes.len()
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> T {
+        self.frontier.root
+    }
+
+    /// Returns the amount of leaves appended to the tree so far, which is
+    /// also the position of the next leaf.
+    pub fn len(&self) -> u64 {
+        self.frontier.next_position
+    }
+
+    /// Returns `true` if no leaf has been appended to the tree.
+    pub fn is_empty(&self) -> bool {
+        self.frontier.next_position == 0
+    }
+
+    /// Returns the frontier of the tree, which is all that is needed to
+    /// resume the tree with [`Self::from_frontier`].
+    pub fn frontier(&self) -> &Frontier<T> {
+        &self.frontier
+    }
+
+    /// Appends a leaf to the tree and updates the root.
+    ///
+    /// # Parameters
+    ///
+    /// - `leaf`: The leaf to append.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the position of the appended leaf, or an `Error`
+    /// if the tree is full or the sponge failed.
+    pub fn append(&mut self, leaf: T) -> Result<u64, Error> {
+        let depth = self.depth();
+        let position = self.frontier.next_position;
+        if depth < MAX_DEPTH && position >> depth != 0 {
+            return Err(Error::InvalidPosition);
+        }
+
+        let mut node = leaf;
+        for height in 0..depth {
+            node = match (position >> height) & 1 {
+                0 => {
+                    self.frontier.nodes[height] = node;
+                    let empty = &self.empty[height];
+                    merkle_hash(
+                        self.safe.clone(),
+                        self.domain_sep,
+                        height,
+                        &node,
+                        empty,
+                    )?
+                }
+                _ => {
+                    let left = &self.frontier.nodes[height];
+                    merkle_hash(
+                        self.safe.clone(),
+                        self.domain_sep,
+                        height,
+                        left,
+                        &node,
+                    )?
+                }
+            };
+        }
+
+        self.frontier.root = node;
+        self.frontier.next_position = position.wrapping_add(1);
+
+        Ok(position)
+    }
+}
+
+/// The frontier of an [`IncrementalMerkleTree`].
+///
+/// It consists of the position of the next leaf, the current root and, for
+/// every height of the tree, the last node that was a left child.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frontier<T> {
+    next_position: u64,
+    root: T,
+    nodes: Vec<T>,
+}
+
+impl<T> Frontier<T>
+where
+    T: Default + Copy,
+{
+    /// Returns the position of the next leaf.
+    pub fn next_position(&self) -> u64 {
+        self.next_position
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> &T {
+        &self.root
+    }
+
+    /// Returns the last left node of every height, starting at the leaves.
+    pub fn nodes(&self) -> &[T] {
+        &self.nodes
+    }
+
+    /// Serializes the frontier into bytes, using `encode` to serialize the
+    /// individual elements.
+    ///
+    /// The encoding is the big-endian position of the next leaf, followed by
+    /// the root and the nodes, starting at the leaves.
+    ///
+    /// # Parameters
+    ///
+    /// - `encode`: Serializes one element into `N` bytes.
+    ///
+    /// # Returns
+    ///
+    /// The serialized frontier.
+    pub fn to_bytes<const N: usize>(
+        &self,
+        encode: impl Fn(&T) -> [u8; N],
+    ) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + (self.nodes.len() + 1) * N);
+        bytes.extend(self.next_position.to_be_bytes());
+        bytes.extend(encode(&self.root));
+        self.nodes
+            .iter()
+            .for_each(|node| bytes.extend(encode(node)));
+        bytes
+    }
+
+    /// Deserializes a frontier from bytes produced by [`Self::to_bytes`],
+    /// using `decode` to deserialize the individual elements.
+    ///
+    /// # Parameters
+    ///
+    /// - `bytes`: The serialized frontier.
+    /// - `decode`: Deserializes one element from `N` bytes, returning `None` if
+    ///   the bytes are not a valid element.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the frontier, or an `Error` if the bytes are not a
+    /// valid encoding.
+    pub fn from_bytes<const N: usize>(
+        bytes: impl AsRef<[u8]>,
+        decode: impl Fn(&[u8; N]) -> Option<T>,
+    ) -> Result<Self, Error> {
+        let bytes = bytes.as_ref();
+        if N == 0 || bytes.len() < 8 + N || (bytes.len() - 8) % N != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let mut next_position = [0u8; 8];
+        next_position.copy_from_slice(&bytes[..8]);
+        let next_position = u64::from_be_bytes(next_position);
+
+        let mut elements = bytes[8..].chunks_exact(N).map(|chunk| {
+            let mut buf = [0u8; N];
+            buf.copy_from_slice(chunk);
+            decode(&buf).ok_or(Error::InvalidEncoding)
+        });
+        let root = elements.next().ok_or(Error::InvalidEncoding)??;
+        let nodes = elements.collect::<Result<Vec<T>, Error>>()?;
+
+        Ok(Self {
+            next_position,
+            root,
+            nodes,
+        })
+    }
+}
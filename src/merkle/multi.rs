@@ -0,0 +1,175 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use super::{check_depth, check_position, merkle_hash_batch};
+use crate::{Error, Safe, SpongeElement};
+
+/// An opening of many leaves of a merkle tree against the same root.
+///
+/// Compared to one [`MerkleOpening`](super::MerkleOpening) per leaf, the
+/// multi-opening only contains the siblings that cannot be computed from the
+/// opened leaves themselves, and every internal node is compressed only once
+/// during verification, no matter how many of the opened paths it is part of.
+/// The nodes of each level are compressed together with
+/// [`merkle_hash_batch`], so that backends implementing
+/// [`Safe::permute_batch`] permute them at once.
+///
+/// The siblings are stored level by level, starting at the leaves, and
+/// ordered by their position within each level. The depth of the tree isn't
+/// part of the opening, it is given by the verifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleMultiOpening<T> {
+    positions: Vec<u64>,
+    siblings: Vec<T>,
+}
+
+impl<T> MerkleMultiOpening<T>
+where
+    T: SpongeElement + PartialEq,
+{
+    /// Creates a multi-opening from its parts.
+    ///
+    /// # Parameters
+    ///
+    /// - `positions`: The positions of the opened leaves, sorted in ascending
+    ///   order and without duplicates.
+    /// - `siblings`: The siblings needed to compute the root, stored level by
+    ///   level and ordered by position.
+    pub fn new(positions: Vec<u64>, siblings: Vec<T>) -> Self {
+        Self {
+            positions,
+            siblings,
+        }
+    }
+
+    /// Returns the positions of the opened leaves in ascending order.
+    pub fn positions(&self) -> &[u64] {
+        &self.positions
+    }
+
+    /// Returns the siblings needed to compute the root.
+    pub fn siblings(&self) -> &[T] {
+        &self.siblings
+    }
+
+    /// Computes the root of the tree from the opened leaves.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator of the merkle tree.
+    /// - `depth`: The depth of the tree, at most 64.
+    /// - `leaves`: The values of the opened leaves, in the same order as
+    ///   [`Self::positions`].
+    ///
+    /// # Returns
+    ///
+    /// A result containing the computed root on success, or an `Error` if the
+    /// depth is invalid, a position lies outside of the tree, the opening is
+    /// malformed or the sponge failed.
+    pub fn root<S, const W: usize>(
+        &self,
+        safe: &S,
+        domain_sep: u64,
+        depth: usize,
+        leaves: &[T],
+    ) -> Result<T, Error>
+    where
+        S: Safe<T, W> + Clone,
+    {
+        check_depth(depth)?;
+        if leaves.is_empty() || leaves.len() != self.positions.len() {
+            return Err(Error::InvalidEncoding);
+        }
+        if self.positions.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(Error::InvalidEncoding);
+        }
+        for position in &self.positions {
+            check_position(*position, depth)?;
+        }
+
+        let mut safe = safe.clone();
+        let mut nodes: Vec<(u64, T)> = self
+            .positions
+            .iter()
+            .copied()
+            .zip(leaves.iter().copied())
+            .collect();
+        let mut siblings = self.siblings.iter();
+
+        for height in 0..depth {
+            let mut pairs = Vec::with_capacity(nodes.len());
+            let mut parents = Vec::with_capacity(nodes.len());
+            let mut i = 0;
+            while i < nodes.len() {
+                let (position, node) = nodes[i];
+                let pair = match nodes.get(i + 1) {
+                    // both children are known, hash them once
+                    Some((next, right))
+                        if position & 1 == 0 && *next == position + 1 =>
+                    {
+                        i += 1;
+                        (node, *right)
+                    }
+                    _ => {
+                        let sibling =
+                            *siblings.next().ok_or(Error::InvalidEncoding)?;
+                        match position & 1 {
+                            0 => (node, sibling),
+                            _ => (sibling, node),
+                        }
+                    }
+                };
+                pairs.push(pair);
+                parents.push(position >> 1);
+                i += 1;
+            }
+
+            // compress the whole level at once
+            let compressed =
+                merkle_hash_batch(&mut safe, domain_sep, height, &pairs)?;
+            nodes = parents.into_iter().zip(compressed).collect();
+        }
+
+        // all siblings need to be used and only the root may remain
+        match (siblings.next(), &nodes[..]) {
+            (None, [(0, root)]) => Ok(*root),
+            _ => Err(Error::InvalidEncoding),
+        }
+    }
+
+    /// Verifies that the opened leaves are part of the tree with the given
+    /// root.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator of the merkle tree.
+    /// - `depth`: The depth of the tree, at most 64.
+    /// - `root`: The root of the merkle tree.
+    /// - `leaves`: The values of the opened leaves, in the same order as
+    ///   [`Self::positions`].
+    ///
+    /// # Returns
+    ///
+    /// A result containing `true` if the opening is valid, or an `Error` if
+    /// the opening is malformed or the sponge failed.
+    pub fn verify<S, const W: usize>(
+        &self,
+        safe: &S,
+        domain_sep: u64,
+        depth: usize,
+        root: &T,
+        leaves: &[T],
+    ) -> Result<bool, Error>
+    where
+        S: Safe<T, W> + Clone,
+    {
+        Ok(self.root(safe, domain_sep, depth, leaves)? == *root)
+    }
+}
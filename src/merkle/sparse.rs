@@ -0,0 +1,239 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use super::{
+    check_depth, check_position, empty_subtrees, merkle_hash,
+    MerkleMultiOpening, MerkleOpening,
+};
+use crate::{Error, Safe, SpongeElement};
+
+/// A sparse merkle tree of fixed depth, with the nodes being compressed by
+/// [`merkle_hash`].
+///
+/// Only the nodes that differ from the root of an empty subtree of the same
+/// height are stored, the roots of the empty subtrees are computed once when
+/// creating the tree. This makes the tree suitable for very large position
+/// spaces with few occupied leaves, such as nullifier or state sets.
+///
+/// Empty leaves have the default value of `T`, and removing a leaf is the
+/// same as setting it to the default value.
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree<S, T, const W: usize>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + PartialEq,
+{
+    safe: S,
+    domain_sep: u64,
+    depth: usize,
+    empty: Vec<T>,
+    // the non-empty nodes indexed by their height and position at that height
+    nodes: BTreeMap<(usize, u64), T>,
+}
+
+impl<S, T, const W: usize> SparseMerkleTree<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + PartialEq,
+{
+    /// Creates a new, empty sparse merkle tree.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator used to compress the nodes.
+    /// - `depth`: The depth of the tree, at most 64.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the empty tree, or an `Error` if the depth is
+    /// invalid or the sponge failed.
+    pub fn new(safe: S, domain_sep: u64, depth: usize) -> Result<Self, Error> {
+        check_depth(depth)?;
+        let empty = empty_subtrees(&safe, domain_sep, depth)?;
+        Ok(Self {
+            safe,
+            domain_sep,
+            depth,
+            empty,
+            nodes: BTreeMap::new(),
+        })
+    }
+
+    /// Returns the depth of the tree.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> T {
+        self.node(self.depth, 0)
+    }
+
+    /// Returns the leaf at the given position, which is the default value of
+    /// `T` if the leaf is empty.
+    pub fn get(&self, position: u64) -> T {
+        self.node(0, position)
+    }
+
+    /// Inserts a leaf at the given position, replacing the previous value and
+    /// updating the path to the root.
+    ///
+    /// # Parameters
+    ///
+    /// - `position`: The position of the leaf.
+    /// - `leaf`: The new value of the leaf.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success, or an `Error` if the position is outside
+    /// of the tree or the sponge failed.
+    pub fn insert(&mut self, position: u64, leaf: T) -> Result<(), Error> {
+        self.check_position(position)?;
+
+        self.set_node(0, position, leaf);
+        let mut position = position;
+        for height in 0..self.depth {
+            let node = self.node(height, position);
+            let sibling = self.node(height, position ^ 1);
+            let (left, right) = match position & 1 {
+                0 => (node, sibling),
+                _ => (sibling, node),
+            };
+            position >>= 1;
+            let parent = merkle_hash(
+                self.safe.clone(),
+                self.domain_sep,
+                height,
+                &left,
+                &right,
+            )?;
+            self.set_node(height + 1, position, parent);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the leaf at the given position by setting it to the empty leaf.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success, or an `Error` if the position is outside
+    /// of the tree or the sponge failed.
+    pub fn remove(&mut self, position: u64) -> Result<(), Error> {
+        self.insert(position, T::default())
+    }
+
+    /// Creates an opening for the leaf at the given position, which can be
+    /// both an occupied or an empty leaf (proving non-membership).
+    ///
+    /// # Returns
+    ///
+    /// A result containing the opening, or an `Error` if the position is
+    /// outside of the tree.
+    pub fn prove(&self, position: u64) -> Result<MerkleOpening<T>, Error> {
+        self.check_position(position)?;
+
+        let siblings = (0..self.depth)
+            .map(|height| self.node(height, (position >> height) ^ 1))
+            .collect();
+
+        Ok(MerkleOpening::new(position, siblings))
+    }
+
+    /// Creates a multi-opening for the leaves at the given positions, which
+    /// only contains the siblings that cannot be computed from the opened
+    /// leaves themselves.
+    ///
+    /// # Parameters
+    ///
+    /// - `positions`: The positions of the leaves to open, in any order.
+    ///   Duplicates are ignored.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the multi-opening, or an `Error` if no position is
+    /// given or a position is outside of the tree.
+    pub fn prove_many(
+        &self,
+        positions: impl AsRef<[u64]>,
+    ) -> Result<MerkleMultiOpening<T>, Error> {
+        let mut positions = positions.as_ref().to_vec();
+        if positions.is_empty() {
+            return Err(Error::InvalidPosition);
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        for position in &positions {
+            self.check_position(*position)?;
+        }
+
+        let mut siblings = Vec::new();
+        let mut known = positions.clone();
+        for height in 0..self.depth {
+            let mut i = 0;
+            while i < known.len() {
+                let position = known[i];
+                match known.get(i + 1) {
+                    // the sibling is computed from the opened leaves
+                    Some(next)
+                        if position & 1 == 0 && *next == position + 1 =>
+                    {
+                        i += 1;
+                    }
+                    _ => siblings.push(self.node(height, position ^ 1)),
+                }
+                i += 1;
+            }
+            known.iter_mut().for_each(|position| *position >>= 1);
+            known.dedup();
+        }
+
+        Ok(MerkleMultiOpening::new(positions, siblings))
+    }
+
+    /// Verifies an opening of a leaf against the current root of the tree.
+    ///
+    /// # Returns
+    ///
+    /// A result containing `true` if the opening is valid, or an `Error` if
+    /// the opening is malformed or the sponge failed.
+    pub fn verify(
+        &self,
+        opening: &MerkleOpening<T>,
+        leaf: &T,
+    ) -> Result<bool, Error> {
+        opening.verify(
+            &self.safe,
+            self.domain_sep,
+            self.depth,
+            &self.root(),
+            leaf,
+        )
+    }
+
+    fn check_position(&self, position: u64) -> Result<(), Error> {
+        check_position(position, self.depth)
+    }
+
+    fn node(&self, height: usize, position: u64) -> T {
+        self.nodes
+            .get(&(height, position))
+            .copied()
+            .unwrap_or(self.empty[height])
+    }
+
+    fn set_node(&mut self, height: usize, position: u64, node: T) {
+        // only store nodes that are not the root of an empty subtree
+        match node == self.empty[height] {
+            true => self.nodes.remove(&(height, position)),
+            false => self.nodes.insert((height, position), node),
+        };
+    }
+}
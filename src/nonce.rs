@@ -0,0 +1,123 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use core::ops::Deref;
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::domain::{COUNTER_NONCE_ID, SIGNING_NONCE_ID};
+use crate::prf::keyed_hash;
+use crate::{Error, Safe, SpongeElement};
+
+/// Derives a signing nonce deterministically from the secret key and the
+/// message, in the spirit of RFC 6979.
+///
+/// Deriving the nonce from the secret key and the message means that the
+/// same nonce is never used for two different messages, while signing the
+/// same message twice results in the same signature. The derivation follows
+/// the [`prf`](fn@crate::prf) construction with the secret key as key and the
+/// message as input, but is bound to the
+/// [`SIGNING_NONCE_ID`](crate::domain::SIGNING_NONCE_ID) in the most
+/// significant byte of the domain-separator.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator of the signature scheme, with the most
+///   significant byte set to zero.
+/// - `secret_key`: The secret signing key, at least one element.
+/// - `message`: The message to sign.
+///
+/// # Returns
+///
+/// A result containing the nonce, which is erased from memory when dropped,
+/// on success, or an `Error` if the domain-separator or the lengths are
+/// invalid.
+pub fn signing_nonce<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    secret_key: impl AsRef<[T]>,
+    message: impl AsRef<[T]>,
+) -> Result<Zeroizing<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let mut output =
+        keyed_hash(safe, SIGNING_NONCE_ID, domain_sep, secret_key, message, 1)?;
+    let nonce = Zeroizing::new(output[0]);
+    output.zeroize();
+    Ok(nonce)
+}
+
+/// A nonce derived from a counter with [`derive_nonce`].
+///
+/// The nonce dereferences to its element, so it can be passed to e.g.
+/// `encrypt` directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nonce<T>(T);
+
+impl<T> Nonce<T> {
+    /// Returns the element of the nonce.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Nonce<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Derives a nonce from a master secret and a counter, for applications that
+/// can't persist random nonces but can keep a monotonic counter.
+///
+/// The derivation follows the [`prf`](fn@crate::prf) construction with the
+/// master secret as key and the counter as input, but is bound to the
+/// [`COUNTER_NONCE_ID`](crate::domain::COUNTER_NONCE_ID) in the most
+/// significant byte of the domain-separator. As long as a counter value is
+/// never used twice with the same master secret and domain-separator, the
+/// nonces are unique up to collisions of the sponge. Since the nonce looks
+/// random, it doesn't leak the counter.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator of the application, with the most
+///   significant byte set to zero.
+/// - `master_secret`: The secret the nonces are derived from, at least one
+///   element.
+/// - `counter`: The value of the counter, which must never repeat.
+///
+/// # Returns
+///
+/// A result containing the nonce on success, or an `Error` if the
+/// domain-separator or the length of the master secret is invalid.
+pub fn derive_nonce<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    master_secret: impl AsRef<[T]>,
+    counter: u64,
+) -> Result<Nonce<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let mut output = keyed_hash(
+        safe,
+        COUNTER_NONCE_ID,
+        domain_sep,
+        master_secret,
+        [T::from(counter)],
+        1,
+    )?;
+    let nonce = Nonce(output[0]);
+    output.zeroize();
+    Ok(nonce)
+}
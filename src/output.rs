@@ -0,0 +1,319 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
+use zeroize::Zeroize;
+
+use crate::{Error, SpongeElement};
+
+/// The amount of elements that an [`Output`] can hold before spilling onto
+/// the heap.
+pub(crate) const INLINE_CAPACITY: usize = 4;
+
+/// Determines how the calls to squeeze of a [`Sponge`](crate::Sponge) fill
+/// its [`Output`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Every call to squeeze appends its elements to the output, so that the
+    /// output holds all squeezed elements.
+    #[default]
+    Accumulate,
+    /// Every call to squeeze overwrites the output with its elements, so that
+    /// the output only holds the elements of the latest call. This keeps the
+    /// memory flat for protocols that consume every squeeze immediately.
+    Overwrite,
+}
+
+/// The error of converting an [`Output`] into a fixed amount of elements
+/// when it holds a different amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LenMismatch {
+    /// The amount of elements expected.
+    pub expected: usize,
+    /// The amount of elements the output holds.
+    pub actual: usize,
+}
+
+impl fmt::Display for LenMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected an output of {} elements, but it holds {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl LenMismatch {
+    /// Writes a description of the mismatch into a `ufmt` writer, the same
+    /// as its `Display` implementation.
+    ///
+    /// # Parameters
+    ///
+    /// - `writer`: The destination of the description.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the writer accepted the description.
+    pub fn write_to<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: ufmt_write::uWrite + ?Sized,
+    {
+        writer.write_str("expected an output of ")?;
+        crate::error::write_decimal(writer, self.expected as u64)?;
+        writer.write_str(" elements, but it holds ")?;
+        crate::error::write_decimal(writer, self.actual as u64)
+    }
+}
+
+impl From<LenMismatch> for Error {
+    fn from(_: LenMismatch) -> Self {
+        Error::OutputLenMismatch
+    }
+}
+
+/// Container for the elements squeezed from the sponge.
+///
+/// Most IO-patterns only squeeze a handful of elements, so the first four
+/// elements are stored inline and no heap allocation takes place. Only when
+/// more elements are pushed, the output spills onto the heap. Without the
+/// `alloc` feature, the output never holds more than four elements.
+///
+/// Like the [`Sponge`](crate::Sponge) it is squeezed from, the output erases
+/// its elements from memory when it is dropped, and so does every clone of
+/// it.
+#[derive(Clone)]
+pub struct Output<T>
+where
+    T: SpongeElement,
+{
+    repr: Repr<T>,
+}
+
+#[derive(Clone)]
+enum Repr<T> {
+    Inline([T; INLINE_CAPACITY], usize),
+    #[cfg(feature = "alloc")]
+    Heap(Vec<T>),
+}
+
+impl<T> Output<T>
+where
+    T: SpongeElement,
+{
+    /// Creates a new, empty output.
+    pub fn new() -> Self {
+        Self {
+            repr: Repr::Inline([T::default(); INLINE_CAPACITY], 0),
+        }
+    }
+
+    /// Appends an element to the back of the output, spilling the elements
+    /// onto the heap when the inline capacity is exceeded.
+    ///
+    /// # Parameters
+    ///
+    /// - `element`: The element to append.
+    ///
+    /// # Panics
+    ///
+    /// Without the `alloc` feature, if the output already holds four
+    /// elements.
+    pub fn push(&mut self, element: T) {
+        match &mut self.repr {
+            Repr::Inline(buf, len) if *len < INLINE_CAPACITY => {
+                buf[*len] = element;
+                *len += 1;
+            }
+            #[cfg(not(feature = "alloc"))]
+            Repr::Inline(..) => panic!("the output exceeds its capacity"),
+            #[cfg(feature = "alloc")]
+            Repr::Inline(buf, len) => {
+                let mut vec = Vec::with_capacity(2 * INLINE_CAPACITY);
+                vec.extend_from_slice(&buf[..*len]);
+                vec.push(element);
+                // erase the inline copy of the elements before moving on
+                buf.zeroize();
+                self.repr = Repr::Heap(vec);
+            }
+            #[cfg(feature = "alloc")]
+            Repr::Heap(vec) => vec.push(element),
+        }
+    }
+
+    /// Returns the output as a slice of elements.
+    pub fn as_slice(&self) -> &[T] {
+        match &self.repr {
+            Repr::Inline(buf, len) => &buf[..*len],
+            #[cfg(feature = "alloc")]
+            Repr::Heap(vec) => &vec[..],
+        }
+    }
+
+    /// Removes all elements from the output, erasing them from memory.
+    ///
+    /// Once the output spilled onto the heap, the allocation is kept for the
+    /// elements pushed afterwards.
+    pub fn clear(&mut self) {
+        self.zeroize();
+    }
+
+    /// Returns `true` if the output has not been spilled onto the heap.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.repr, Repr::Inline(..))
+    }
+
+    /// Returns the only element of the output, for the common case of
+    /// squeezing exactly one element.
+    ///
+    /// # Returns
+    ///
+    /// The element, or a [`LenMismatch`] if the output doesn't hold exactly
+    /// one element.
+    pub fn single(self) -> Result<T, LenMismatch> {
+        let [element]: [T; 1] = self.try_into()?;
+        Ok(element)
+    }
+
+    /// Converts the output into a vector of elements.
+    #[cfg(feature = "alloc")]
+    pub fn into_vec(mut self) -> Vec<T> {
+        match &mut self.repr {
+            Repr::Inline(_, _) => {
+                let vec = Vec::from(self.as_slice());
+                self.zeroize();
+                vec
+            }
+            Repr::Heap(vec) => core::mem::take(vec),
+        }
+    }
+}
+
+impl<T> Default for Output<T>
+where
+    T: SpongeElement,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for Output<T>
+where
+    T: SpongeElement,
+{
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> AsRef<[T]> for Output<T>
+where
+    T: SpongeElement,
+{
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> fmt::Debug for Output<T>
+where
+    T: SpongeElement + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T> PartialEq for Output<T>
+where
+    T: SpongeElement + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> PartialEq<Vec<T>> for Output<T>
+where
+    T: SpongeElement + PartialEq,
+{
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.as_slice() == &other[..]
+    }
+}
+
+impl<T> PartialEq<[T]> for Output<T>
+where
+    T: SpongeElement + PartialEq,
+{
+    fn eq(&self, other: &[T]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> From<Output<T>> for Vec<T>
+where
+    T: SpongeElement,
+{
+    fn from(output: Output<T>) -> Self {
+        output.into_vec()
+    }
+}
+
+impl<T, const N: usize> TryFrom<Output<T>> for [T; N]
+where
+    T: SpongeElement,
+{
+    type Error = LenMismatch;
+
+    fn try_from(mut output: Output<T>) -> Result<Self, LenMismatch> {
+        if output.len() != N {
+            return Err(LenMismatch {
+                expected: N,
+                actual: output.len(),
+            });
+        }
+        let mut elements = [T::default(); N];
+        elements.copy_from_slice(&output);
+        output.zeroize();
+        Ok(elements)
+    }
+}
+
+impl<T> Drop for Output<T>
+where
+    T: SpongeElement,
+{
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<T> Zeroize for Output<T>
+where
+    T: SpongeElement,
+{
+    fn zeroize(&mut self) {
+        match &mut self.repr {
+            Repr::Inline(buf, len) => {
+                buf.zeroize();
+                len.zeroize();
+            }
+            #[cfg(feature = "alloc")]
+            Repr::Heap(vec) => vec.zeroize(),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use crate::domain::{
+    construction_domain, PADDING_LENGTH_ID, PADDING_ONE_ID, RESERVED_MASK,
+};
+use crate::{Call, Error, Output, Safe, Sponge, SpongeElement};
+
+/// A padding scheme for absorbing inputs whose length is only known at
+/// runtime, see [`Padding`] for the standard schemes.
+///
+/// A scheme determines the calls that absorb a padded input, so that they can
+/// be part of the IO-pattern, and absorbs the padded input along those calls,
+/// see [`Sponge::absorb_padded`]. Different schemes may absorb an input with
+/// the same calls, the identifier of the scheme is therefore encoded in the
+/// most significant byte of the domain-separator.
+pub trait PaddingScheme<T>
+where
+    T: SpongeElement,
+{
+    /// Returns the identifier of the scheme as encoded in the
+    /// domain-separator. It must differ from the identifiers of the other
+    /// constructions in the [`domain`](crate::domain) module.
+    fn id(&self) -> u8;
+
+    /// Returns the calls that absorb an input of `input_len` elements with
+    /// this scheme.
+    ///
+    /// # Parameters
+    ///
+    /// - `input_len`: The amount of elements of the unpadded input.
+    ///
+    /// # Returns
+    ///
+    /// The calls to absorb the padded input.
+    fn absorb_calls(&self, input_len: usize) -> Vec<Call>;
+
+    /// Absorbs the padded input into a sponge whose IO-pattern expects the
+    /// calls returned by [`Self::absorb_calls`].
+    ///
+    /// # Parameters
+    ///
+    /// - `sponge`: The sponge to absorb the input into.
+    /// - `input`: The unpadded input.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    fn absorb<S, const W: usize>(
+        &self,
+        sponge: &mut Sponge<S, T, W>,
+        input: &[T],
+    ) -> Result<(), Error>
+    where
+        S: Safe<T, W>;
+
+    /// Encodes the identifier of the scheme into the domain-separator.
+    ///
+    /// # Parameters
+    ///
+    /// - `domain_sep`: The domain separator chosen by the user.
+    ///
+    /// # Returns
+    ///
+    /// The domain-separator with the identifier stored in its most
+    /// significant byte, or an `Error` if `domain_sep` already uses any of
+    /// the bits of [`RESERVED_MASK`](crate::domain::RESERVED_MASK).
+    fn domain_sep(&self, domain_sep: u64) -> Result<u64, Error> {
+        construction_domain(self.id(), domain_sep)
+    }
+
+    /// Returns the IO-pattern for hashing `input_len` elements with this
+    /// scheme into `output_len` elements.
+    ///
+    /// # Parameters
+    ///
+    /// - `input_len`: The amount of elements of the unpadded input.
+    /// - `output_len`: The amount of elements to squeeze.
+    ///
+    /// # Returns
+    ///
+    /// The IO-pattern to start the sponge with.
+    fn iopattern(&self, input_len: usize, output_len: usize) -> Vec<Call> {
+        let mut iopattern = self.absorb_calls(input_len);
+        iopattern.push(Call::Squeeze(output_len));
+        iopattern
+    }
+}
+
+/// Padding schemes for hashing inputs whose length is only known at runtime.
+///
+/// Both schemes add exactly one element to the input, so that a padded input
+/// of length `len` is absorbed with a single `Call::Absorb(len + 1)` in the
+/// IO-pattern. Since the IO-patterns of both schemes are identical, the scheme
+/// is additionally encoded in the most significant byte of the
+/// domain-separator (see [`Padding::domain_sep`] and the
+/// [`domain`](crate::domain) module). This way, the same input hashed with
+/// different padding schemes never results in the same tag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Padding {
+    /// Append a single element of value one to the input (Poseidon-style
+    /// padding).
+    One,
+    /// Prefix the input with an element that encodes its length.
+    Length,
+}
+
+impl Padding {
+    /// The bits of the domain-separator that are reserved for encoding the
+    /// padding scheme, which are the bits of
+    /// [`RESERVED_MASK`](crate::domain::RESERVED_MASK).
+    pub const DOMAIN_MASK: u64 = RESERVED_MASK;
+
+    /// Returns the identifier of the padding scheme as encoded in the
+    /// domain-separator.
+    const fn id(&self) -> u8 {
+        match self {
+            Padding::One => PADDING_ONE_ID,
+            Padding::Length => PADDING_LENGTH_ID,
+        }
+    }
+
+    /// Encodes the padding scheme into the domain-separator.
+    ///
+    /// # Parameters
+    ///
+    /// - `domain_sep`: The domain separator chosen by the user.
+    ///
+    /// # Returns
+    ///
+    /// The domain-separator with the padding scheme stored in its most
+    /// significant byte, or an `Error` if `domain_sep` already uses any of
+    /// the bits of [`Padding::DOMAIN_MASK`].
+    pub const fn domain_sep(&self, domain_sep: u64) -> Result<u64, Error> {
+        construction_domain(self.id(), domain_sep)
+    }
+
+    /// Returns the IO-pattern for hashing `input_len` elements with this
+    /// padding scheme into `output_len` elements.
+    ///
+    /// # Parameters
+    ///
+    /// - `input_len`: The amount of elements of the unpadded input.
+    /// - `output_len`: The amount of elements to squeeze.
+    ///
+    /// # Returns
+    ///
+    /// The IO-pattern to start the sponge with.
+    pub fn iopattern(&self, input_len: usize, output_len: usize) -> Vec<Call> {
+        let mut iopattern = Vec::with_capacity(3);
+        match (self, input_len) {
+            (_, 0) => iopattern.push(Call::Absorb(1)),
+            (Padding::One, _) => {
+                iopattern.push(Call::Absorb(input_len));
+                iopattern.push(Call::Absorb(1));
+            }
+            (Padding::Length, _) => {
+                iopattern.push(Call::Absorb(1));
+                iopattern.push(Call::Absorb(input_len));
+            }
+        }
+        iopattern.push(Call::Squeeze(output_len));
+        iopattern
+    }
+
+    /// Absorbs the padded input into a sponge that was started with the
+    /// IO-pattern returned by [`Padding::iopattern`].
+    ///
+    /// # Parameters
+    ///
+    /// - `sponge`: The sponge to absorb the input into.
+    /// - `input`: The unpadded input.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    pub fn absorb<S, T, const W: usize>(
+        &self,
+        sponge: &mut Sponge<S, T, W>,
+        input: impl AsRef<[T]>,
+    ) -> Result<(), Error>
+    where
+        S: Safe<T, W>,
+        T: SpongeElement + From<u64>,
+    {
+        let input = input.as_ref();
+        let len = input.len();
+        match self {
+            Padding::One => {
+                if len > 0 {
+                    sponge.absorb(len, input)?;
+                }
+                sponge.absorb(1, [T::from(1)])
+            }
+            Padding::Length => {
+                sponge.absorb(1, [T::from(len as u64)])?;
+                match len {
+                    0 => Ok(()),
+                    _ => sponge.absorb(len, input),
+                }
+            }
+        }
+    }
+}
+
+impl<T> PaddingScheme<T> for Padding
+where
+    T: SpongeElement + From<u64>,
+{
+    fn id(&self) -> u8 {
+        Padding::id(self)
+    }
+
+    fn absorb_calls(&self, input_len: usize) -> Vec<Call> {
+        let mut iopattern = Padding::iopattern(self, input_len, 1);
+        iopattern.pop();
+        iopattern
+    }
+
+    fn absorb<S, const W: usize>(
+        &self,
+        sponge: &mut Sponge<S, T, W>,
+        input: &[T],
+    ) -> Result<(), Error>
+    where
+        S: Safe<T, W>,
+    {
+        Padding::absorb(self, sponge, input)
+    }
+}
+
+/// Hashes an input of arbitrary length into `output_len` elements, using the
+/// given padding scheme.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `padding`: The padding scheme to apply to the input.
+/// - `domain_sep`: The domain separator to be used, the padding scheme is
+///   encoded into it as described in [`Padding::domain_sep`].
+/// - `input`: The input to hash.
+/// - `output_len`: The amount of elements to squeeze.
+///
+/// # Returns
+///
+/// A result containing the hash output on success, or an `Error` if the
+/// domain-separator is invalid or the sponge failed.
+pub fn hash_padded<S, T, const W: usize>(
+    safe: S,
+    padding: impl PaddingScheme<T>,
+    domain_sep: u64,
+    input: impl AsRef<[T]>,
+    output_len: usize,
+) -> Result<Output<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let input = input.as_ref();
+    let mut sponge = Sponge::start(
+        safe,
+        PaddingScheme::iopattern(&padding, input.len(), output_len),
+        PaddingScheme::domain_sep(&padding, domain_sep)?,
+    )?;
+    sponge.absorb_padded(&padding, input)?;
+    sponge.squeeze(output_len)?;
+    sponge.finish()
+}
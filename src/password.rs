@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+use crate::domain::{construction_domain, PASSWORD_HASH_ID};
+use crate::{Call, Error, Output, Safe, Sponge, SpongeElement};
+
+/// Hashes a low-entropy secret, such as a password or a PIN, into
+/// `output_len` elements, applying `iterations` additional permutations to
+/// make brute-forcing the secret expensive.
+///
+/// The iteration count is a [`Call::Label`] of the IO-pattern, so that it is
+/// bound to the tag. The sponge first absorbs the length of the salt, then
+/// the salt and the password. Before squeezing the output, the state is
+/// permuted `iterations` times. Only the sponge state is kept in
+/// memory, independently of the iteration count. The construction is bound to
+/// the [`PASSWORD_HASH_ID`](crate::domain::PASSWORD_HASH_ID) in the most
+/// significant byte of the domain-separator.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `salt`: A unique, public salt.
+/// - `password`: The secret to hash.
+/// - `iterations`: The amount of additional permutations.
+/// - `output_len`: The amount of elements to squeeze.
+///
+/// # Returns
+///
+/// A result containing the output, which is erased from memory when dropped,
+/// on success, or an `Error` if the domain-separator or the lengths are
+/// invalid.
+pub fn hash_password<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    salt: impl AsRef<[T]>,
+    password: impl AsRef<[T]>,
+    iterations: u64,
+    output_len: usize,
+) -> Result<Zeroizing<Output<T>>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let salt = salt.as_ref();
+    let password = password.as_ref();
+
+    let mut iopattern = Vec::with_capacity(5);
+    iopattern.push(Call::Label(iterations));
+    iopattern.push(Call::Absorb(1));
+    for len in [salt.len(), password.len()] {
+        if len > 0 {
+            iopattern.push(Call::Absorb(len));
+        }
+    }
+    iopattern.push(Call::Squeeze(output_len));
+
+    let mut sponge = Sponge::start(
+        safe,
+        iopattern,
+        construction_domain(PASSWORD_HASH_ID, domain_sep)?,
+    )?;
+    sponge.absorb(1, [T::from(salt.len() as u64)])?;
+    for input in [salt, password] {
+        if !input.is_empty() {
+            sponge.absorb(input.len(), input)?;
+        }
+    }
+    for _ in 0..iterations {
+        sponge.permute();
+    }
+    sponge.squeeze(output_len)?;
+    sponge.finish().map(Zeroizing::new)
+}
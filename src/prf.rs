@@ -0,0 +1,107 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use crate::domain::{construction_domain, PRF_ID};
+use crate::{Call, Error, Output, Safe, Sponge, SpongeElement};
+
+/// Returns the IO-pattern of the [`prf`] for the given lengths.
+///
+/// The key is prefixed with its length, so that the boundary between the
+/// key and the input is unambiguous even though contiguous calls to absorb
+/// are aggregated in the tag.
+///
+/// # Parameters
+///
+/// - `key_len`: The amount of elements of the key.
+/// - `input_len`: The amount of elements of the input.
+/// - `output_len`: The amount of elements to squeeze.
+///
+/// # Returns
+///
+/// The IO-pattern used by the [`prf`].
+pub fn prf_io_pattern(
+    key_len: usize,
+    input_len: usize,
+    output_len: usize,
+) -> Vec<Call> {
+    let mut iopattern = Vec::with_capacity(4);
+    iopattern.push(Call::Absorb(1));
+    iopattern.push(Call::Absorb(key_len));
+    if input_len > 0 {
+        iopattern.push(Call::Absorb(input_len));
+    }
+    iopattern.push(Call::Squeeze(output_len));
+    iopattern
+}
+
+/// A keyed pseudo-random function, e.g. for deriving nullifiers or per-note
+/// randomness deterministically.
+///
+/// The sponge absorbs the length of the key, the key and the input, and
+/// squeezes `output_len` elements. The construction is bound to the
+/// [`PRF_ID`](crate::domain::PRF_ID) in the most significant byte of the
+/// domain-separator, so its outputs never collide with other constructions
+/// of this crate.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `key`: The secret key, at least one element.
+/// - `input`: The input to the function, possibly empty.
+/// - `output_len`: The amount of elements to squeeze.
+///
+/// # Returns
+///
+/// A result containing the output on success, or an `Error` if the
+/// domain-separator or the lengths are invalid.
+pub fn prf<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    key: impl AsRef<[T]>,
+    input: impl AsRef<[T]>,
+    output_len: usize,
+) -> Result<Output<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    keyed_hash(safe, PRF_ID, domain_sep, key, input, output_len)
+}
+
+/// Runs the sponge of the [`prf`] for the construction with the given
+/// identifier.
+pub(crate) fn keyed_hash<S, T, const W: usize>(
+    safe: S,
+    id: u8,
+    domain_sep: u64,
+    key: impl AsRef<[T]>,
+    input: impl AsRef<[T]>,
+    output_len: usize,
+) -> Result<Output<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let key = key.as_ref();
+    let input = input.as_ref();
+
+    let mut sponge = Sponge::start(
+        safe,
+        prf_io_pattern(key.len(), input.len(), output_len),
+        construction_domain(id, domain_sep)?,
+    )?;
+    sponge.absorb(1, [T::from(key.len() as u64)])?;
+    sponge.absorb(key.len(), key)?;
+    if !input.is_empty() {
+        sponge.absorb(input.len(), input)?;
+    }
+    sponge.squeeze(output_len)?;
+    sponge.finish()
+}
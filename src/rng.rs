@@ -0,0 +1,164 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use zeroize::Zeroize;
+
+use crate::domain::SPONGE_RNG;
+use crate::{embed_bytes, Call, Error, Safe, Sponge, SpongeElement};
+
+/// The IO-pattern of one step of the generator: absorb the key and the
+/// counter, squeeze the next key and the generated element.
+const STEP_IOPATTERN: &[Call] = &[Call::Absorb(2), Call::Squeeze(2)];
+
+/// A deterministic generator of elements seeded from plain bytes, e.g. for
+/// reproducible randomness in test suites.
+///
+/// The seed is embedded with [`embed_bytes`] and hashed, together with its
+/// length in bytes, into the initial key under the crate-defined
+/// [`SPONGE_RNG`](crate::domain::SPONGE_RNG) domain-separator. Every element
+/// is then generated by a sponge with a fixed IO-pattern, which absorbs the
+/// key and a counter and squeezes the next key and the element. Since the
+/// seed is encoded explicitly and the patterns are fixed, a seed generates
+/// the same elements on every platform and in every version of the crate.
+///
+/// The generator is only as unpredictable as its seed, a seed from a
+/// test-suite is public and must not be used for secrets.
+pub struct SpongeRng<S, T, const W: usize>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    safe: S,
+    key: T,
+    counter: u64,
+    _marker: PhantomData<[T; W]>,
+}
+
+impl<S, T, const W: usize> SpongeRng<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    /// Creates a generator from a `u64` seed, which is the same as seeding it
+    /// with [`SpongeRng::from_bytes`] with the 8 bytes of the seed in big
+    /// endian.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `seed`: The seed of the generator.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the generator, or an `Error` if the sponge failed.
+    pub fn from_seed(safe: S, seed: u64) -> Result<Self, Error> {
+        Self::from_bytes(safe, seed.to_be_bytes())
+    }
+
+    /// Creates a generator from a seed of arbitrary bytes.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `seed`: The seed of the generator, which may be empty.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the generator, or an `Error` if the sponge failed.
+    pub fn from_bytes(safe: S, seed: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let seed = seed.as_ref();
+        let limbs: Vec<T> = embed_bytes(seed);
+
+        let mut iopattern = vec![Call::Absorb(1)];
+        if !limbs.is_empty() {
+            iopattern.push(Call::Absorb(limbs.len()));
+        }
+        iopattern.push(Call::Squeeze(1));
+
+        let mut sponge = Sponge::start(safe.clone(), iopattern, SPONGE_RNG)?;
+        sponge.absorb(1, [T::from(seed.len() as u64)])?;
+        if !limbs.is_empty() {
+            sponge.absorb(limbs.len(), &limbs)?;
+        }
+        sponge.squeeze(1)?;
+        let key = sponge.finish()?[0];
+
+        Ok(Self {
+            safe,
+            key,
+            counter: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Generates the next element.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the element, or an `Error` if the sponge failed.
+    pub fn next_element(&mut self) -> Result<T, Error> {
+        let mut sponge = Sponge::start_static(
+            self.safe.clone(),
+            STEP_IOPATTERN,
+            SPONGE_RNG,
+        )?;
+        sponge.absorb(2, [self.key, T::from(self.counter)])?;
+        sponge.squeeze(2)?;
+        let mut output = sponge.finish()?;
+
+        // the previous key is overwritten and therefore erased
+        self.key = output[0];
+        let element = output[1];
+        output.zeroize();
+        self.counter += 1;
+
+        Ok(element)
+    }
+
+    /// Fills the buffer with the next generated elements, in order.
+    ///
+    /// # Parameters
+    ///
+    /// - `dest`: The buffer to fill.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success, or an `Error` if the sponge failed.
+    pub fn fill(&mut self, dest: &mut [T]) -> Result<(), Error> {
+        for element in dest.iter_mut() {
+            *element = self.next_element()?;
+        }
+        Ok(())
+    }
+}
+
+// the key is omitted so that the upcoming elements aren't revealed
+impl<S, T, const W: usize> fmt::Debug for SpongeRng<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpongeRng")
+            .field("counter", &self.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, T, const W: usize> Drop for SpongeRng<S, T, W>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
@@ -0,0 +1,191 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use crate::{validate_io_pattern, Call, Error};
+
+/// A permutation of the state, as scheduled by a [`PatternSchedule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledPermutation {
+    /// The index of the call in the IO-pattern during which the permutation
+    /// occurs.
+    pub call_index: usize,
+    /// The amount of elements the call absorbed or squeezed before the
+    /// permutation.
+    pub offset: usize,
+}
+
+/// The exact points at which a [`Sponge`](crate::Sponge) of a given width
+/// permutes its state when following an IO-pattern, e.g. for preallocating
+/// the constraints of a gadget or for auditing the scheduling.
+///
+/// The schedule mirrors the sponge: a call to absorb permutes the state
+/// before adding an element when the rate is full, and a call to squeeze
+/// permutes the state before extracting an element when all elements of the
+/// rate have been squeezed or something was absorbed since the last
+/// permutation. Labels never permute the state, while ratchets always permute
+/// it once. Sponges in full duplex mode follow the schedule computed with
+/// [`PatternSchedule::new_duplex`].
+///
+/// The schedule assumes the default capacity of one element. For a backend
+/// with a larger [`Safe::CAPACITY`](crate::Safe::CAPACITY), compute it for
+/// the width `W - CAPACITY + 1`, which has the same rate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternSchedule {
+    permutations: Vec<ScheduledPermutation>,
+}
+
+impl PatternSchedule {
+    /// Computes the schedule of the permutations for a sponge of width `W`.
+    ///
+    /// # Parameters
+    ///
+    /// - `iopattern`: The IO-pattern of the sponge.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the schedule on success, or an `Error` if the
+    /// IO-pattern is invalid.
+    pub fn new<const W: usize>(
+        iopattern: impl AsRef<[Call]>,
+    ) -> Result<Self, Error> {
+        Self::compute::<W>(iopattern.as_ref(), false)
+    }
+
+    /// Computes the schedule of the permutations for a sponge of width `W`
+    /// in full duplex mode, as started with
+    /// [`Sponge::start_duplex`](crate::Sponge::start_duplex).
+    ///
+    /// In full duplex mode, absorb and squeeze share one position in the
+    /// rate, and a call to squeeze permutes the state before extracting an
+    /// element when the rate is full or something was absorbed since the last
+    /// permutation.
+    ///
+    /// # Parameters
+    ///
+    /// - `iopattern`: The IO-pattern of the sponge.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the schedule on success, or an `Error` if the
+    /// IO-pattern is invalid.
+    pub fn new_duplex<const W: usize>(
+        iopattern: impl AsRef<[Call]>,
+    ) -> Result<Self, Error> {
+        Self::compute::<W>(iopattern.as_ref(), true)
+    }
+
+    /// Computes the schedule by following the positions of the sponge in the
+    /// given mode.
+    fn compute<const W: usize>(
+        iopattern: &[Call],
+        duplex: bool,
+    ) -> Result<Self, Error> {
+        let rate = Rate::<W>::RATE;
+        validate_io_pattern(iopattern)?;
+
+        let mut permutations = Vec::new();
+        let mut pos_absorb = 0;
+        let mut pos_squeeze = 0;
+        for (call_index, call) in iopattern.iter().enumerate() {
+            match call {
+                Call::Absorb(len) => {
+                    for offset in 0..*len {
+                        if pos_absorb == rate {
+                            permutations.push(ScheduledPermutation {
+                                call_index,
+                                offset,
+                            });
+                            pos_absorb = 0;
+                        }
+                        pos_absorb += 1;
+                    }
+                    pos_squeeze = rate;
+                }
+                // in full duplex mode, squeezing advances the
+                // absorb-position, while a squeeze-position at the rate
+                // marks that something was absorbed since the last
+                // permutation
+                Call::Squeeze(len) if duplex => {
+                    for offset in 0..*len {
+                        if pos_absorb == rate || pos_squeeze == rate {
+                            permutations.push(ScheduledPermutation {
+                                call_index,
+                                offset,
+                            });
+                            pos_squeeze = 0;
+                            pos_absorb = 0;
+                        }
+                        pos_absorb += 1;
+                    }
+                }
+                Call::Squeeze(len) => {
+                    for offset in 0..*len {
+                        if pos_squeeze == rate {
+                            permutations.push(ScheduledPermutation {
+                                call_index,
+                                offset,
+                            });
+                            pos_squeeze = 0;
+                            pos_absorb = 0;
+                        }
+                        pos_squeeze += 1;
+                    }
+                }
+                Call::Label(_) => {}
+                Call::Ratchet => {
+                    permutations.push(ScheduledPermutation {
+                        call_index,
+                        offset: 0,
+                    });
+                    pos_absorb = 0;
+                    pos_squeeze = rate;
+                }
+            }
+        }
+
+        Ok(Self { permutations })
+    }
+
+    /// Returns all scheduled permutations, in the order they occur.
+    pub fn permutations(&self) -> &[ScheduledPermutation] {
+        &self.permutations
+    }
+
+    /// Returns the total amount of permutations.
+    pub fn permutation_count(&self) -> usize {
+        self.permutations.len()
+    }
+
+    /// Returns the amount of permutations that occur during a call.
+    ///
+    /// # Parameters
+    ///
+    /// - `call_index`: The index of the call in the IO-pattern.
+    ///
+    /// # Returns
+    ///
+    /// The amount of permutations during the call, zero if the call doesn't
+    /// exist.
+    pub fn call_permutations(&self, call_index: usize) -> usize {
+        self.permutations
+            .iter()
+            .filter(|permutation| permutation.call_index == call_index)
+            .count()
+    }
+}
+
+/// The rate of a sponge of width `W`, failing to compile when the width
+/// doesn't exceed the capacity.
+struct Rate<const W: usize>;
+
+impl<const W: usize> Rate<W> {
+    const RATE: usize = {
+        assert!(W > 1, "the width needs to exceed the capacity");
+        W - 1
+    };
+}
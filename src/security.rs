@@ -0,0 +1,198 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use crate::{Call, Error, Safe, Sponge, SpongeElement};
+
+/// Target levels of security for the sponge parameters.
+///
+/// The recommendations follow the generic security bounds of the sponge
+/// construction: a sponge with a capacity of `c` elements over a field with
+/// `n`-bit elements offers `c * n / 2` bits of security, an output of `l`
+/// elements offers `l * n / 2` bits of collision resistance and a tag of `l`
+/// elements resists forgeries with `l * n` bits of security.
+///
+/// As in the parameters of Poseidon, a level counts as reached when the
+/// bound falls short of it by less than one bit, so that a single element of
+/// a 255-bit field offers 128 bits of security.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecurityLevel {
+    /// 128 bits of security.
+    Bits128,
+    /// 192 bits of security.
+    Bits192,
+    /// 256 bits of security.
+    Bits256,
+}
+
+impl SecurityLevel {
+    /// Returns the targeted bits of security.
+    pub const fn bits(&self) -> usize {
+        match self {
+            SecurityLevel::Bits128 => 128,
+            SecurityLevel::Bits192 => 192,
+            SecurityLevel::Bits256 => 256,
+        }
+    }
+
+    /// Returns the minimal capacity, in elements, for reaching the security
+    /// level.
+    ///
+    /// # Parameters
+    ///
+    /// - `field_bits`: The size of the field elements in bits.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the capacity, or an `Error` if `field_bits` is
+    /// zero.
+    pub const fn capacity(&self, field_bits: usize) -> Result<usize, Error> {
+        elements(2 * self.bits() - 1, field_bits)
+    }
+
+    /// Returns the minimal amount of elements to squeeze for an output, such
+    /// as a digest, that is collision resistant at the security level.
+    ///
+    /// # Parameters
+    ///
+    /// - `field_bits`: The size of the field elements in bits.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the length of the output, or an `Error` if
+    /// `field_bits` is zero.
+    pub const fn output_len(&self, field_bits: usize) -> Result<usize, Error> {
+        elements(2 * self.bits() - 1, field_bits)
+    }
+
+    /// Returns the minimal amount of elements of an authentication tag, such
+    /// as the tag of a cipher, that resists forgeries at the security level.
+    ///
+    /// # Parameters
+    ///
+    /// - `field_bits`: The size of the field elements in bits.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the length of the tag, or an `Error` if
+    /// `field_bits` is zero.
+    pub const fn tag_len(&self, field_bits: usize) -> Result<usize, Error> {
+        elements(self.bits(), field_bits)
+    }
+
+    /// Returns the maximal amount of elements that may be squeezed from a
+    /// keyed sponge with the given capacity while keeping the security level,
+    /// e.g. for the key-stream of an encryption.
+    ///
+    /// An adversary evaluating the permutation `q` times distinguishes a
+    /// keyed sponge that output `m` elements with an advantage of about
+    /// `m * q / 2^(c * n)`, which stays below `2^-bits` for all
+    /// `q < 2^bits` as long as `m <= 2^(c * n - bits)`. A keyed sponge may
+    /// therefore use a smaller capacity than [`Self::capacity`] when it
+    /// squeezes few elements. The result saturates at `usize::MAX`.
+    ///
+    /// # Parameters
+    ///
+    /// - `field_bits`: The size of the field elements in bits.
+    /// - `capacity`: The capacity of the sponge in elements.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the maximal amount of squeezed elements, or an
+    /// `Error` if `field_bits` is zero or the capacity holds fewer bits than
+    /// the security level.
+    pub const fn max_squeeze_len(
+        &self,
+        field_bits: usize,
+        capacity: usize,
+    ) -> Result<usize, Error> {
+        if field_bits == 0 {
+            return Err(Error::InvalidFieldSize);
+        }
+        let capacity_bits = capacity.saturating_mul(field_bits);
+        if capacity_bits < self.bits() {
+            return Err(Error::InsufficientCapacity);
+        }
+        let margin = capacity_bits - self.bits();
+        match margin < usize::BITS as usize {
+            true => Ok(1 << margin),
+            false => Ok(usize::MAX),
+        }
+    }
+
+    /// Checks that the [`Sponge`] with the given parameters reaches the
+    /// security level.
+    ///
+    /// # Parameters
+    ///
+    /// - `field_bits`: The size of the field elements in bits.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the capacity of the sponge is
+    /// sufficient, or an `Error` otherwise.
+    pub fn check<S, T, const W: usize>(
+        &self,
+        field_bits: usize,
+    ) -> Result<(), Error>
+    where
+        S: Safe<T, W>,
+        T: SpongeElement,
+    {
+        match Sponge::<S, T, W>::CAPACITY >= self.capacity(field_bits)? {
+            true => Ok(()),
+            false => Err(Error::InsufficientCapacity),
+        }
+    }
+
+    /// Checks that a [`Sponge`] with the given parameters reaches the
+    /// security level when following the IO-pattern, i.e. that its capacity
+    /// is sufficient and that it squeezes no more than
+    /// [`Self::max_squeeze_len`] elements.
+    ///
+    /// # Parameters
+    ///
+    /// - `field_bits`: The size of the field elements in bits.
+    /// - `iopattern`: The IO-pattern of the sponge.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the sponge reaches the security level,
+    /// or an `Error` otherwise.
+    pub fn check_pattern<S, T, const W: usize>(
+        &self,
+        field_bits: usize,
+        iopattern: impl AsRef<[Call]>,
+    ) -> Result<(), Error>
+    where
+        S: Safe<T, W>,
+        T: SpongeElement,
+    {
+        self.check::<S, T, W>(field_bits)?;
+        let max_squeeze_len =
+            self.max_squeeze_len(field_bits, Sponge::<S, T, W>::CAPACITY)?;
+        let squeeze_len = iopattern
+            .as_ref()
+            .iter()
+            .map(|call| match call {
+                Call::Squeeze(len) => *len,
+                _ => 0,
+            })
+            .fold(0usize, usize::saturating_add);
+        match squeeze_len <= max_squeeze_len {
+            true => Ok(()),
+            false => Err(Error::InsufficientCapacity),
+        }
+    }
+}
+
+/// Returns the amount of `field_bits`-sized elements that hold at least the
+/// given amount of bits.
+const fn elements(bits: usize, field_bits: usize) -> Result<usize, Error> {
+    match field_bits {
+        0 => Err(Error::InvalidFieldSize),
+        _ => Ok((bits + field_bits - 1) / field_bits),
+    }
+}
@@ -0,0 +1,174 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#[cfg(feature = "encryption")]
+use crate::{decrypt, encrypt, Encryption};
+use crate::{
+    with_tag_input, Call, EncodingProfile, Error, Safe, Sponge, SpongeElement,
+    TagVersion,
+};
+
+/// The domain-separator used by the checks of the self-test.
+const SELF_TEST_DOMAIN: u64 = 0x0102_0304_0506_0708;
+
+/// The IO-pattern used by the checks of the self-test.
+const SELF_TEST_IOPATTERN: [Call; 3] =
+    [Call::Absorb(1), Call::Absorb(2), Call::Squeeze(3)];
+
+/// The known encoding of the tag input for the [`SELF_TEST_IOPATTERN`] and
+/// the [`SELF_TEST_DOMAIN`].
+const SELF_TEST_TAG_INPUT: [u8; 16] = [
+    0x80, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03, 0x04,
+    0x05, 0x06, 0x07, 0x08,
+];
+
+/// Runs a small set of health checks against the backend, e.g. at start-up
+/// of a deployed validator.
+///
+/// The checks are:
+/// - the encoding of the tag input matches a known answer,
+/// - the output of the sponge matches the known answer of the backend,
+/// - hashing the same input twice yields the same output.
+///
+/// The known answer is the output of a sponge with the IO-pattern
+/// `[Call::Absorb(1), Call::Absorb(2), Call::Squeeze(3)]` and the
+/// domain-separator `0x0102_0304_0506_0708`, absorbing `[1]` and `[2, 3]`
+/// converted with `T::from`. It depends on the backend and is computed once
+/// from a reference implementation of the permutation, so that a backend
+/// computing a different permutation than intended fails the self-test.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] trait.
+/// - `expected`: The known answer of the backend.
+///
+/// # Returns
+///
+/// A result indicating success if all checks passed, or
+/// [`Error::SelfTestFailed`] otherwise.
+pub fn self_test<S, T, const W: usize>(
+    safe: S,
+    expected: &[T; 3],
+) -> Result<(), Error>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64> + PartialEq,
+{
+    let passed =
+        check_tag_input() && check_sponge(&safe, expected).unwrap_or(false);
+    match passed {
+        true => Ok(()),
+        false => Err(Error::SelfTestFailed),
+    }
+}
+
+/// Runs the health checks of [`self_test`], and additionally checks that
+/// decrypting an encrypted message yields the message, while decrypting a
+/// tampered cipher-text fails.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `expected`: The known answer of the backend, see [`self_test`].
+///
+/// # Returns
+///
+/// A result indicating success if all checks passed, or
+/// [`Error::SelfTestFailed`] otherwise.
+#[cfg(feature = "encryption")]
+pub fn self_test_encryption<S, T, const W: usize>(
+    safe: S,
+    expected: &[T; 3],
+) -> Result<(), Error>
+where
+    S: Safe<T, W> + Encryption<T, W> + Clone,
+    T: SpongeElement + From<u64> + PartialEq,
+{
+    self_test(safe.clone(), expected)?;
+    match check_encryption(&safe).unwrap_or(false) {
+        true => Ok(()),
+        false => Err(Error::SelfTestFailed),
+    }
+}
+
+/// Checks the encoding of the tag input against the known answer.
+fn check_tag_input() -> bool {
+    with_tag_input(
+        &SELF_TEST_IOPATTERN,
+        SELF_TEST_DOMAIN,
+        TagVersion::V0,
+        EncodingProfile::SAFE,
+        |input| input[..] == SELF_TEST_TAG_INPUT[..],
+    )
+    .unwrap_or(false)
+}
+
+/// Checks that the sponge is deterministic and that its output matches the
+/// known answer.
+fn check_sponge<S, T, const W: usize>(
+    safe: &S,
+    expected: &[T; 3],
+) -> Result<bool, Error>
+where
+    S: Safe<T, W> + Clone,
+    T: SpongeElement + From<u64> + PartialEq,
+{
+    let hash = || -> Result<[T; 3], Error> {
+        let mut sponge = Sponge::start_static(
+            safe.clone(),
+            &SELF_TEST_IOPATTERN,
+            SELF_TEST_DOMAIN,
+        )?;
+        sponge.absorb(1, [T::from(1)])?;
+        sponge.absorb(2, [T::from(2), T::from(3)])?;
+        sponge.squeeze(3)?;
+        sponge.finish_array()
+    };
+
+    let output = hash()?;
+    Ok(output == *expected && output == hash()?)
+}
+
+/// Checks that a message survives the encryption and that tampering with the
+/// cipher-text is detected.
+#[cfg(feature = "encryption")]
+fn check_encryption<S, T, const W: usize>(safe: &S) -> Result<bool, Error>
+where
+    S: Safe<T, W> + Encryption<T, W> + Clone,
+    T: SpongeElement + From<u64> + PartialEq,
+{
+    let message = [T::from(1), T::from(2), T::from(3)];
+    let shared_secret = [T::from(4), T::from(5)];
+    let nonce = T::from(6);
+
+    let cipher = encrypt(
+        safe.clone(),
+        SELF_TEST_DOMAIN,
+        message,
+        &shared_secret,
+        &nonce,
+    )?;
+    let decrypted = decrypt(
+        safe.clone(),
+        SELF_TEST_DOMAIN,
+        &cipher,
+        &shared_secret,
+        &nonce,
+    )?;
+
+    let mut tampered = cipher.clone();
+    tampered[0] = safe.clone().add(&cipher[0], &T::from(1));
+    let tampered_fails = decrypt(
+        safe.clone(),
+        SELF_TEST_DOMAIN,
+        &tampered,
+        &shared_secret,
+        &nonce,
+    )
+    .is_err();
+
+    Ok(decrypted[..] == message[..] && tampered_fails)
+}
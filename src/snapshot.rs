@@ -0,0 +1,84 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+/// A copy of the state of a sponge, taken when it started and after every
+/// call to absorb or squeeze, see
+/// [`Sponge::snapshots`](crate::Sponge::snapshots).
+///
+/// Snapshots contain the secret state of the sponge and are only meant for
+/// debugging, e.g. for chasing nondeterminism between two versions of the
+/// library or between platforms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot<T, const W: usize> {
+    /// The index of the call in the IO-pattern after which the snapshot was
+    /// taken, or `None` for the state the sponge started with.
+    pub call_index: Option<usize>,
+    /// The state of the sponge.
+    pub state: [T; W],
+}
+
+/// The first point at which two runs of a sponge diverge, as found by
+/// [`first_divergence`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Divergence {
+    /// The index of the snapshot at which the runs diverge.
+    pub snapshot: usize,
+    /// The index of the call in the IO-pattern after which the runs diverge,
+    /// or `None` if they already start with different states.
+    pub call_index: Option<usize>,
+    /// The position of the first element of the state that differs, or
+    /// `None` if the runs diverge in their calls or one run has fewer
+    /// snapshots than the other.
+    pub position: Option<usize>,
+}
+
+/// Compares the snapshots of two runs of a sponge and finds the first point
+/// at which they diverge.
+///
+/// # Parameters
+///
+/// - `left`: The snapshots of the first run.
+/// - `right`: The snapshots of the second run.
+///
+/// # Returns
+///
+/// The first [`Divergence`] of the runs, or `None` if they are identical.
+pub fn first_divergence<T, const W: usize>(
+    left: &[Snapshot<T, W>],
+    right: &[Snapshot<T, W>],
+) -> Option<Divergence>
+where
+    T: PartialEq,
+{
+    for (snapshot, (l, r)) in left.iter().zip(right).enumerate() {
+        if l.call_index != r.call_index {
+            return Some(Divergence {
+                snapshot,
+                call_index: l.call_index,
+                position: None,
+            });
+        }
+        let position = l.state.iter().zip(&r.state).position(|(l, r)| l != r);
+        if position.is_some() {
+            return Some(Divergence {
+                snapshot,
+                call_index: l.call_index,
+                position,
+            });
+        }
+    }
+
+    // the runs agree as far as both go, but one of them may be longer
+    let len = left.len().min(right.len());
+    match left.len() == right.len() {
+        true => None,
+        false => Some(Divergence {
+            snapshot: len,
+            call_index: left.get(len).or(right.get(len))?.call_index,
+            position: None,
+        }),
+    }
+}
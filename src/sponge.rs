@@ -4,10 +4,68 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 use zeroize::Zeroize;
+#[cfg(feature = "alloc")]
+use zeroize::Zeroizing;
 
-use crate::{tag_input, Call, Error};
+#[cfg(feature = "audit")]
+use crate::audit::{record, AuditEntry};
+
+use crate::domain::{
+    construction_domain, SESSION_HASH, TRANSCRIPT_COMMITMENT, UNBOUNDED_ID,
+};
+#[cfg(feature = "alloc")]
+use crate::domain::{DUPLEX_ID, WIDE_DOMAIN_ID};
+#[cfg(feature = "test-utils")]
+use crate::Snapshot;
+use crate::{
+    critical_check, pattern_digest, with_tag_input, Absorbable, Call,
+    EncodingProfile, Error, FieldWriter, Output, OutputMode, SqueezeInto,
+    SyncPoint, TagVersion, TAG_INPUT_STACK_LEN,
+};
+#[cfg(feature = "alloc")]
+use crate::{
+    tag_input_elements, validate_io_pattern, wide_tag_input, Embed,
+    PaddingScheme, SecurityLevel,
+};
+
+/// The bounds every element of a sponge needs to satisfy.
+///
+/// This trait is implemented for every type that is `Default + Copy +
+/// Zeroize`, so that downstream crates can use it in their own bounds instead
+/// of repeating them. Together with the re-export of [`zeroize`] it keeps the
+/// bounds in sync with the version of `zeroize` used by this crate.
+pub trait SpongeElement: Default + Copy + Zeroize {}
+
+impl<T> SpongeElement for T where T: Default + Copy + Zeroize {}
+
+/// Determines how the elements that are absorbed are combined with the rate
+/// of the state, see [`Safe::ABSORB_MODE`].
+///
+/// Overwriting is a known variant of the sponge with the same generic
+/// security as adding, since absorbing never touches the capacity. The
+/// modes differ in cost only: in
+/// overwrite-mode, absorbing an element doesn't need an addition, which
+/// saves a constraint per absorbed element in a circuit, while natively the
+/// difference is usually negligible next to the permutation. Which mode is
+/// cheaper depends on the permutation and on the circuit builder, so
+/// integrators should measure both, e.g. with the `absorb_mode` benchmark of
+/// this crate for the native cost, and by counting the constraints of their
+/// circuit for the in-circuit cost. Since the mode changes the output of the
+/// sponge, all parties of a protocol need to select the same mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AbsorbMode {
+    /// Add the absorbed element to the element of the state with
+    /// [`Safe::add`].
+    #[default]
+    Add,
+    /// Overwrite the element of the state with the absorbed element.
+    Overwrite,
+}
 
 /// This trait defines the behavior of a sponge algorithm.
 ///
@@ -15,11 +73,25 @@ use crate::{tag_input, Call, Error};
 /// zero-knowledge circuits.
 pub trait Safe<T, const W: usize>
 where
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
 {
     /// Apply one permutation to the state.
     fn permute(&mut self, state: &mut [T; W]);
 
+    /// Apply one permutation to each of the states, e.g. for verifying many
+    /// merkle paths at once.
+    ///
+    /// This defaults to calling [`Self::permute`] on every state. Backends
+    /// that permute several states faster at once, e.g. with SIMD
+    /// instructions or on a GPU, can override it.
+    ///
+    /// # Parameters
+    ///
+    /// - `states`: The states to permute.
+    fn permute_batch(&mut self, states: &mut [[T; W]]) {
+        states.iter_mut().for_each(|state| self.permute(state));
+    }
+
     /// Create the tag by hashing the tag input to an element of type `T`.
     ///
     /// # Parameters
@@ -44,6 +116,33 @@ where
     /// The result of the addition, of type `T`.
     fn add(&mut self, right: &T, left: &T) -> T;
 
+    /// Combine an element of the state with an element that is absorbed into
+    /// it and return the new element of the state.
+    ///
+    /// This defaults to [`Self::add`], or to returning `element` when
+    /// [`Self::ABSORB_MODE`] is [`AbsorbMode::Overwrite`]. Implementations
+    /// can override it for other modes, e.g. XOR-mode for wrapping binary
+    /// permutations.
+    ///
+    /// # Parameters
+    ///
+    /// - `state`: The element of the state.
+    /// - `element`: The element that is absorbed.
+    ///
+    /// # Returns
+    ///
+    /// The new element of the state, of type `T`.
+    fn combine(&mut self, state: &T, element: &T) -> T {
+        match Self::ABSORB_MODE {
+            AbsorbMode::Add => self.add(state, element),
+            AbsorbMode::Overwrite => *element,
+        }
+    }
+
+    /// How [`Self::combine`] absorbs elements into the state by default,
+    /// [`AbsorbMode::Add`] unless selected otherwise, see [`AbsorbMode`].
+    const ABSORB_MODE: AbsorbMode = AbsorbMode::Add;
+
     /// Create a state and initialize it with the tag and default values of `T`.
     ///
     /// # Parameters
@@ -58,38 +157,165 @@ where
         state[0] = tag;
         state
     }
+
+    /// The amount of elements at the beginning of the state that form the
+    /// capacity of the sponge, one by default.
+    ///
+    /// Backends over small elements, such as bytes, need a capacity of
+    /// several elements for reaching their level of security.
+    const CAPACITY: usize = 1;
+
+    /// Create the initial state of a sponge from the tag input.
+    ///
+    /// This defaults to [`Self::initialized_state`] with the [`Self::tag`] of
+    /// the input. With a capacity of several elements, every further element
+    /// `i` of the capacity is set to the tag of the input followed by `i` as
+    /// 8 bytes big endian, so that the tag input determines all elements of
+    /// the capacity. Backends can override it, e.g. for squeezing all
+    /// elements from a single hash of the input.
+    ///
+    /// # Parameters
+    ///
+    /// - `input`: The domain-separator and IO-pattern encoded as a slice of
+    ///   bytes.
+    ///
+    /// # Returns
+    ///
+    /// An array of type `[T; W]` representing the initialized state.
+    ///
+    /// # Panics
+    ///
+    /// The default implementation panics without the `alloc` feature if the
+    /// capacity has several elements and the input exceeds 256 bytes, which
+    /// the tag input of a sponge never does.
+    fn tagged_state(&mut self, input: &[u8]) -> [T; W] {
+        let mut state = Self::initialized_state(self.tag(input));
+        tag_capacity(&mut state, Self::CAPACITY, input, |i| self.tag(i));
+        state
+    }
+}
+
+/// Sets the elements of the capacity after the first one to the tag of the
+/// tag input followed by their index, see [`Safe::tagged_state`].
+pub(crate) fn tag_capacity<T, const W: usize>(
+    state: &mut [T; W],
+    capacity: usize,
+    input: &[u8],
+    mut tag: impl FnMut(&[u8]) -> T,
+) {
+    if capacity < 2 {
+        return;
+    }
+    // the tag input of a sponge always fits on the stack, only longer inputs
+    // passed to `Safe::tagged_state` directly need the heap
+    let mut buf = [0u8; TAG_INPUT_STACK_LEN + 8];
+    #[cfg(feature = "alloc")]
+    let mut heap = Vec::new();
+    let indexed = match buf.get_mut(..input.len() + 8) {
+        Some(indexed) => indexed,
+        #[cfg(feature = "alloc")]
+        None => {
+            heap.resize(input.len() + 8, 0);
+            &mut heap[..]
+        }
+        #[cfg(not(feature = "alloc"))]
+        None => panic!("the tag input exceeds {TAG_INPUT_STACK_LEN} bytes"),
+    };
+    indexed[..input.len()].copy_from_slice(input);
+    for (i, element) in state.iter_mut().enumerate().take(capacity).skip(1) {
+        indexed[input.len()..].copy_from_slice(&(i as u64).to_be_bytes());
+        *element = tag(indexed);
+    }
+}
+
+/// The IO-pattern of a sponge, borrowed when it is `'static` and owned
+/// otherwise.
+#[cfg(feature = "alloc")]
+type Pattern = Cow<'static, [Call]>;
+
+/// The IO-pattern of a sponge, which is always `'static` without the `alloc`
+/// feature.
+#[cfg(not(feature = "alloc"))]
+#[derive(Debug, Clone, PartialEq)]
+struct Pattern(&'static [Call]);
+
+#[cfg(not(feature = "alloc"))]
+impl From<&'static [Call]> for Pattern {
+    fn from(iopattern: &'static [Call]) -> Self {
+        Self(iopattern)
+    }
+}
+
+#[cfg(not(feature = "alloc"))]
+impl core::ops::Deref for Pattern {
+    type Target = [Call];
+
+    fn deref(&self) -> &[Call] {
+        self.0
+    }
 }
 
 /// Struct that implements the Sponge API over field elements.
 ///
-/// The capacity is fixed to one field element and the rate are `W - 1` field
-/// elements.
-#[derive(Debug, Clone, PartialEq)]
+/// The capacity are the first [`Safe::CAPACITY`] elements of the state, one
+/// field element by default, and the rate are the remaining elements.
+///
+/// The sponge doesn't implement `Clone`, so that its secret state can't be
+/// duplicated by accident, see [`Sponge::checkpoint`] for copying it
+/// deliberately. For the same reason, sponges are compared in constant time
+/// with `subtle::ConstantTimeEq` behind the `subtle` feature, while the
+/// variable time `PartialEq` is only available behind the `test-utils`
+/// feature.
+///
+/// A sponge doesn't need to allocate: when it is started from a `'static`
+/// IO-pattern with [`Sponge::start_static`] whose tag input fits into 256
+/// bytes, the tag input is encoded on the stack, also for every element of a
+/// capacity of several elements. As long as the squeezed elements are written
+/// into caller-provided buffers with [`Sponge::squeeze_into`] or
+/// [`Sponge::squeeze_to`], or the output of [`Sponge::finish`] holds no more
+/// than four elements, no memory is allocated on the heap. Without the
+/// `alloc` feature, the sponge is limited to this. The `audit` and
+/// `test-utils` features record the calls and allocate regardless.
+#[derive(Debug)]
+#[cfg_attr(feature = "test-utils", derive(PartialEq))]
 pub struct Sponge<S, T, const W: usize>
 where
     S: Safe<T, W>,
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
 {
     state: [T; W],
     pub(crate) safe: S,
     pos_absorb: usize,
     pos_squeeze: usize,
     io_count: usize,
-    iopattern: Vec<Call>,
+    partial: usize,
+    iopattern: Pattern,
     domain_sep: u64,
-    pub(crate) output: Vec<T>,
+    version: TagVersion,
+    unbounded: bool,
+    duplex: bool,
+    pub(crate) output: Output<T>,
+    output_mode: OutputMode,
+    #[cfg(feature = "audit")]
+    audit_log: Vec<AuditEntry<T>>,
+    #[cfg(feature = "test-utils")]
+    snapshots: Vec<Snapshot<T, W>>,
 }
 
 impl<S, T, const W: usize> Sponge<S, T, W>
 where
     S: Safe<T, W>,
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
 {
-    /// The capacity of the sponge.
-    const CAPACITY: usize = 1;
+    /// The capacity of the sponge, see [`Safe::CAPACITY`].
+    pub const CAPACITY: usize = S::CAPACITY;
 
     /// The rate of the sponge.
-    const RATE: usize = W - Self::CAPACITY;
+    pub const RATE: usize = W - Self::CAPACITY;
+
+    /// The IO-pattern of the child sponge of [`Self::state_hash`].
+    const STATE_HASH_IOPATTERN: &'static [Call] =
+        &[Call::Absorb(1), Call::Absorb(W), Call::Squeeze(1)];
 
     /// This initializes the sponge, setting the first element of the state to
     /// the [`Safe::tag()`] and the other elements to the default value of
@@ -105,164 +331,1846 @@ where
     ///
     /// A result containing the initialized Sponge on success, or an `Error` if
     /// the IO-pattern is invalid.
+    #[cfg(feature = "alloc")]
     pub fn start(
         safe: S,
         iopattern: impl Into<Vec<Call>>,
         domain_sep: u64,
+    ) -> Result<Self, Error> {
+        Self::start_with_version(safe, iopattern, domain_sep, TagVersion::V0)
+    }
+
+    /// This initializes the sponge like [`Self::start`] with a borrowed
+    /// IO-pattern, which also accepts a reference to a const `[Call; N]`.
+    /// The IO-pattern isn't copied into the sponge.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern for the sponge.
+    /// - `domain_sep`: The domain separator to be used.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the IO-pattern is invalid.
+    pub fn start_static(
+        safe: S,
+        iopattern: &'static [Call],
+        domain_sep: u64,
+    ) -> Result<Self, Error> {
+        Self::start_inner(
+            safe,
+            iopattern.into(),
+            domain_sep,
+            TagVersion::V0,
+            EncodingProfile::SAFE,
+        )
+    }
+
+    /// This initializes the sponge like [`Self::start`], but encodes the tag
+    /// input with the given version of the encoding.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern for the sponge.
+    /// - `domain_sep`: The domain separator to be used.
+    /// - `version`: The version of the encoding of the tag input.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the IO-pattern is invalid.
+    #[cfg(feature = "alloc")]
+    pub fn start_with_version(
+        safe: S,
+        iopattern: impl Into<Vec<Call>>,
+        domain_sep: u64,
+        version: TagVersion,
+    ) -> Result<Self, Error> {
+        Self::start_inner(
+            safe,
+            Cow::Owned(iopattern.into()),
+            domain_sep,
+            version,
+            EncodingProfile::SAFE,
+        )
+    }
+
+    /// This initializes the sponge like [`Self::start`], but lays out the tag
+    /// input as per the given encoding profile, e.g. for reproducing the tags
+    /// of an existing deployment that encodes the words little endian.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern for the sponge.
+    /// - `domain_sep`: The domain separator to be used.
+    /// - `profile`: The layout of the encoding of the tag input.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the IO-pattern is invalid.
+    #[cfg(feature = "alloc")]
+    pub fn start_with_profile(
+        safe: S,
+        iopattern: impl Into<Vec<Call>>,
+        domain_sep: u64,
+        profile: EncodingProfile,
+    ) -> Result<Self, Error> {
+        Self::start_inner(
+            safe,
+            Cow::Owned(iopattern.into()),
+            domain_sep,
+            TagVersion::V0,
+            profile,
+        )
+    }
+
+    /// This initializes the sponge like [`Self::start`], but first enforces
+    /// the security level, see [`SecurityLevel::check_pattern`].
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern for the sponge.
+    /// - `domain_sep`: The domain separator to be used.
+    /// - `level`: The security level the sponge needs to reach.
+    /// - `field_bits`: The size of the field elements in bits.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the IO-pattern is invalid or the sponge doesn't reach the security
+    /// level.
+    #[cfg(feature = "alloc")]
+    pub fn start_with_level(
+        safe: S,
+        iopattern: impl Into<Vec<Call>>,
+        domain_sep: u64,
+        level: SecurityLevel,
+        field_bits: usize,
+    ) -> Result<Self, Error> {
+        let iopattern = iopattern.into();
+        level.check_pattern::<S, T, W>(field_bits, &iopattern)?;
+        Self::start(safe, iopattern, domain_sep)
+    }
+
+    /// Initializes the sponge with the given version and layout of the
+    /// encoding of the tag input.
+    fn start_inner(
+        safe: S,
+        iopattern: Pattern,
+        domain_sep: u64,
+        version: TagVersion,
+        profile: EncodingProfile,
     ) -> Result<Self, Error> {
         // Compute the tag and initialize the state.
         // Note: This will return an error if the IO-pattern is invalid.
-        let iopattern: Vec<Call> = iopattern.into();
         let mut safe = safe;
-        let tag = safe.tag(&tag_input(&iopattern, domain_sep)?);
-        let state = S::initialized_state(tag);
+        let state = with_tag_input(
+            &iopattern,
+            domain_sep,
+            version,
+            profile,
+            |input| safe.tagged_state(input),
+        )?;
+        Ok(Self::with_state(
+            safe, iopattern, state, domain_sep, version,
+        ))
+    }
 
-        Ok(Self {
+    /// Initializes a sponge with the given initial state.
+    fn with_state(
+        safe: S,
+        iopattern: Pattern,
+        state: [T; W],
+        domain_sep: u64,
+        version: TagVersion,
+    ) -> Self {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            domain_sep,
+            calls = iopattern.len(),
+            version = version.to_byte(),
+            "sponge started"
+        );
+
+        #[cfg_attr(not(feature = "test-utils"), allow(unused_mut))]
+        let mut sponge = Self {
             state,
             safe,
             pos_absorb: 0,
             pos_squeeze: 0,
             io_count: 0,
+            partial: 0,
             iopattern,
             domain_sep,
-            output: Vec::new(),
-        })
+            version,
+            unbounded: false,
+            duplex: false,
+            output: Output::new(),
+            output_mode: OutputMode::Accumulate,
+            #[cfg(feature = "audit")]
+            audit_log: Vec::new(),
+            #[cfg(feature = "test-utils")]
+            snapshots: Vec::new(),
+        };
+        #[cfg(feature = "test-utils")]
+        sponge.take_snapshot(None);
+
+        sponge
     }
 
-    /// This marks the end of the sponge life, preventing any further operation.
-    /// In particular, the state is erased from memory.
+    /// This initializes a sponge like [`Self::start`], but with a byte-string
+    /// as domain-separator, e.g. for embedding structured identifiers like a
+    /// network id, a protocol name and its version without hashing them down
+    /// to 64 bits first.
+    ///
+    /// The byte-string and its length as 8 bytes big endian are inserted into
+    /// the tag input right before the domain-separator, which is set to
+    /// [`WIDE_DOMAIN_ID`](crate::domain::WIDE_DOMAIN_ID) encoded into its most
+    /// significant byte. This way the tags never collide with those of sponges
+    /// started with a `u64` domain-separator. The sponge reports that
+    /// domain-separator, e.g. in errors.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern for the sponge.
+    /// - `domain`: The byte-string domain-separator.
     ///
     /// # Returns
     ///
-    /// A result containing the output vector on success, or an `Error` if the
-    /// IO-pattern wasn't followed.
-    pub fn finish(mut self) -> Result<Vec<T>, Error> {
-        let ret = match self.io_count == self.iopattern.len() {
-            true => Ok(self.output.clone()),
-            false => Err(Error::IOPatternViolation),
-        };
-        // no matter the return, we erase the internal state of the sponge
-        self.zeroize();
-        ret
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the IO-pattern is invalid.
+    #[cfg(feature = "alloc")]
+    pub fn start_with_domain_bytes(
+        safe: S,
+        iopattern: impl Into<Vec<Call>>,
+        domain: impl AsRef<[u8]>,
+    ) -> Result<Self, Error> {
+        let iopattern = Cow::Owned(iopattern.into());
+        let mut safe = safe;
+        let state =
+            safe.tagged_state(&wide_tag_input(&iopattern, domain.as_ref())?);
+        let domain_sep = construction_domain(WIDE_DOMAIN_ID, 0)?;
+        Ok(Self::with_state(
+            safe,
+            iopattern,
+            state,
+            domain_sep,
+            TagVersion::V0,
+        ))
     }
 
-    /// This absorbs `len` field elements from the input into the state with
-    /// interleaving calls to the permutation function. It also checks if the
-    /// call matches the IO-pattern.
+    /// This initializes a sponge like [`Self::start`], but with a 128-bit
+    /// domain-separator. This is the same as using its 16 bytes big endian
+    /// with [`Self::start_with_domain_bytes`].
     ///
     /// # Parameters
     ///
-    /// - `len`: The number of field elements to absorb.
-    /// - `input`: The input slice of field elements.
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern for the sponge.
+    /// - `domain_sep`: The 128-bit domain-separator.
     ///
     /// # Returns
     ///
-    /// A result indicating success if the operation completes, or an `Error`
-    /// if the IO-pattern wasn't followed.
-    pub fn absorb(
-        &mut self,
-        len: usize,
-        input: impl AsRef<[T]>,
-    ) -> Result<(), Error> {
-        // Check that input yields enough elements
-        if input.as_ref().len() < len {
-            self.zeroize();
-            return Err(Error::TooFewInputElements);
-        }
-        // Check that the IO-pattern is followed
-        match self.iopattern.get(self.io_count) {
-            // only proceed if we expect a call to absorb with the correct
-            // length as per the IO-pattern
-            Some(Call::Absorb(call_len)) if *call_len == len => {}
-            Some(Call::Absorb(_)) => {
-                self.zeroize();
-                return Err(Error::IOPatternViolation);
-            }
-            _ => {
-                self.zeroize();
-                return Err(Error::IOPatternViolation);
-            }
-        }
-
-        // Absorb `len` elements into the state, calling [`permute`] when the
-        // absorb-position reached the rate.
-        for element in input.as_ref().iter().take(len) {
-            if self.pos_absorb == Self::RATE {
-                self.safe.permute(&mut self.state);
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the IO-pattern is invalid.
+    #[cfg(feature = "alloc")]
+    pub fn start_with_u128_domain(
+        safe: S,
+        iopattern: impl Into<Vec<Call>>,
+        domain_sep: u128,
+    ) -> Result<Self, Error> {
+        Self::start_with_domain_bytes(safe, iopattern, domain_sep.to_be_bytes())
+    }
 
-                self.pos_absorb = 0;
-            }
-            // add the input to the state using `Safe::add`
-            let pos = self.pos_absorb + Self::CAPACITY;
-            let previous_value = self.state[pos];
-            let sum = self.safe.add(&previous_value, element);
-            self.state[pos] = sum;
-            self.pos_absorb += 1;
-        }
+    /// This initializes a sponge in full duplex mode, for protocols that
+    /// interleave absorbing and squeezing within one permutation.
+    ///
+    /// By default the sponge is half duplex: absorbing after squeezing
+    /// overwrites the rate from its start. In full duplex mode, absorb and
+    /// squeeze share one position in the rate instead, so that elements
+    /// absorbed after squeezing fill the part of the rate that wasn't
+    /// squeezed yet and the state is only permuted once that position reaches
+    /// the rate. As in half duplex mode, the state is always permuted before
+    /// squeezing when something was absorbed since the last permutation, so
+    /// that every squeezed element depends on all elements absorbed before
+    /// it.
+    ///
+    /// The tag is computed like for [`Self::start`], with the identifier
+    /// [`DUPLEX_ID`](crate::domain::DUPLEX_ID) encoded into the most
+    /// significant byte of the domain-separator, so that the outputs of both
+    /// modes are separated.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `iopattern`: The IO-pattern for the sponge.
+    /// - `domain_sep`: The domain separator chosen by the user, with the most
+    ///   significant byte set to zero.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the IO-pattern is invalid or the domain-separator uses the reserved
+    /// byte.
+    #[cfg(feature = "alloc")]
+    pub fn start_duplex(
+        safe: S,
+        iopattern: impl Into<Vec<Call>>,
+        domain_sep: u64,
+    ) -> Result<Self, Error> {
+        let duplex_domain = construction_domain(DUPLEX_ID, domain_sep)?;
+        let mut sponge = Self::start_inner(
+            safe,
+            Cow::Owned(iopattern.into()),
+            duplex_domain,
+            TagVersion::V0,
+            EncodingProfile::SAFE,
+        )?;
+        sponge.domain_sep = domain_sep;
+        sponge.duplex = true;
+        Ok(sponge)
+    }
 
-        // Set squeeze position to rate to force a permutation at the next
-        // call to squeeze
-        self.pos_squeeze = Self::RATE;
+    /// This initializes a sponge without a fixed IO-pattern, for protocols in
+    /// which the sequence of calls depends on the data.
+    ///
+    /// After a first call to absorb, every call to absorb or squeeze is
+    /// accepted and appended to the IO-pattern actually executed. The sponge
+    /// needs to be finished with [`Self::finish_unbounded`], which absorbs
+    /// the encoding of the executed IO-pattern and squeezes one more
+    /// element binding the output to it.
+    ///
+    /// The tag is computed from the domain-separator alone, with the
+    /// identifier [`UNBOUNDED_ID`](crate::domain::UNBOUNDED_ID) encoded into
+    /// its most significant byte. Since that input is shorter than the
+    /// encoding of any IO-pattern, the tag differs from those of sponges with
+    /// a fixed IO-pattern.
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator chosen by the user, with the most
+    ///   significant byte set to zero.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the initialized Sponge on success, or an `Error` if
+    /// the domain-separator uses the reserved byte.
+    #[cfg(feature = "alloc")]
+    pub fn start_unbounded(safe: S, domain_sep: u64) -> Result<Self, Error> {
+        let unbounded_domain = construction_domain(UNBOUNDED_ID, domain_sep)?;
+        let mut safe = safe;
+        let state = safe.tagged_state(&unbounded_domain.to_be_bytes());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(domain_sep, "unbounded sponge started");
 
-        // Increase the position for the IO-pattern
-        self.io_count += 1;
+        let sponge = Self {
+            state,
+            safe,
+            pos_absorb: 0,
+            pos_squeeze: 0,
+            io_count: 0,
+            partial: 0,
+            iopattern: Cow::Owned(Vec::new()),
+            domain_sep,
+            version: TagVersion::V0,
+            unbounded: true,
+            duplex: false,
+            output: Output::new(),
+            output_mode: OutputMode::Accumulate,
+            #[cfg(feature = "audit")]
+            audit_log: Vec::new(),
+            #[cfg(feature = "test-utils")]
+            snapshots: Vec::new(),
+        };
+        #[cfg(feature = "test-utils")]
+        let mut sponge = sponge;
+        #[cfg(feature = "test-utils")]
+        sponge.take_snapshot(None);
 
-        Ok(())
+        Ok(sponge)
     }
 
-    /// This extracts `len` field elements from the state with interleaving
-    /// calls to the permutation function. It also checks if the call matches
-    /// the IO-pattern.
+    /// Sets how the calls to squeeze fill the output of the sponge, see
+    /// [`OutputMode`]. By default all squeezed elements are accumulated.
     ///
     /// # Parameters
     ///
-    /// - `len`: The number of field elements to squeeze.
+    /// - `mode`: The output mode.
     ///
     /// # Returns
     ///
-    /// A result indicating success if the operation completes, or an `Error`
+    /// The sponge with the given output mode.
+    pub fn with_output_mode(mut self, mode: OutputMode) -> Self {
+        self.output_mode = mode;
+        self
+    }
+
+    /// Returns the call the sponge expects next as per the IO-pattern,
+    /// skipping any labels, e.g. for driving the sponge generically or for
+    /// reporting a mismatch before it fails the sponge.
+    ///
+    /// If a call to absorb was partially fed with [`Self::absorb_partial`],
+    /// the call to absorb its remaining elements is returned.
+    ///
+    /// # Returns
+    ///
+    /// The expected call, or `None` if the IO-pattern is complete or the
+    /// sponge has no fixed IO-pattern.
+    pub fn expected_next_call(&self) -> Option<Call> {
+        if self.unbounded {
+            return None;
+        }
+        self.remaining_pattern()
+            .iter()
+            .find(|call| !matches!(call, Call::Label(_)))
+            .map(|call| match call {
+                Call::Absorb(len) => Call::Absorb(len - self.partial),
+                call => *call,
+            })
+    }
+
+    /// Returns the part of the IO-pattern that wasn't executed yet, including
+    /// a partially absorbed call. For sponges without a fixed IO-pattern, it
+    /// is empty.
+    pub fn remaining_pattern(&self) -> &[Call] {
+        match self.unbounded {
+            true => &[],
+            false => &self.iopattern[self.io_count..],
+        }
+    }
+
+    /// Returns the elements squeezed so far that are still part of the
+    /// output, e.g. for using a challenge right away without finishing the
+    /// sponge.
+    ///
+    /// Note that the elements must not be used if the sponge later fails,
+    /// see [`Call`].
+    pub fn peek_output(&self) -> &[T] {
+        &self.output
+    }
+
+    /// Takes the elements squeezed so far out of the output, so that
+    /// they can be consumed in the middle of the protocol.
+    ///
+    /// The taken elements are no longer part of the output returned by
+    /// [`Self::finish`], and [`Self::finish_array`] expects only the elements
+    /// squeezed afterwards. Note that the elements must not be used if the
+    /// sponge later fails, see [`Call`].
+    ///
+    /// # Returns
+    ///
+    /// The elements of the output, as an [`Output`] that erases them when it
+    /// is dropped.
+    pub fn take_output(&mut self) -> Output<T> {
+        core::mem::take(&mut self.output)
+    }
+
+    /// Returns the IO-pattern of the sponge in its normalized form, see
+    /// [`aggregate`](crate::aggregate). For sponges without a fixed
+    /// IO-pattern, these are the calls executed so far.
+    #[cfg(feature = "alloc")]
+    pub fn aggregated_iopattern(&self) -> Vec<Call> {
+        crate::aggregate(&self.iopattern)
+    }
+
+    /// Returns the version of the encoding of the tag input the sponge was
+    /// started with.
+    pub fn tag_version(&self) -> TagVersion {
+        self.version
+    }
+
+    /// Creates an independent copy of the sponge, including its secret state.
+    ///
+    /// Both copies continue from the same state, which is useful for trying
+    /// out several continuations of a transcript. Note that any secret
+    /// absorbed so far now lives in both copies.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the sponge.
+    pub fn checkpoint(&self) -> Self
+    where
+        S: Clone,
+    {
+        Self {
+            state: self.state,
+            safe: self.safe.clone(),
+            pos_absorb: self.pos_absorb,
+            pos_squeeze: self.pos_squeeze,
+            io_count: self.io_count,
+            partial: self.partial,
+            iopattern: self.iopattern.clone(),
+            domain_sep: self.domain_sep,
+            version: self.version,
+            unbounded: self.unbounded,
+            duplex: self.duplex,
+            output: self.output.clone(),
+            output_mode: self.output_mode,
+            #[cfg(feature = "audit")]
+            audit_log: self.audit_log.clone(),
+            #[cfg(feature = "test-utils")]
+            snapshots: self.snapshots.clone(),
+        }
+    }
+
+    /// Suspends the sponge by serializing everything needed to resume it with
+    /// [`Self::resume`], e.g. for persisting a long-lived transcript across
+    /// restarts of the process.
+    ///
+    /// **The serialization contains the secret state of the sponge**, as
+    /// well as any squeezed elements not yet returned by [`Self::finish`].
+    /// Whoever can read it can continue the transcript, it therefore needs to
+    /// be stored with the same care as a key. The returned bytes are erased
+    /// from memory when dropped. The audit log and the snapshots of the
+    /// `test-utils` feature are not serialized.
+    ///
+    /// The bytes are, with every integer encoded as 8 bytes little endian:
+    /// the `W` elements of the state, the absorb and squeeze positions, the
+    /// amount of calls made, the amount of elements of a partially absorbed
+    /// call, the domain-separator, one byte each for the version of the tag
+    /// input, the mode flags and the output mode, the amount of calls in the
+    /// IO-pattern followed by every call as one byte for its kind and its
+    /// length or label, and the amount of elements in the output followed by
+    /// the elements.
+    ///
+    /// # Parameters
+    ///
+    /// - `encode`: Serializes an element into `N` bytes.
+    ///
+    /// # Returns
+    ///
+    /// The serialized sponge.
+    #[cfg(feature = "alloc")]
+    pub fn suspend<const N: usize>(
+        self,
+        encode: impl Fn(&T) -> [u8; N],
+    ) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(Vec::new());
+        let element = |bytes: &mut Vec<u8>, t: &T| {
+            let mut encoded = encode(t);
+            bytes.extend_from_slice(&encoded);
+            encoded.zeroize();
+        };
+
+        self.state.iter().for_each(|t| element(&mut bytes, t));
+        for n in [
+            self.pos_absorb,
+            self.pos_squeeze,
+            self.io_count,
+            self.partial,
+        ] {
+            bytes.extend_from_slice(&(n as u64).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.domain_sep.to_le_bytes());
+        bytes.push(self.version.to_byte());
+        bytes.push(self.unbounded as u8 | (self.duplex as u8) << 1);
+        bytes.push(match self.output_mode {
+            OutputMode::Accumulate => 0,
+            OutputMode::Overwrite => 1,
+        });
+
+        bytes.extend_from_slice(&(self.iopattern.len() as u64).to_le_bytes());
+        for call in self.iopattern.iter() {
+            let (kind, value) = match call {
+                Call::Absorb(len) => (0, *len as u64),
+                Call::Squeeze(len) => (1, *len as u64),
+                Call::Label(label) => (2, *label),
+                Call::Ratchet => (3, 0),
+            };
+            bytes.push(kind);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.output.len() as u64).to_le_bytes());
+        self.output.iter().for_each(|t| element(&mut bytes, t));
+
+        bytes
+    }
+
+    /// Resumes a sponge suspended with [`Self::suspend`].
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `bytes`: The serialized sponge.
+    /// - `decode`: Deserializes `N` bytes into an element.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the resumed sponge, or an `Error` if the bytes are
+    /// not a valid encoding of a sponge of width `W`.
+    #[cfg(feature = "alloc")]
+    pub fn resume<const N: usize>(
+        safe: S,
+        bytes: &[u8],
+        decode: impl Fn(&[u8; N]) -> Option<T>,
+    ) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+        let element = |reader: &mut Reader| -> Result<T, Error> {
+            let mut encoded = [0u8; N];
+            encoded.copy_from_slice(reader.take(N)?);
+            let t = decode(&encoded);
+            encoded.zeroize();
+            t.ok_or(Error::InvalidEncoding)
+        };
+
+        let mut state = [T::default(); W];
+        for t in state.iter_mut() {
+            *t = element(&mut reader)?;
+        }
+        let pos_absorb = reader.len()?;
+        let pos_squeeze = reader.len()?;
+        let io_count = reader.len()?;
+        let partial = reader.len()?;
+        let domain_sep = reader.u64()?;
+        let version = TagVersion::from_byte(reader.u8()?)
+            .ok_or(Error::InvalidEncoding)?;
+        let (unbounded, duplex) = match reader.u8()? {
+            flags if flags < 4 => (flags & 1 != 0, flags & 2 != 0),
+            _ => return Err(Error::InvalidEncoding),
+        };
+        let output_mode = match reader.u8()? {
+            0 => OutputMode::Accumulate,
+            1 => OutputMode::Overwrite,
+            _ => return Err(Error::InvalidEncoding),
+        };
+
+        let calls = reader.count(9)?;
+        let mut iopattern = Vec::with_capacity(calls);
+        for _ in 0..calls {
+            let kind = reader.u8()?;
+            let value = reader.u64()?;
+            let len =
+                usize::try_from(value).map_err(|_| Error::InvalidEncoding);
+            iopattern.push(match kind {
+                0 => Call::Absorb(len?),
+                1 => Call::Squeeze(len?),
+                2 => Call::Label(value),
+                3 if value == 0 => Call::Ratchet,
+                _ => return Err(Error::InvalidEncoding),
+            });
+        }
+
+        let len = reader.count(N)?;
+        let mut output = Output::new();
+        for _ in 0..len {
+            output.push(element(&mut reader)?);
+        }
+
+        // check that the sponge can continue from the decoded positions
+        let partial_valid = match (partial, iopattern.get(io_count)) {
+            (0, _) => true,
+            (partial, Some(Call::Absorb(len))) => partial < *len,
+            _ => false,
+        };
+        if !reader.0.is_empty()
+            || pos_absorb > Self::RATE
+            || pos_squeeze > Self::RATE
+            || io_count > iopattern.len()
+            || !partial_valid
+            || (!unbounded && validate_io_pattern(&iopattern).is_err())
+        {
+            output.zeroize();
+            state.zeroize();
+            return Err(Error::InvalidEncoding);
+        }
+
+        Ok(Self {
+            state,
+            safe,
+            pos_absorb,
+            pos_squeeze,
+            io_count,
+            partial,
+            iopattern: Cow::Owned(iopattern),
+            domain_sep,
+            version,
+            unbounded,
+            duplex,
+            output,
+            output_mode,
+            #[cfg(feature = "audit")]
+            audit_log: Vec::new(),
+            #[cfg(feature = "test-utils")]
+            snapshots: Vec::new(),
+        })
+    }
+
+    /// This marks the end of the sponge life, preventing any further operation.
+    /// In particular, the state is erased from memory.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the [`Output`] on success, or an `Error` if the
+    /// IO-pattern wasn't followed.
+    pub fn finish(mut self) -> Result<Output<T>, Error> {
+        let complete = critical_check(|| {
+            !self.unbounded && self.io_count == self.iopattern.len()
+        });
+        let ret = match complete {
+            true => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    domain_sep = self.domain_sep,
+                    output_len = self.output.len(),
+                    "sponge finished"
+                );
+                // move the output out, leaving an empty one behind, so that
+                // no copy of it remains in memory
+                Ok(core::mem::take(&mut self.output))
+            }
+            false => Err(self.fail(Error::IOPatternViolation, None)),
+        };
+        // no matter the return, we erase the internal state of the sponge
+        self.zeroize();
+        ret
+    }
+
+    /// This marks the end of the life of a sponge started with
+    /// [`Self::start_unbounded`], binding its output to the IO-pattern
+    /// actually executed.
+    ///
+    /// The encoding of the executed IO-pattern, followed by the call to
+    /// squeeze the binding element, is absorbed as per [`tag_input_elements`]
+    /// and one more element is squeezed and appended to the output. The
+    /// executed calls need to start with a call to absorb.
+    ///
+    /// [`tag_input_elements`]: crate::tag_input_elements
+    ///
+    /// # Returns
+    ///
+    /// A result containing the [`Output`] on success, or an `Error` if the
+    /// sponge has a fixed IO-pattern or the executed calls don't form a valid
+    /// IO-pattern.
+    #[cfg(feature = "alloc")]
+    pub fn finish_unbounded(mut self) -> Result<Output<T>, Error>
+    where
+        T: From<u64>,
+    {
+        if !self.unbounded {
+            return Err(self.fail(Error::IOPatternViolation, None));
+        }
+        let mut executed = self.iopattern.to_vec();
+        executed.push(Call::Squeeze(1));
+        let words = match tag_input_elements::<u64>(&executed, self.domain_sep)
+        {
+            Ok(words) => words,
+            Err(_) => return Err(self.fail(Error::IOPatternViolation, None)),
+        };
+
+        // absorb the executed IO-pattern and squeeze the binding element
+        for word in words {
+            self.absorb_one(&T::from(word));
+        }
+        self.pos_squeeze = Self::RATE;
+        let binding = self.squeeze_one();
+        self.output.push(binding);
+
+        self.unbounded = false;
+        self.finish()
+    }
+
+    /// This marks the end of the sponge life like [`Self::finish`], but
+    /// returns the output as an array of `N` elements.
+    ///
+    /// When the IO-pattern is known at compile time, `N` can be computed with
+    /// [`squeeze_len`](crate::squeeze_len) so that the output never touches the
+    /// heap.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the output array on success, or an `Error` if the
+    /// IO-pattern wasn't followed or the sponge didn't squeeze exactly `N`
+    /// elements.
+    pub fn finish_array<const N: usize>(mut self) -> Result<[T; N], Error> {
+        let complete = critical_check(|| {
+            !self.unbounded && self.io_count == self.iopattern.len()
+        });
+        let ret = match complete {
+            true if self.output.len() == N => {
+                let mut output = [T::default(); N];
+                output.copy_from_slice(&self.output);
+                Ok(output)
+            }
+            true => Err(self.fail(Error::OutputLenMismatch, None)),
+            false => Err(self.fail(Error::IOPatternViolation, None)),
+        };
+        // no matter the return, we erase the internal state of the sponge
+        self.zeroize();
+        ret
+    }
+
+    /// This marks the end of the sponge life like [`Self::finish`], and
+    /// additionally returns the audit log of all calls to absorb and squeeze.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the [`Output`] and the audit log on success, or an
+    /// `Error` if the IO-pattern wasn't followed.
+    #[cfg(feature = "audit")]
+    pub fn finish_with_audit_log(
+        mut self,
+    ) -> Result<(Output<T>, Vec<AuditEntry<T>>), Error> {
+        let audit_log = core::mem::take(&mut self.audit_log);
+        self.finish().map(|output| (output, audit_log))
+    }
+
+    /// This marks the end of the sponge life like [`Self::finish`], and
+    /// additionally returns a single element committing to the entire
+    /// transcript, e.g. for storing a compact fingerprint of it alongside the
+    /// output.
+    ///
+    /// The commitment is squeezed from a child sponge that absorbs the final
+    /// state of this sponge under the crate-defined
+    /// [`TRANSCRIPT_COMMITMENT`](crate::domain::TRANSCRIPT_COMMITMENT)
+    /// domain-separator. Since the state started from the tag, it is bound
+    /// to the IO-pattern, the domain-separator and all absorbed elements.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the [`Output`] and the commitment on success, or
+    /// an `Error` if the IO-pattern wasn't followed.
+    pub fn finish_with_commitment(self) -> Result<(Output<T>, T), Error>
+    where
+        S: Clone,
+    {
+        let commitment = self.state_hash(TRANSCRIPT_COMMITMENT)?;
+        self.finish().map(|output| (output, commitment))
+    }
+
+    /// This marks the end of the sponge life like [`Self::finish`], and starts
+    /// the sponge of the next phase of a multi-phase protocol, chained to
+    /// this one.
+    ///
+    /// The next sponge first absorbs the commitment to this transcript, see
+    /// [`Self::finish_with_commitment`]. Its IO-pattern is therefore
+    /// `next_pattern` prefixed with a `Call::Absorb(1)`, which is already
+    /// executed when the sponge is returned. When `next_pattern` starts with
+    /// a call to absorb, both calls are aggregated in the tag.
+    ///
+    /// # Parameters
+    ///
+    /// - `next_pattern`: The IO-pattern of the next phase.
+    /// - `domain_sep`: The domain separator of the next phase.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the [`Output`] and the next sponge on success, or
+    /// an `Error` if the IO-pattern wasn't followed or the IO-pattern of the
+    /// next sponge is invalid.
+    #[cfg(feature = "alloc")]
+    pub fn finish_into(
+        self,
+        next_pattern: impl AsRef<[Call]>,
+        domain_sep: u64,
+    ) -> Result<(Output<T>, Self), Error>
+    where
+        S: Clone,
+    {
+        let safe = self.safe.clone();
+        let (output, commitment) = self.finish_with_commitment()?;
+
+        let next_pattern = next_pattern.as_ref();
+        let mut iopattern = Vec::with_capacity(next_pattern.len() + 1);
+        iopattern.push(Call::Absorb(1));
+        iopattern.extend_from_slice(next_pattern);
+        let mut next = Self::start(safe, iopattern, domain_sep)?;
+        next.absorb(1, [commitment])?;
+
+        Ok((output, next))
+    }
+
+    /// Returns the audit log of all calls to absorb and squeeze so far.
+    #[cfg(feature = "audit")]
+    pub fn audit_log(&self) -> &[AuditEntry<T>] {
+        &self.audit_log
+    }
+
+    /// Computes a hash that is bound to everything absorbed by the sponge so
+    /// far, without advancing the IO-pattern or touching the state.
+    ///
+    /// The hash is squeezed from a child sponge that absorbs the position of
+    /// this sponge in its IO-pattern and in the rate, followed by its entire
+    /// state, under the crate-defined
+    /// [`SESSION_HASH`](crate::domain::SESSION_HASH) domain-separator. It can
+    /// be used for channel binding or for committing to a prefix of a
+    /// transcript in multi-round protocols.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the session hash, or an `Error` if the child
+    /// sponge failed.
+    pub fn session_hash(&self) -> Result<T, Error>
+    where
+        S: Clone,
+    {
+        self.state_hash(SESSION_HASH)
+    }
+
+    /// Hashes the position and the entire state with a child sponge under the
+    /// given domain-separator.
+    ///
+    /// The position, i.e. the amount of calls made, the elements of a
+    /// partially absorbed call and the positions of absorb and squeeze in the
+    /// rate, is encoded as 8 bytes little endian each and mapped to a single
+    /// element with [`Safe::tag`], so that two sponges with the same state but
+    /// at different points of their IO-pattern hash differently.
+    fn state_hash(&self, domain_sep: u64) -> Result<T, Error>
+    where
+        S: Clone,
+    {
+        let mut position = [0u8; 32];
+        position[..8].copy_from_slice(&(self.io_count as u64).to_le_bytes());
+        position[8..16].copy_from_slice(&(self.partial as u64).to_le_bytes());
+        position[16..24]
+            .copy_from_slice(&(self.pos_absorb as u64).to_le_bytes());
+        position[24..]
+            .copy_from_slice(&(self.pos_squeeze as u64).to_le_bytes());
+        let position = self.safe.clone().tag(&position);
+
+        let mut child = Sponge::start_static(
+            self.safe.clone(),
+            Self::STATE_HASH_IOPATTERN,
+            domain_sep,
+        )?;
+        child.absorb(1, [position])?;
+        child.absorb(W, self.state)?;
+        child.squeeze(1)?;
+        Ok(child.finish()?[0])
+    }
+
+    /// Returns a [`SyncPoint`] committing to the progress of the sponge, which
+    /// a verifier can compare to the one of its own sponge to assert that it
+    /// absorbed the same values under the same IO-pattern so far.
+    ///
+    /// The digest of the IO-pattern is the [`pattern_digest`] of the
+    /// IO-pattern and the domain-separator. For a sponge started with
+    /// [`Self::start_unbounded`], which has no IO-pattern to digest, it is the
+    /// tag computed from the domain-separator.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the sync point, or an `Error` if the digests
+    /// couldn't be computed.
+    pub fn sync_point(&self) -> Result<SyncPoint<T>, Error>
+    where
+        S: Clone,
+    {
+        let pattern_digest = match self.unbounded {
+            true => {
+                let domain =
+                    construction_domain(UNBOUNDED_ID, self.domain_sep)?;
+                self.safe.clone().tag(&domain.to_be_bytes())
+            }
+            false => pattern_digest(
+                self.safe.clone(),
+                &self.iopattern[..],
+                self.domain_sep,
+            )?,
+        };
+        Ok(SyncPoint {
+            domain_sep: self.domain_sep,
+            io_count: self.io_count as u64,
+            pattern_digest,
+            values_digest: self.session_hash()?,
+        })
+    }
+
+    /// Applies one permutation to the state outside of the IO-pattern, for
+    /// constructions that need to make the sponge deliberately slow.
+    ///
+    /// The squeeze position is set to the rate, so that the next call to
+    /// squeeze permutes the state once more before extracting any element.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn permute(&mut self) {
+        self.safe.permute(&mut self.state);
+        self.pos_absorb = 0;
+        self.pos_squeeze = Self::RATE;
+    }
+
+    /// This absorbs `len` field elements from the input into the state with
+    /// interleaving calls to the permutation function. It also checks if the
+    /// call matches the IO-pattern.
+    ///
+    /// # Parameters
+    ///
+    /// - `len`: The number of field elements to absorb.
+    /// - `input`: The input slice of field elements.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    pub fn absorb(
+        &mut self,
+        len: usize,
+        input: impl AsRef<[T]>,
+    ) -> Result<(), Error> {
+        // Check that input yields enough elements
+        if input.as_ref().len() < len {
+            let call = Some(Call::Absorb(len));
+            return Err(self.fail(Error::TooFewInputElements, call));
+        }
+        // Check that the IO-pattern is followed
+        match self.expected_call(Call::Absorb(len)) {
+            // only proceed if we expect a call to absorb with the correct
+            // length as per the IO-pattern
+            Some(Call::Absorb(call_len)) if call_len == len => {}
+            Some(Call::Absorb(_)) => {
+                let call = Some(Call::Absorb(len));
+                return Err(self.fail(Error::IOPatternViolation, call));
+            }
+            _ => {
+                let call = Some(Call::Absorb(len));
+                return Err(self.fail(Error::IOPatternViolation, call));
+            }
+        }
+
+        // Record the call before modifying the state
+        #[cfg(feature = "audit")]
+        if let Err(e) = record(
+            &mut self.safe,
+            &mut self.audit_log,
+            Call::Absorb(len),
+            input.as_ref()[..len].iter().copied(),
+        ) {
+            return Err(self.fail(e, Some(Call::Absorb(len))));
+        }
+
+        // Absorb `len` elements into the state
+        for element in input.as_ref().iter().take(len) {
+            self.absorb_one(element);
+        }
+
+        // Set squeeze position to rate to force a permutation at the next
+        // call to squeeze
+        self.pos_squeeze = Self::RATE;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(len, io_count = self.io_count, "absorbed");
+
+        #[cfg(feature = "test-utils")]
+        self.take_snapshot(Some(self.io_count));
+
+        // Increase the position for the IO-pattern
+        self.io_count += 1;
+
+        Ok(())
+    }
+
+    /// This absorbs `len` field elements produced by an iterator into the state
+    /// like [`Self::absorb`], without collecting them into a slice first.
+    ///
+    /// Elements yielded past `len` are not consumed. Since the elements are
+    /// only produced while absorbing, an iterator yielding less than `len`
+    /// elements is detected after the state has been modified, failing the
+    /// sponge.
+    ///
+    /// # Parameters
+    ///
+    /// - `len`: The number of field elements to absorb.
+    /// - `input`: The iterator producing the field elements.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed or the iterator yielded too few
+    /// elements.
+    pub fn absorb_iter(
+        &mut self,
+        len: usize,
+        input: impl IntoIterator<Item = T>,
+    ) -> Result<(), Error> {
+        // Check that the IO-pattern is followed
+        match self.expected_call(Call::Absorb(len)) {
+            Some(Call::Absorb(call_len)) if call_len == len => {}
+            _ => {
+                let call = Some(Call::Absorb(len));
+                return Err(self.fail(Error::IOPatternViolation, call));
+            }
+        }
+
+        // Absorb `len` elements into the state
+        #[cfg(feature = "audit")]
+        let mut elements = Vec::with_capacity(len);
+        let mut absorbed = 0;
+        for element in input.into_iter().take(len) {
+            self.absorb_one(&element);
+            #[cfg(feature = "audit")]
+            elements.push(element);
+            absorbed += 1;
+        }
+        if absorbed < len {
+            #[cfg(feature = "audit")]
+            elements.zeroize();
+            let call = Some(Call::Absorb(len));
+            return Err(self.fail(Error::TooFewInputElements, call));
+        }
+        #[cfg(feature = "audit")]
+        {
+            let recorded = record(
+                &mut self.safe,
+                &mut self.audit_log,
+                Call::Absorb(len),
+                elements.iter().copied(),
+            );
+            elements.zeroize();
+            if let Err(e) = recorded {
+                return Err(self.fail(e, Some(Call::Absorb(len))));
+            }
+        }
+
+        // Set squeeze position to rate to force a permutation at the next
+        // call to squeeze
+        self.pos_squeeze = Self::RATE;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(len, io_count = self.io_count, "absorbed");
+
+        #[cfg(feature = "test-utils")]
+        self.take_snapshot(Some(self.io_count));
+
+        // Increase the position for the IO-pattern
+        self.io_count += 1;
+
+        Ok(())
+    }
+
+    /// This absorbs an input whose length is only known at runtime, applying
+    /// the padding scheme at the end of the absorb phase, as per the calls of
+    /// [`PaddingScheme::absorb_calls`] in the IO-pattern.
+    ///
+    /// # Parameters
+    ///
+    /// - `padding`: The padding scheme.
+    /// - `input`: The unpadded input.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    #[cfg(feature = "alloc")]
+    pub fn absorb_padded(
+        &mut self,
+        padding: &impl PaddingScheme<T>,
+        input: impl AsRef<[T]>,
+    ) -> Result<(), Error> {
+        padding.absorb(self, input.as_ref())
+    }
+
+    /// This absorbs a single element into the state like [`Self::absorb`],
+    /// for a `Call::Absorb(1)` in the IO-pattern.
+    ///
+    /// # Parameters
+    ///
+    /// - `element`: The element to absorb.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    pub fn absorb_element(&mut self, element: &T) -> Result<(), Error> {
+        self.absorb(1, core::slice::from_ref(element))
+    }
+
+    /// This absorbs a part of the elements of a `Call::Absorb(n)`, so that the
+    /// elements of a call can be fed as they arrive, e.g. from a stream,
+    /// instead of being buffered first.
+    ///
+    /// The IO-pattern only advances once all `n` elements of the call have
+    /// been absorbed, until then any other call fails. The result is the same
+    /// as absorbing all elements at once with [`Self::absorb`], except that
+    /// the audit log records every chunk as its own entry.
+    ///
+    /// # Parameters
+    ///
+    /// - `input`: The next elements of the call.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the amount of elements that remain to be absorbed
+    /// for the call, or an `Error` if the IO-pattern doesn't expect a call to
+    /// absorb, the input exceeds the length of the call or the sponge has no
+    /// fixed IO-pattern.
+    pub fn absorb_partial(
+        &mut self,
+        input: impl AsRef<[T]>,
+    ) -> Result<usize, Error> {
+        let input = input.as_ref();
+        let call = Some(Call::Absorb(input.len()));
+        if self.unbounded {
+            return Err(self.fail(Error::IOPatternViolation, call));
+        }
+        // Check that the IO-pattern is followed, resuming the current call if
+        // it was already partially fed
+        let expected = match self.partial {
+            0 => self.expected_call(Call::Absorb(input.len())),
+            _ => self.iopattern.get(self.io_count).copied(),
+        };
+        let call_len = match expected {
+            Some(Call::Absorb(call_len))
+                if input.len() <= call_len - self.partial =>
+            {
+                call_len
+            }
+            _ => return Err(self.fail(Error::IOPatternViolation, call)),
+        };
+
+        // Record the chunk before modifying the state
+        #[cfg(feature = "audit")]
+        if let Err(e) = record(
+            &mut self.safe,
+            &mut self.audit_log,
+            Call::Absorb(input.len()),
+            input.iter().copied(),
+        ) {
+            return Err(self.fail(e, call));
+        }
+
+        // Absorb the elements into the state
+        for element in input {
+            self.absorb_one(element);
+        }
+        self.partial += input.len();
+
+        // Set squeeze position to rate to force a permutation at the next
+        // call to squeeze
+        self.pos_squeeze = Self::RATE;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            len = input.len(),
+            io_count = self.io_count,
+            "absorbed partially"
+        );
+
+        let remaining = call_len - self.partial;
+        if remaining == 0 {
+            #[cfg(feature = "test-utils")]
+            self.take_snapshot(Some(self.io_count));
+
+            // Increase the position for the IO-pattern
+            self.partial = 0;
+            self.io_count += 1;
+        }
+
+        Ok(remaining)
+    }
+
+    /// This absorbs a label that delimits a logical section of the transcript,
+    /// encoded as a single element. The label needs to be accounted for in
+    /// the IO-pattern as a `Call::Absorb(1)`.
+    ///
+    /// Labeling the sections prevents two transcripts from colliding when
+    /// their sections happen to absorb the same amount of elements, since
+    /// contiguous calls to absorb are aggregated in the tag.
+    ///
+    /// # Parameters
+    ///
+    /// - `label`: The label of the section that starts with this call.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
     /// if the IO-pattern wasn't followed.
-    pub fn squeeze(&mut self, len: usize) -> Result<(), Error> {
+    pub fn absorb_domain(&mut self, label: u64) -> Result<(), Error>
+    where
+        T: From<u64>,
+    {
+        self.absorb(1, [T::from(label)])
+    }
+
+    /// This absorbs a value that implements [`Absorbable`] into the state, as
+    /// a single call to absorb of [`Absorbable::elements_len`] elements. It
+    /// also checks if the call matches the IO-pattern.
+    ///
+    /// # Parameters
+    ///
+    /// - `value`: The value to absorb.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed or the value yielded fewer elements
+    /// than it announced.
+    pub fn absorb_value(
+        &mut self,
+        value: &impl Absorbable<T>,
+    ) -> Result<(), Error> {
+        let len = value.elements_len();
+        // Check that the IO-pattern is followed
+        match self.expected_call(Call::Absorb(len)) {
+            Some(Call::Absorb(call_len)) if call_len == len => {}
+            _ => {
+                let call = Some(Call::Absorb(len));
+                return Err(self.fail(Error::IOPatternViolation, call));
+            }
+        }
+
+        // Absorb `len` elements into the state
+        let mut absorbed = 0;
+        for element in value.to_elements().into_iter().take(len) {
+            self.absorb_one(&element);
+            absorbed += 1;
+        }
+        if absorbed < len {
+            let call = Some(Call::Absorb(len));
+            return Err(self.fail(Error::TooFewInputElements, call));
+        }
+        #[cfg(feature = "audit")]
+        if let Err(e) = record(
+            &mut self.safe,
+            &mut self.audit_log,
+            Call::Absorb(len),
+            value.to_elements(),
+        ) {
+            return Err(self.fail(e, Some(Call::Absorb(len))));
+        }
+
+        // Set squeeze position to rate to force a permutation at the next
+        // call to squeeze
+        self.pos_squeeze = Self::RATE;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(len, io_count = self.io_count, "absorbed");
+
+        #[cfg(feature = "test-utils")]
+        self.take_snapshot(Some(self.io_count));
+
+        // Increase the position for the IO-pattern
+        self.io_count += 1;
+
+        Ok(())
+    }
+
+    /// This absorbs an element of another field into the state, as a single
+    /// call to absorb of the elements it embeds into, see [`Embed`].
+    ///
+    /// # Parameters
+    ///
+    /// - `value`: The foreign element to absorb.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    #[cfg(feature = "alloc")]
+    pub fn absorb_foreign(
+        &mut self,
+        value: &impl Embed<T>,
+    ) -> Result<(), Error> {
+        let mut elements = value.embed();
+        let absorbed = self.absorb(elements.len(), &elements);
+        elements.zeroize();
+        absorbed
+    }
+
+    /// This absorbs `len` elements computed one at a time from the safe and the
+    /// output squeezed so far, so that they never need to be stored together,
+    /// e.g. for verifying a cipher-text without materializing its plain-text.
+    ///
+    /// # Parameters
+    ///
+    /// - `len`: The number of elements to absorb.
+    /// - `element`: Computes the element with the given index.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    #[cfg(feature = "encryption")]
+    pub(crate) fn absorb_with(
+        &mut self,
+        len: usize,
+        mut element: impl FnMut(&mut S, &[T], usize) -> T,
+    ) -> Result<(), Error> {
+        // Check that the IO-pattern is followed
+        match self.expected_call(Call::Absorb(len)) {
+            Some(Call::Absorb(call_len)) if call_len == len => {}
+            _ => {
+                let call = Some(Call::Absorb(len));
+                return Err(self.fail(Error::IOPatternViolation, call));
+            }
+        }
+
+        // Absorb `len` elements into the state
+        #[cfg(feature = "audit")]
+        let mut elements = Vec::with_capacity(len);
+        for i in 0..len {
+            let mut e = element(&mut self.safe, &self.output, i);
+            self.absorb_one(&e);
+            #[cfg(feature = "audit")]
+            elements.push(e);
+            e.zeroize();
+        }
+        #[cfg(feature = "audit")]
+        {
+            let recorded = record(
+                &mut self.safe,
+                &mut self.audit_log,
+                Call::Absorb(len),
+                elements.iter().copied(),
+            );
+            elements.zeroize();
+            if let Err(e) = recorded {
+                return Err(self.fail(e, Some(Call::Absorb(len))));
+            }
+        }
+
+        // Set squeeze position to rate to force a permutation at the next
+        // call to squeeze
+        self.pos_squeeze = Self::RATE;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(len, io_count = self.io_count, "absorbed");
+
+        #[cfg(feature = "test-utils")]
+        self.take_snapshot(Some(self.io_count));
+
+        // Increase the position for the IO-pattern
+        self.io_count += 1;
+
+        Ok(())
+    }
+
+    /// Returns the current state of the sponge, e.g. for generating the
+    /// witness of a circuit that mirrors the sponge or for debugging.
+    ///
+    /// The state is secret, it is therefore only available behind the
+    /// `inspect` feature.
+    #[cfg(feature = "inspect")]
+    pub fn state(&self) -> &[T; W] {
+        &self.state
+    }
+
+    /// Returns the positions in the rate at which the next element is
+    /// absorbed and squeezed, in this order.
+    ///
+    /// A position equal to the rate means that the state is permuted before
+    /// the next element is absorbed or squeezed. In full duplex mode, both
+    /// calls use the absorb-position, and the squeeze-position only equals
+    /// the rate when the next call to squeeze permutes the state. Available
+    /// behind the `inspect` feature.
+    #[cfg(feature = "inspect")]
+    pub fn positions(&self) -> (usize, usize) {
+        (self.pos_absorb, self.pos_squeeze)
+    }
+
+    /// Returns the snapshots of the state taken when the sponge started and
+    /// after every call to absorb or squeeze, for comparing two runs with
+    /// [`first_divergence`](crate::first_divergence).
+    ///
+    /// The snapshots contain the secret state of the sponge, they are
+    /// therefore only available behind the `test-utils` feature.
+    #[cfg(feature = "test-utils")]
+    pub fn snapshots(&self) -> &[Snapshot<T, W>] {
+        &self.snapshots
+    }
+
+    /// Records a snapshot of the state after the call with the given index.
+    #[cfg(feature = "test-utils")]
+    fn take_snapshot(&mut self, call_index: Option<usize>) {
+        self.snapshots.push(Snapshot {
+            call_index,
+            state: self.state,
+        });
+    }
+
+    /// Returns the next call to absorb or squeeze as per the IO-pattern,
+    /// skipping any labels.
+    ///
+    /// Sponges without a fixed IO-pattern append the attempted `call` to the
+    /// executed IO-pattern instead, as long as the executed calls start with a
+    /// call to absorb.
+    fn expected_call(&mut self, call: Call) -> Option<Call> {
+        // a call to absorb that was only partially fed blocks any other call
+        if self.partial != 0 {
+            return None;
+        }
+        #[cfg(feature = "alloc")]
+        if self.unbounded {
+            // nothing may be squeezed from the initial state, which only
+            // depends on the domain-separator
+            if self.iopattern.is_empty() && !matches!(call, Call::Absorb(_)) {
+                return None;
+            }
+            self.iopattern.to_mut().push(call);
+        }
+        #[cfg(not(feature = "alloc"))]
+        let _ = call;
+        while let Some(Call::Label(_)) = self.iopattern.get(self.io_count) {
+            self.io_count += 1;
+        }
+        self.iopattern.get(self.io_count).copied()
+    }
+
+    /// Erases the state of the sponge after an error, reporting the error to
+    /// the hook set with [`set_error_hook`](crate::set_error_hook).
+    ///
+    /// # Parameters
+    ///
+    /// - `error`: The error that occurred.
+    /// - `call`: The call to absorb or squeeze that failed, if any.
+    ///
+    /// # Returns
+    ///
+    /// The error, for returning it to the caller.
+    fn fail(&mut self, error: Error, call: Option<Call>) -> Error {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            ?error,
+            ?call,
+            io_count = self.io_count,
+            domain_sep = self.domain_sep,
+            "sponge failed"
+        );
+        #[cfg(feature = "error-hook")]
+        crate::error::report(&crate::ErrorContext {
+            error,
+            call,
+            expected: self.iopattern.get(self.io_count).copied(),
+            io_count: self.io_count,
+            domain_sep: self.domain_sep,
+        });
+        #[cfg(not(any(feature = "error-hook", feature = "tracing")))]
+        let _ = call;
+        self.zeroize();
+        error
+    }
+
+    /// Adds one element to the state, calling [`Safe::permute`] when the
+    /// absorb-position reached the rate.
+    fn absorb_one(&mut self, element: &T) {
+        if self.pos_absorb == Self::RATE {
+            self.safe.permute(&mut self.state);
+
+            self.pos_absorb = 0;
+        }
+        // combine the input with the state using `Safe::combine`
+        let pos = self.pos_absorb + Self::CAPACITY;
+        let previous_value = self.state[pos];
+        let combined = self.safe.combine(&previous_value, element);
+        self.state[pos] = combined;
+        self.pos_absorb += 1;
+    }
+
+    /// This permutes the state and erases its rate, as per a `Call::Ratchet`
+    /// in the IO-pattern.
+    ///
+    /// Since the permutation can't be inverted once the rate is erased, a
+    /// later compromise of the state doesn't reveal the elements absorbed
+    /// and squeezed before the ratchet, which gives long-lived transcripts
+    /// forward secrecy. The following call to squeeze permutes the state
+    /// again before extracting any element.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    pub fn ratchet(&mut self) -> Result<(), Error> {
         // Check that the IO-pattern is followed
-        match self.iopattern.get(self.io_count) {
+        if self.expected_call(Call::Ratchet) != Some(Call::Ratchet) {
+            return Err(
+                self.fail(Error::IOPatternViolation, Some(Call::Ratchet))
+            );
+        }
+
+        // Permute the state and erase its rate
+        self.safe.permute(&mut self.state);
+        self.state[Self::CAPACITY..]
+            .iter_mut()
+            .for_each(|element| element.zeroize());
+        #[cfg(feature = "audit")]
+        self.audit_log.push(AuditEntry {
+            call: Call::Ratchet,
+            digest: T::default(),
+        });
+
+        // Force a permutation at the next call to squeeze
+        self.pos_absorb = 0;
+        self.pos_squeeze = Self::RATE;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(io_count = self.io_count, "ratcheted");
+
+        #[cfg(feature = "test-utils")]
+        self.take_snapshot(Some(self.io_count));
+
+        // Increase the position for the IO-pattern
+        self.io_count += 1;
+
+        Ok(())
+    }
+
+    /// This extracts `len` field elements from the state with interleaving
+    /// calls to the permutation function. It also checks if the call matches
+    /// the IO-pattern.
+    ///
+    /// The elements are appended to the output of the sponge, or replace it
+    /// when the sponge runs in [`OutputMode::Overwrite`].
+    ///
+    /// # Parameters
+    ///
+    /// - `len`: The number of field elements to squeeze.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    pub fn squeeze(&mut self, len: usize) -> Result<(), Error> {
+        self.check_squeeze(len)?;
+        if self.output_mode == OutputMode::Overwrite {
+            self.output.clear();
+        }
+        // without the heap, the output can't outgrow its inline capacity
+        #[cfg(not(feature = "alloc"))]
+        if self.output.len() + len > crate::output::INLINE_CAPACITY {
+            let call = Some(Call::Squeeze(len));
+            return Err(self.fail(Error::OutputLenMismatch, call));
+        }
+
+        // Squeeze 'len` field elements from the state
+        for _ in 0..len {
+            let element = self.squeeze_one();
+            self.output.push(element);
+        }
+        #[cfg(feature = "audit")]
+        if let Err(e) = record(
+            &mut self.safe,
+            &mut self.audit_log,
+            Call::Squeeze(len),
+            self.output[self.output.len() - len..].iter().copied(),
+        ) {
+            return Err(self.fail(e, Some(Call::Squeeze(len))));
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(len, io_count = self.io_count, "squeezed");
+
+        #[cfg(feature = "test-utils")]
+        self.take_snapshot(Some(self.io_count));
+
+        // Increase the position for the IO-pattern
+        self.io_count += 1;
+
+        Ok(())
+    }
+
+    /// This squeezes `len` field elements from the state like
+    /// [`Self::squeeze`], but passes them to the `writer` instead of
+    /// appending them to the output of the sponge.
+    ///
+    /// This way the elements can be streamed directly into an encoder, an
+    /// accumulator or a circuit. The elements are not part of the output
+    /// returned by [`Self::finish`].
+    ///
+    /// # Parameters
+    ///
+    /// - `writer`: The destination of the squeezed elements.
+    /// - `len`: The number of field elements to squeeze.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed.
+    pub fn squeeze_to(
+        &mut self,
+        mut writer: impl FieldWriter<T>,
+        len: usize,
+    ) -> Result<(), Error> {
+        self.check_squeeze(len)?;
+
+        // Squeeze 'len` field elements from the state
+        #[cfg(feature = "audit")]
+        let mut elements = Vec::with_capacity(len);
+        for _ in 0..len {
+            let element = self.squeeze_one();
+            #[cfg(feature = "audit")]
+            elements.push(element);
+            writer.write(element);
+        }
+        #[cfg(feature = "audit")]
+        {
+            let recorded = record(
+                &mut self.safe,
+                &mut self.audit_log,
+                Call::Squeeze(len),
+                elements.iter().copied(),
+            );
+            elements.zeroize();
+            if let Err(e) = recorded {
+                return Err(self.fail(e, Some(Call::Squeeze(len))));
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(len, io_count = self.io_count, "squeezed");
+
+        #[cfg(feature = "test-utils")]
+        self.take_snapshot(Some(self.io_count));
+
+        // Increase the position for the IO-pattern
+        self.io_count += 1;
+
+        Ok(())
+    }
+
+    /// This squeezes `len` field elements from the state like
+    /// [`Self::squeeze`], but writes them into the first `len` elements of
+    /// `buf` instead of appending them to the output of the sponge.
+    ///
+    /// This avoids allocating the output and copying it out of the
+    /// [`Output`] returned by [`Self::finish`]. The elements are not part of
+    /// that output.
+    ///
+    /// # Parameters
+    ///
+    /// - `len`: The number of field elements to squeeze.
+    /// - `buf`: The buffer to write the squeezed elements into.
+    ///
+    /// # Returns
+    ///
+    /// A result indicating success if the operation completes, or an `Error`
+    /// if the IO-pattern wasn't followed or `buf` holds less than `len`
+    /// elements. The length of `buf` is checked before anything else, so in
+    /// the latter case the sponge is left untouched and the call can be
+    /// repeated with a larger buffer.
+    pub fn squeeze_into(
+        &mut self,
+        len: usize,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        // a buffer that is too short is a mistake of the caller rather than a
+        // deviation from the IO-pattern, so the sponge stays usable
+        if buf.len() < len {
+            return Err(Error::OutputLenMismatch);
+        }
+        self.squeeze_to(buf[..len].iter_mut(), len)
+    }
+
+    /// Checks that a call to squeeze `len` elements follows the IO-pattern.
+    fn check_squeeze(&mut self, len: usize) -> Result<(), Error> {
+        match self.expected_call(Call::Squeeze(len)) {
             // only proceed if we expect a call to squeeze with the correct
             // length as per the IO-pattern
-            Some(Call::Squeeze(call_len)) if *call_len == len => {}
+            Some(Call::Squeeze(call_len)) if call_len == len => {}
             Some(Call::Squeeze(_)) => {
-                self.zeroize();
-                return Err(Error::IOPatternViolation);
+                let call = Some(Call::Squeeze(len));
+                return Err(self.fail(Error::IOPatternViolation, call));
             }
             _ => {
-                self.zeroize();
-                return Err(Error::IOPatternViolation);
+                let call = Some(Call::Squeeze(len));
+                return Err(self.fail(Error::IOPatternViolation, call));
             }
         }
+        Ok(())
+    }
 
-        // Squeeze 'len` field elements from the state, calling [`permute`] when
-        // the squeeze-position reached the rate.
-        for _ in 0..len {
-            if self.pos_squeeze == Self::RATE {
+    /// Extracts one element from the state, calling [`Safe::permute`] when
+    /// the squeeze-position reached the rate.
+    fn squeeze_one(&mut self) -> T {
+        // in full duplex mode, absorb and squeeze share the absorb-position,
+        // while a squeeze-position at the rate marks that something was
+        // absorbed since the last permutation
+        if self.duplex {
+            if self.pos_absorb == Self::RATE || self.pos_squeeze == Self::RATE {
                 self.safe.permute(&mut self.state);
 
-                self.pos_squeeze = 0;
                 self.pos_absorb = 0;
+                self.pos_squeeze = 0;
             }
-            self.output
-                .push(self.state[self.pos_squeeze + Self::CAPACITY]);
-            self.pos_squeeze += 1;
+            let element = self.state[self.pos_absorb + Self::CAPACITY];
+            self.pos_absorb += 1;
+            return element;
         }
+        if self.pos_squeeze == Self::RATE {
+            self.safe.permute(&mut self.state);
 
-        // Increase the position for the IO-pattern
-        self.io_count += 1;
+            self.pos_squeeze = 0;
+            self.pos_absorb = 0;
+        }
+        let element = self.state[self.pos_squeeze + Self::CAPACITY];
+        self.pos_squeeze += 1;
+        element
+    }
 
-        Ok(())
+    /// This squeezes [`SqueezeInto::LEN`] field elements from the state like
+    /// [`Self::squeeze`], and constructs a value of type `V` from them.
+    ///
+    /// The squeezed elements are still added to the output of the sponge.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the constructed value if the operation completes,
+    /// or an `Error` if the IO-pattern wasn't followed.
+    pub fn squeeze_typed<V>(&mut self) -> Result<V, Error>
+    where
+        V: SqueezeInto<T>,
+    {
+        self.squeeze(V::LEN)?;
+        let start = self.output.len() - V::LEN;
+        Ok(V::from_elements(&self.output[start..]))
+    }
+
+    /// This squeezes a single element from the state like [`Self::squeeze`],
+    /// for a `Call::Squeeze(1)` in the IO-pattern, and returns it.
+    ///
+    /// The squeezed element is still added to the output of the sponge.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the squeezed element if the operation completes,
+    /// or an `Error` if the IO-pattern wasn't followed.
+    pub fn squeeze_element(&mut self) -> Result<T, Error> {
+        self.squeeze(1)?;
+        Ok(self.output[self.output.len() - 1])
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl<S, T, const W: usize> subtle::ConstantTimeEq for Sponge<S, T, W>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + subtle::ConstantTimeEq,
+{
+    // The safe implementation is not part of the comparison. The lengths of
+    // the output and the IO-pattern are public, only the elements of the
+    // state and the output are compared in constant time.
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        let public = self.pos_absorb == other.pos_absorb
+            && self.pos_squeeze == other.pos_squeeze
+            && self.io_count == other.io_count
+            && self.partial == other.partial
+            && self.iopattern == other.iopattern
+            && self.domain_sep == other.domain_sep
+            && self.version == other.version
+            && self.unbounded == other.unbounded
+            && self.duplex == other.duplex
+            && self.output_mode == other.output_mode
+            && self.output.len() == other.output.len();
+        if !public {
+            return subtle::Choice::from(0);
+        }
+        self.state
+            .iter()
+            .chain(self.output.iter())
+            .zip(other.state.iter().chain(other.output.iter()))
+            .fold(subtle::Choice::from(1), |eq, (a, b)| eq & a.ct_eq(b))
     }
 }
 
 impl<S, T, const W: usize> Drop for Sponge<S, T, W>
 where
     S: Safe<T, W>,
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
 {
     fn drop(&mut self) {
         self.zeroize();
@@ -272,7 +2180,7 @@ where
 impl<S, T, const W: usize> Zeroize for Sponge<S, T, W>
 where
     S: Safe<T, W>,
-    T: Default + Copy + Zeroize,
+    T: SpongeElement,
 {
     fn zeroize(&mut self) {
         self.state.zeroize();
@@ -281,3 +2189,43 @@ where
         self.output.zeroize();
     }
 }
+
+/// Reads the serialization of a suspended sponge, see [`Sponge::resume`].
+#[cfg(feature = "alloc")]
+struct Reader<'a>(&'a [u8]);
+
+#[cfg(feature = "alloc")]
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < n {
+            return Err(Error::InvalidEncoding);
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn len(&mut self) -> Result<usize, Error> {
+        usize::try_from(self.u64()?).map_err(|_| Error::InvalidEncoding)
+    }
+
+    // reads the amount of items of `size` bytes that follow, making sure
+    // that the remaining bytes can hold them before anything is allocated
+    fn count(&mut self, size: usize) -> Result<usize, Error> {
+        let count = self.len()?;
+        match count.checked_mul(size) {
+            Some(bytes) if bytes <= self.0.len() => Ok(count),
+            _ => Err(Error::InvalidEncoding),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Trait for domain types that are constructed from elements squeezed out of
+/// a sponge with elements of type `T`, e.g. challenges or indices.
+///
+/// The mapping from the squeezed elements to the value should be canonical
+/// and documented by the implementor, e.g. "the challenge is the lower 128
+/// bits of the little endian encoding of the element", so that prover and
+/// verifier always derive the same value. See
+/// [`Sponge::squeeze_typed`](crate::Sponge::squeeze_typed).
+pub trait SqueezeInto<T>: Sized {
+    /// The amount of elements needed to construct the value.
+    const LEN: usize;
+
+    /// Constructs the value from exactly [`Self::LEN`] squeezed elements.
+    fn from_elements(elements: &[T]) -> Self;
+}
+
+impl<T, const N: usize> SqueezeInto<T> for [T; N]
+where
+    T: Default + Copy,
+{
+    const LEN: usize = N;
+
+    fn from_elements(elements: &[T]) -> Self {
+        let mut array = [T::default(); N];
+        array.copy_from_slice(elements);
+        array
+    }
+}
+
+/// Trait for destinations that squeezed elements of type `T` are streamed
+/// into, e.g. encoders, accumulators or circuits. See
+/// [`Sponge::squeeze_to`](crate::Sponge::squeeze_to).
+pub trait FieldWriter<T> {
+    /// Writes one squeezed element.
+    fn write(&mut self, t: T);
+}
+
+#[cfg(feature = "alloc")]
+impl<T> FieldWriter<T> for Vec<T> {
+    fn write(&mut self, t: T) {
+        self.push(t);
+    }
+}
+
+/// Writes the squeezed elements into the slice, one after the other. Elements
+/// written past the end of the slice are dropped.
+impl<T> FieldWriter<T> for core::slice::IterMut<'_, T> {
+    fn write(&mut self, t: T) {
+        if let Some(dest) = self.next() {
+            *dest = t;
+        }
+    }
+}
+
+impl<T, W> FieldWriter<T> for &mut W
+where
+    W: FieldWriter<T> + ?Sized,
+{
+    fn write(&mut self, t: T) {
+        (**self).write(t);
+    }
+}
@@ -0,0 +1,334 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use std::io::{self, Read, Write};
+
+use zeroize::Zeroize;
+
+use crate::domain::{construction_domain, STREAM_ID, STREAM_LAST_ID};
+use crate::{
+    decrypt, encrypt, Call, Encryption, Error, Safe, Sponge, SpongeElement,
+};
+
+/// The amount of elements of plain-text that are encrypted together in one
+/// chunk of a stream.
+///
+/// Each chunk additionally contains one element encoding the amount of bytes
+/// in the chunk, and its encryption adds one more element to the
+/// cipher-text.
+pub const STREAM_CHUNK_LEN: usize = 64;
+
+/// The IO-pattern for deriving the nonce of a chunk.
+const CHUNK_NONCE_IOPATTERN: [Call; 2] = [Call::Absorb(2), Call::Squeeze(1)];
+
+/// Encrypts all bytes from the `reader` and writes the cipher-text to the
+/// `writer`.
+///
+/// The plain-text is split into chunks of [`STREAM_CHUNK_LEN`] elements of
+/// `N - 1` bytes each, and every chunk is encrypted with [`encrypt`] under a
+/// nonce derived from the `nonce` and the index of the chunk. The last chunk
+/// is encrypted under a different domain-separator, so that truncating the
+/// cipher-text at a chunk boundary is detected by the decryption.
+///
+/// Bytes are embedded into elements with the `decode` function, by appending
+/// a zero byte to every `N - 1` bytes of plain-text. The encoding therefore
+/// needs to store the most significant byte last, so that every such array
+/// of bytes is a canonical encoding of an element. The length of each chunk
+/// is embedded the same way as its 8-byte little endian encoding, so `N`
+/// needs to be larger than 8.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `reader`: The source of the plain-text.
+/// - `writer`: The destination of the cipher-text.
+/// - `shared_secret`: The shared secret key used for encryption.
+/// - `nonce`: A unique value for the encryption of the stream.
+/// - `encode`: Serializes an element into `N` bytes.
+/// - `decode`: Deserializes `N` bytes into an element.
+///
+/// # Returns
+///
+/// A result indicating success if the whole stream was encrypted, or an
+/// `io::Error` if reading or writing failed or the encryption failed.
+#[allow(clippy::too_many_arguments)]
+pub fn encrypt_stream<E, T, const W: usize, const N: usize>(
+    safe: E,
+    domain_sep: u64,
+    mut reader: impl Read,
+    mut writer: impl Write,
+    shared_secret: &[T; 2],
+    nonce: &T,
+    encode: impl Fn(&T) -> [u8; N],
+    decode: impl Fn(&[u8; N]) -> Option<T>,
+) -> io::Result<()>
+where
+    E: Safe<T, W> + Encryption<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    if N <= 8 {
+        return Err(Error::InvalidEncoding.into());
+    }
+    let chunk_bytes = STREAM_CHUNK_LEN * (N - 1);
+    let mut chunk = vec![0u8; chunk_bytes];
+    let mut next = vec![0u8; chunk_bytes];
+    let mut message = Vec::with_capacity(STREAM_CHUNK_LEN + 1);
+
+    let mut encrypt_chunks = || -> io::Result<()> {
+        let mut len = read_full(&mut reader, &mut chunk)?;
+        let mut index = 0;
+        loop {
+            // a chunk is the last one if the reader is exhausted after it
+            let next_len = match len == chunk_bytes {
+                true => read_full(&mut reader, &mut next)?,
+                false => 0,
+            };
+            let last = next_len == 0;
+
+            // embed the length and the bytes of the chunk into elements
+            message.clear();
+            message.push(embed(&(len as u64).to_le_bytes(), &decode)?);
+            for bytes in chunk[..len].chunks(N - 1) {
+                message.push(embed(bytes, &decode)?);
+            }
+
+            let domain_sep = chunk_domain(domain_sep, last)?;
+            let nonce = chunk_nonce(safe.clone(), domain_sep, nonce, index)?;
+            let cipher = encrypt(
+                safe.clone(),
+                domain_sep,
+                &message,
+                shared_secret,
+                &nonce,
+            )?;
+            for element in cipher {
+                writer.write_all(&encode(&element))?;
+            }
+
+            if last {
+                return Ok(());
+            }
+            core::mem::swap(&mut chunk, &mut next);
+            len = next_len;
+            index += 1;
+        }
+    };
+    let result = encrypt_chunks();
+
+    chunk.zeroize();
+    next.zeroize();
+    message.zeroize();
+    result
+}
+
+/// Decrypts the cipher-text from the `reader`, as written by
+/// [`encrypt_stream`], and writes the plain-text to the `writer`.
+///
+/// Every chunk is authenticated before its plain-text is written, so when
+/// decryption fails, the `writer` may already contain the plain-text of the
+/// preceding chunks and the caller is responsible for discarding it.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `reader`: The source of the cipher-text.
+/// - `writer`: The destination of the plain-text.
+/// - `shared_secret`: The shared secret key used for decryption.
+/// - `nonce`: The nonce used for the encryption of the stream.
+/// - `encode`: Serializes an element into `N` bytes.
+/// - `decode`: Deserializes `N` bytes into an element.
+///
+/// # Returns
+///
+/// A result indicating success if the whole stream was decrypted, or an
+/// `io::Error` if reading or writing failed, the cipher-text is malformed or
+/// the decryption failed.
+#[allow(clippy::too_many_arguments)]
+pub fn decrypt_stream<E, T, const W: usize, const N: usize>(
+    safe: E,
+    domain_sep: u64,
+    mut reader: impl Read,
+    mut writer: impl Write,
+    shared_secret: &[T; 2],
+    nonce: &T,
+    encode: impl Fn(&T) -> [u8; N],
+    decode: impl Fn(&[u8; N]) -> Option<T>,
+) -> io::Result<()>
+where
+    E: Safe<T, W> + Encryption<T, W> + Clone,
+    T: SpongeElement + From<u64>,
+{
+    if N <= 8 {
+        return Err(Error::InvalidEncoding.into());
+    }
+    let chunk_bytes = STREAM_CHUNK_LEN * (N - 1);
+    let cipher_bytes = (STREAM_CHUNK_LEN + 2) * N;
+    let mut chunk = vec![0u8; cipher_bytes];
+    let mut next = vec![0u8; cipher_bytes];
+    let mut plain = Vec::with_capacity(chunk_bytes);
+
+    let mut decrypt_chunks = || -> io::Result<()> {
+        let mut len = read_full(&mut reader, &mut chunk)?;
+        let mut index = 0;
+        loop {
+            // a chunk is the last one if the reader is exhausted after it
+            let next_len = match len == cipher_bytes {
+                true => read_full(&mut reader, &mut next)?,
+                false => 0,
+            };
+            let last = next_len == 0;
+
+            if len % N != 0 || len < 2 * N {
+                return Err(Error::InvalidEncoding.into());
+            }
+            let cipher = chunk[..len]
+                .chunks_exact(N)
+                .map(|bytes| {
+                    let mut element = [0u8; N];
+                    element.copy_from_slice(bytes);
+                    decode(&element).ok_or(Error::InvalidEncoding)
+                })
+                .collect::<Result<Vec<T>, Error>>()?;
+
+            let domain_sep = chunk_domain(domain_sep, last)?;
+            let nonce = chunk_nonce(safe.clone(), domain_sep, nonce, index)?;
+            let message = decrypt(
+                safe.clone(),
+                domain_sep,
+                &cipher,
+                shared_secret,
+                &nonce,
+            )?;
+
+            // unpack the length and the bytes of the chunk from the elements
+            plain.clear();
+            let unpacked = message
+                .iter()
+                .try_for_each(|element| unembed(element, &encode, &mut plain));
+            drop(message);
+            unpacked?;
+            let plain_len = chunk_len::<N>(&plain, chunk_bytes, last)?;
+            writer.write_all(&plain[N - 1..N - 1 + plain_len])?;
+
+            if last {
+                return Ok(());
+            }
+            core::mem::swap(&mut chunk, &mut next);
+            len = next_len;
+            index += 1;
+        }
+    };
+    let result = decrypt_chunks();
+
+    chunk.zeroize();
+    next.zeroize();
+    plain.zeroize();
+    result
+}
+
+/// Embeds fewer than `N` bytes into an element, by padding them with zeros
+/// and decoding them.
+fn embed<T, const N: usize>(
+    bytes: &[u8],
+    decode: impl Fn(&[u8; N]) -> Option<T>,
+) -> Result<T, Error> {
+    let mut padded = [0u8; N];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    let element = decode(&padded);
+    padded.zeroize();
+    element.ok_or(Error::InvalidEncoding)
+}
+
+/// Appends the `N - 1` bytes embedded into an element to `plain`, checking
+/// that the most significant byte is zero.
+fn unembed<T, const N: usize>(
+    element: &T,
+    encode: impl Fn(&T) -> [u8; N],
+    plain: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let mut bytes = encode(element);
+    plain.extend_from_slice(&bytes[..N - 1]);
+    let top = bytes[N - 1];
+    bytes.zeroize();
+    match top {
+        0 => Ok(()),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+/// Returns the length of the plain-text of a decrypted chunk, where the first
+/// `N - 1` bytes of `plain` encode the length, making sure that the chunk is
+/// encoded canonically.
+fn chunk_len<const N: usize>(
+    plain: &[u8],
+    chunk_bytes: usize,
+    last: bool,
+) -> Result<usize, Error> {
+    let (len, bytes) = plain.split_at(N - 1);
+    let (len, rest) = len.split_at(8);
+    let mut len_bytes = [0u8; 8];
+    len_bytes.copy_from_slice(len);
+    let len = usize::try_from(u64::from_le_bytes(len_bytes))
+        .map_err(|_| Error::InvalidEncoding)?;
+
+    let canonical = rest.iter().all(|byte| *byte == 0)
+        && len <= chunk_bytes
+        && (last || len == chunk_bytes)
+        && bytes.len() == (len + N - 2) / (N - 1) * (N - 1)
+        && bytes[len..].iter().all(|byte| *byte == 0);
+    match canonical {
+        true => Ok(len),
+        false => Err(Error::InvalidEncoding),
+    }
+}
+
+/// Returns the domain-separator under which a chunk is encrypted.
+fn chunk_domain(domain_sep: u64, last: bool) -> Result<u64, Error> {
+    match last {
+        true => construction_domain(STREAM_LAST_ID, domain_sep),
+        false => construction_domain(STREAM_ID, domain_sep),
+    }
+}
+
+/// Derives the nonce of the chunk with the given index from the nonce of the
+/// stream.
+fn chunk_nonce<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    nonce: &T,
+    index: u64,
+) -> Result<T, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let mut sponge =
+        Sponge::start_static(safe, &CHUNK_NONCE_IOPATTERN, domain_sep)?;
+    sponge.absorb(2, [*nonce, T::from(index)])?;
+    sponge.squeeze(1)?;
+    Ok(sponge.finish()?[0])
+}
+
+/// Reads from the `reader` until the buffer is full or the reader is
+/// exhausted, and returns the amount of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
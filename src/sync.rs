@@ -0,0 +1,97 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::Error;
+
+/// A compact commitment to the progress of a sponge, for asserting that a
+/// prover and a verifier are in sync in the middle of a protocol, see
+/// [`Sponge::sync_point`](crate::Sponge::sync_point).
+///
+/// It doesn't contain the state of the sponge, but binds the IO-pattern with
+/// its [`pattern_digest`](crate::pattern_digest), the position in the
+/// IO-pattern with the amount of calls made, and the values absorbed so far
+/// with the [`session_hash`](crate::Sponge::session_hash). Two sync points
+/// are therefore only equal if the sponges run the same IO-pattern under the
+/// same domain-separator, made the same amount of calls and absorbed the same
+/// values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncPoint<T> {
+    /// The domain-separator of the sponge.
+    pub domain_sep: u64,
+    /// The amount of calls of the IO-pattern that were made so far.
+    pub io_count: u64,
+    /// The digest of the IO-pattern and the domain-separator.
+    pub pattern_digest: T,
+    /// The digest of the values absorbed so far, which is the session hash
+    /// of the sponge.
+    pub values_digest: T,
+}
+
+impl<T> SyncPoint<T> {
+    /// Serializes the sync point into `16 + 2 * N` bytes: the
+    /// domain-separator and the amount of calls as 8 bytes little endian
+    /// each, followed by the encoded digests of the IO-pattern and of the
+    /// values.
+    ///
+    /// # Parameters
+    ///
+    /// - `encode`: Serializes an element into `N` bytes.
+    ///
+    /// # Returns
+    ///
+    /// The serialized sync point.
+    #[cfg(feature = "alloc")]
+    pub fn to_bytes<const N: usize>(
+        &self,
+        encode: impl Fn(&T) -> [u8; N],
+    ) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + 2 * N);
+        bytes.extend_from_slice(&self.domain_sep.to_le_bytes());
+        bytes.extend_from_slice(&self.io_count.to_le_bytes());
+        bytes.extend_from_slice(&encode(&self.pattern_digest));
+        bytes.extend_from_slice(&encode(&self.values_digest));
+        bytes
+    }
+
+    /// Deserializes a sync point serialized with [`SyncPoint::to_bytes`].
+    ///
+    /// # Parameters
+    ///
+    /// - `bytes`: The serialized sync point.
+    /// - `decode`: Deserializes `N` bytes into an element.
+    ///
+    /// # Returns
+    ///
+    /// The sync point, or an `Error` if the bytes are not a valid encoding.
+    pub fn from_bytes<const N: usize>(
+        bytes: &[u8],
+        decode: impl Fn(&[u8; N]) -> Option<T>,
+    ) -> Result<Self, Error> {
+        if bytes.len() != 16 + 2 * N {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut domain_sep = [0u8; 8];
+        domain_sep.copy_from_slice(&bytes[..8]);
+        let mut io_count = [0u8; 8];
+        io_count.copy_from_slice(&bytes[8..16]);
+        let mut pattern_digest = [0u8; N];
+        pattern_digest.copy_from_slice(&bytes[16..16 + N]);
+        let mut values_digest = [0u8; N];
+        values_digest.copy_from_slice(&bytes[16 + N..]);
+
+        Ok(Self {
+            domain_sep: u64::from_le_bytes(domain_sep),
+            io_count: u64::from_le_bytes(io_count),
+            pattern_digest: decode(&pattern_digest)
+                .ok_or(Error::InvalidEncoding)?,
+            values_digest: decode(&values_digest)
+                .ok_or(Error::InvalidEncoding)?,
+        })
+    }
+}
@@ -0,0 +1,224 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Add, Mul};
+
+use digest::typenum::{IsGreaterOrEqual, True, U16};
+use digest::{Digest, OutputSizeUser};
+
+/// The amount of digest bytes that are reduced into the tag.
+const WIDE_LEN: usize = 64;
+
+/// Hashes the tag input into an element of a prime field, for implementing
+/// [`Safe::tag`](crate::Safe::tag) with a generic byte hash.
+///
+/// The input is hashed into 64 bytes by concatenating the digests of
+/// `[i] || input` for a counter byte `i = 0, 1, ...`, and truncating the
+/// result to 64 bytes. The 64 bytes are then interpreted as a little endian
+/// integer and reduced modulo the order of the field. Since the order of the
+/// fields used with this crate is much smaller than `2^512`, the bias of the
+/// reduction is negligible.
+///
+/// # Parameters
+///
+/// - `input`: The tag input as passed to [`Safe::tag`](crate::Safe::tag).
+///
+/// # Returns
+///
+/// The tag as an element of type `T`.
+pub fn tag_from_digest<D, T>(input: &[u8]) -> T
+where
+    D: Digest,
+    T: From<u64> + Add<Output = T> + Mul<Output = T>,
+{
+    // hash the input into 64 bytes
+    let mut wide = [0u8; WIDE_LEN];
+    let mut len = 0;
+    let mut counter = 0u8;
+    while len < WIDE_LEN {
+        let digest = D::new().chain_update([counter]).chain_update(input);
+        let digest = digest.finalize();
+        let n = digest.len().min(WIDE_LEN - len);
+        wide[len..len + n].copy_from_slice(&digest[..n]);
+        len += n;
+        counter += 1;
+    }
+
+    // reduce the little endian integer, starting with the most significant
+    // limb: tag = tag * 2^64 + limb
+    let two_pow_64 = || T::from(u64::MAX) + T::from(1);
+    wide.rchunks_exact(8).fold(T::from(0), |tag, limb| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(limb);
+        tag * two_pow_64() + T::from(u64::from_le_bytes(bytes))
+    })
+}
+
+/// The amount of digest bytes that make the tag of the SAFE specification.
+const SPEC_TAG_LEN: usize = 16;
+
+/// Hashes the tag input into an element like the original SAFE
+/// specification, for interoperating with other implementations of SAFE that
+/// expect its standard initial value.
+///
+/// The tag is the first 128 bits of the digest of the tag input, with `D`
+/// being SHA3-256 in the specification, read as a little endian integer.
+/// Since it is smaller than the order of the fields used with this crate, it
+/// doesn't need to be reduced. The tag input of a sponge started with
+/// [`Sponge::start`](crate::Sponge::start) follows the encoding of the
+/// specification, see [`tag_input`](crate::tag_input). Digests shorter than
+/// 128 bits are rejected at compile time.
+///
+/// # Parameters
+///
+/// - `input`: The tag input as passed to [`Safe::tag`](crate::Safe::tag).
+///
+/// # Returns
+///
+/// The tag as an element of type `T`.
+pub fn spec_tag_from_digest<D, T>(input: &[u8]) -> T
+where
+    D: Digest,
+    <D as OutputSizeUser>::OutputSize: IsGreaterOrEqual<U16, Output = True>,
+    T: From<u64> + Add<Output = T> + Mul<Output = T>,
+{
+    let digest = D::digest(input);
+
+    // tag = hi * 2^64 + lo
+    let limb = |bytes: &[u8]| {
+        let mut limb = [0u8; 8];
+        limb.copy_from_slice(bytes);
+        T::from(u64::from_le_bytes(limb))
+    };
+    let two_pow_64 = T::from(u64::MAX) + T::from(1);
+    limb(&digest[8..SPEC_TAG_LEN]) * two_pow_64 + limb(&digest[..8])
+}
+
+/// Adapter computing the tag of a sponge with the byte hash `D`, so that a
+/// [`Safe`](crate::Safe) backend can keep its field permutation for the body
+/// of the sponge while hashing the tag input with e.g. BLAKE2 or SHA3.
+///
+/// The tag is computed with [`tag_from_digest`], which documents how the
+/// digest is reduced into an element. Backends that need to interoperate
+/// with other implementations of SAFE select the initial value of the
+/// specification instead by forwarding to [`DigestTag::spec_tag`].
+///
+/// # Example
+///
+/// A backend stores the adapter and forwards [`Safe::tag`](crate::Safe::tag)
+/// to it:
+///
+/// ```
+/// use dusk_bls12_381::BlsScalar;
+/// use dusk_safe::{DigestTag, Safe};
+/// use sha2::Sha512;
+///
+/// struct Backend {
+///     tag_hasher: DigestTag<Sha512>,
+/// }
+///
+/// impl Safe<BlsScalar, 3> for Backend {
+///     fn permute(&mut self, state: &mut [BlsScalar; 3]) {
+///         // the field permutation of the backend
+/// #       let sum: BlsScalar = state.iter().sum();
+/// #       state.iter_mut().for_each(|s| *s = (*s + sum).square());
+///     }
+///
+///     fn tag(&mut self, input: &[u8]) -> BlsScalar {
+///         self.tag_hasher.tag(input)
+///     }
+///
+///     fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+///         right + left
+///     }
+/// }
+///
+/// let mut backend = Backend {
+///     tag_hasher: DigestTag::new(),
+/// };
+/// assert_eq!(
+///     backend.tag(b"tag input"),
+///     DigestTag::<Sha512>::new().tag::<BlsScalar>(b"tag input"),
+/// );
+/// ```
+pub struct DigestTag<D>(PhantomData<fn() -> D>);
+
+impl<D> DigestTag<D>
+where
+    D: Digest,
+{
+    /// Creates a new adapter for the byte hash `D`.
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// Hashes the tag input into an element of a prime field.
+    ///
+    /// # Parameters
+    ///
+    /// - `input`: The tag input as passed to [`Safe::tag`](crate::Safe::tag).
+    ///
+    /// # Returns
+    ///
+    /// The tag as an element of type `T`.
+    pub fn tag<T>(&self, input: &[u8]) -> T
+    where
+        T: From<u64> + Add<Output = T> + Mul<Output = T>,
+    {
+        tag_from_digest::<D, T>(input)
+    }
+
+    /// Hashes the tag input into an element as per the original SAFE
+    /// specification, see [`spec_tag_from_digest`].
+    ///
+    /// # Parameters
+    ///
+    /// - `input`: The tag input as passed to [`Safe::tag`](crate::Safe::tag).
+    ///
+    /// # Returns
+    ///
+    /// The tag as an element of type `T`.
+    pub fn spec_tag<T>(&self, input: &[u8]) -> T
+    where
+        <D as OutputSizeUser>::OutputSize: IsGreaterOrEqual<U16, Output = True>,
+        T: From<u64> + Add<Output = T> + Mul<Output = T>,
+    {
+        spec_tag_from_digest::<D, T>(input)
+    }
+}
+
+impl<D> Default for DigestTag<D>
+where
+    D: Digest,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D> Clone for DigestTag<D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D> Copy for DigestTag<D> {}
+
+impl<D> fmt::Debug for DigestTag<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DigestTag")
+    }
+}
+
+impl<D> PartialEq for DigestTag<D> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<D> Eq for DigestTag<D> {}
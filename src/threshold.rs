@@ -0,0 +1,323 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+use crate::domain::{
+    construction_domain, KEYSTREAM_SHARE_ID, SHARED_TAG_ID, TAG_KEY_SHARE_ID,
+};
+use crate::prf::keyed_hash;
+use crate::{
+    critical_check, Call, Encryption, Error, Safe, Sponge, SpongeElement,
+};
+
+/// Derives the share of a keystream from the secret share of one party, for
+/// decrypting a message encrypted with [`encrypt_shared`] without
+/// reconstructing the key.
+///
+/// The keystream of a message is the sum of the keystream shares of all
+/// parties, see [`combine_keystream`]. Each share is the output of a keyed
+/// hash of the nonce under the secret share of the party, bound to the
+/// [`KEYSTREAM_SHARE_ID`](crate::domain::KEYSTREAM_SHARE_ID).
+///
+/// This is a scheme of its own, not a threshold decryption of the
+/// cipher-texts of [`encrypt`](crate::encrypt): their keystream is squeezed
+/// from a sponge keyed with the shared secret, a non-linear function of the
+/// key, so additive shares of the key don't result in shares of the
+/// keystream without a multi-party computation of the sponge. The sharing is
+/// n-of-n: the keystream needs the shares of all parties, there is no
+/// threshold below that.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `secret_share`: The secret share of the party, at least one element.
+/// - `nonce`: A unique value for the encryption of the message.
+/// - `len`: The amount of elements of the message.
+///
+/// # Returns
+///
+/// A result containing the keystream share, which is erased from memory
+/// when dropped, or an `Error` if the domain-separator or the lengths are
+/// invalid.
+pub fn keystream_share<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    secret_share: impl AsRef<[T]>,
+    nonce: &T,
+    len: usize,
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let share = keyed_hash(
+        safe,
+        KEYSTREAM_SHARE_ID,
+        domain_sep,
+        secret_share,
+        [*nonce],
+        len,
+    )?;
+    Ok(Zeroizing::new(share.to_vec()))
+}
+
+/// Combines the keystream shares of all parties into the keystream, by
+/// adding them element-wise.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `shares`: The keystream shares of all parties, see [`keystream_share`].
+///
+/// # Returns
+///
+/// A result containing the keystream, which is erased from memory when
+/// dropped, or an [`Error::InvalidInput`] if there are no shares or they
+/// differ in length.
+pub fn combine_keystream<S, T, const W: usize>(
+    safe: S,
+    shares: impl IntoIterator<Item = impl AsRef<[T]>>,
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let mut safe = safe;
+    let mut shares = shares.into_iter();
+    let first = shares.next().ok_or(Error::InvalidInput)?;
+    let mut keystream = Zeroizing::new(first.as_ref().to_vec());
+    for share in shares {
+        let share = share.as_ref();
+        if share.len() != keystream.len() {
+            return Err(Error::InvalidInput);
+        }
+        keystream
+            .iter_mut()
+            .zip(share)
+            .for_each(|(k, s)| *k = safe.add(k, s));
+    }
+    Ok(keystream)
+}
+
+/// Derives the share of the key authenticating a message encrypted with
+/// [`encrypt_shared`] from the secret share of one party.
+///
+/// The tag key is the sum of the tag key shares of all parties, see
+/// [`combine_tag_key`]. Each share is the output of a keyed hash of the nonce
+/// under the secret share of the party, bound to the
+/// [`TAG_KEY_SHARE_ID`](crate::domain::TAG_KEY_SHARE_ID), so that the tag
+/// key is independent of the keystream.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `secret_share`: The secret share of the party, at least one element.
+/// - `nonce`: A unique value for the encryption of the message.
+///
+/// # Returns
+///
+/// A result containing the tag key share, which is erased from memory when
+/// dropped, or an `Error` if the domain-separator or the secret share are
+/// invalid.
+pub fn tag_key_share<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    secret_share: impl AsRef<[T]>,
+    nonce: &T,
+) -> Result<Zeroizing<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement + From<u64>,
+{
+    let share = keyed_hash(
+        safe,
+        TAG_KEY_SHARE_ID,
+        domain_sep,
+        secret_share,
+        [*nonce],
+        1,
+    )?;
+    Ok(Zeroizing::new(share[0]))
+}
+
+/// Combines the tag key shares of all parties into the tag key, by adding
+/// them.
+///
+/// # Parameters
+///
+/// - `safe`: The sponge safe implementation.
+/// - `shares`: The tag key shares of all parties, see [`tag_key_share`].
+///
+/// # Returns
+///
+/// A result containing the tag key, which is erased from memory when
+/// dropped, or an [`Error::InvalidInput`] if there are no shares.
+pub fn combine_tag_key<S, T, const W: usize>(
+    safe: S,
+    shares: impl IntoIterator<Item = T>,
+) -> Result<Zeroizing<T>, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let mut safe = safe;
+    let mut shares = shares.into_iter();
+    let mut tag_key = Zeroizing::new(shares.next().ok_or(Error::InvalidInput)?);
+    for share in shares {
+        *tag_key = safe.add(&tag_key, &share);
+    }
+    Ok(tag_key)
+}
+
+/// Computes the element authenticating a message under a tag key and the
+/// nonce it was derived from.
+fn shared_tag<S, T, const W: usize>(
+    safe: S,
+    domain_sep: u64,
+    message: &[T],
+    tag_key: &T,
+    nonce: &T,
+) -> Result<T, Error>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    let len = message.len();
+    let iopattern = vec![Call::Absorb(2), Call::Absorb(len), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(
+        safe,
+        iopattern,
+        construction_domain(SHARED_TAG_ID, domain_sep)?,
+    )?;
+    sponge.absorb(2, [*tag_key, *nonce])?;
+    sponge.absorb(len, message)?;
+    sponge.squeeze(1)?;
+    Ok(sponge.finish()?[0])
+}
+
+/// Encrypts a message under a keystream that is additively shared among
+/// several parties, so that it can be decrypted by combining the keystream
+/// shares of all parties, see [`decrypt_shared`].
+///
+/// Each element of the message is added to the element of the keystream at
+/// the same position, and the cipher-text is extended with an element
+/// authenticating the message, squeezed from a sponge absorbing the tag key,
+/// the nonce and the message.
+///
+/// The encryptor needs the combined keystream and tag key, and therefore the
+/// shares of all parties for the nonce. Whoever combines the shares learns
+/// the keystream and the tag key of this message, but not the secret shares
+/// of the parties. The tag key is derived independently of the keystream, so
+/// that the keystream, which anyone knowing a message and its cipher-text
+/// can compute, doesn't allow forging cipher-texts. Whoever knows the tag
+/// key, such as its combiner, can still forge cipher-texts under the same
+/// nonce, like with any symmetric authentication. Every nonce must only be
+/// used for one message.
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator chosen by the user, with the most
+///   significant byte set to zero.
+/// - `message`: The message to encrypt, at least one element.
+/// - `keystream`: The keystream combined from the keystream shares of all
+///   parties, as long as the message.
+/// - `tag_key`: The tag key combined from the tag key shares of all parties.
+/// - `nonce`: The nonce the shares were derived from.
+///
+/// # Returns
+///
+/// A result containing the cipher-text of `message.len() + 1` elements, or
+/// an `Error` if the keystream doesn't match the message or the
+/// domain-separator is invalid.
+pub fn encrypt_shared<E, T, const W: usize>(
+    safe: E,
+    domain_sep: u64,
+    message: impl AsRef<[T]>,
+    keystream: &[T],
+    tag_key: &T,
+    nonce: &T,
+) -> Result<Vec<T>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W>,
+    T: SpongeElement,
+{
+    let message = message.as_ref();
+    if message.len() != keystream.len() {
+        return Err(Error::EncryptionFailed);
+    }
+
+    let mut safe = safe;
+    let mut cipher: Vec<T> = message
+        .iter()
+        .zip(keystream)
+        .map(|(m, k)| safe.add(m, k))
+        .collect();
+    cipher.push(shared_tag(safe, domain_sep, message, tag_key, nonce)?);
+    Ok(cipher)
+}
+
+/// Decrypts a cipher-text created with [`encrypt_shared`], using the
+/// keystream and the tag key combined from the shares of all parties.
+///
+/// The authentication doesn't protect against whoever knows the tag key, see
+/// [`encrypt_shared`].
+///
+/// # Parameters
+///
+/// - `safe`: An instance implementing the [`Safe`] and [`Encryption`] traits.
+/// - `domain_sep`: The domain separator used for the encryption.
+/// - `cipher`: The cipher-text to decrypt.
+/// - `keystream`: The keystream combined from the keystream shares of all
+///   parties, one element shorter than the cipher-text.
+/// - `tag_key`: The tag key combined from the tag key shares of all parties.
+/// - `nonce`: The nonce used for the encryption.
+///
+/// # Returns
+///
+/// A result containing the message, which is erased from memory when
+/// dropped, or an `Error` if the keystream doesn't match the cipher-text or
+/// the cipher-text fails to authenticate.
+pub fn decrypt_shared<E, T, const W: usize>(
+    safe: E,
+    domain_sep: u64,
+    cipher: impl AsRef<[T]>,
+    keystream: &[T],
+    tag_key: &T,
+    nonce: &T,
+) -> Result<Zeroizing<Vec<T>>, Error>
+where
+    E: Safe<T, W> + Encryption<T, W> + Clone,
+    T: SpongeElement,
+{
+    let cipher = cipher.as_ref();
+    if cipher.len() != keystream.len() + 1 {
+        return Err(Error::DecryptionFailed);
+    }
+    let (payload, tag) = cipher.split_at(keystream.len());
+
+    let mut safe = safe;
+    let message = Zeroizing::new(
+        payload
+            .iter()
+            .zip(keystream)
+            .map(|(c, k)| safe.subtract(c, k))
+            .collect::<Vec<T>>(),
+    );
+    let expected =
+        shared_tag(safe.clone(), domain_sep, &message, tag_key, nonce)?;
+    match critical_check(|| safe.is_equal(&expected, &tag[0])) {
+        true => Ok(message),
+        false => Err(Error::DecryptionFailed),
+    }
+}
@@ -0,0 +1,162 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+/// Trait to get the length of an array type in constant context, used by the
+/// [`transcript!`](crate::transcript) macro.
+#[doc(hidden)]
+pub trait ArrayLen {
+    const LEN: usize;
+}
+
+impl<T, const N: usize> ArrayLen for [T; N] {
+    const LEN: usize = N;
+}
+
+/// Describes a whole protocol and generates both its IO-pattern and a
+/// transcript struct with one method per step, so that the IO-pattern and the
+/// code using it are in sync by construction.
+///
+/// Every step is either
+/// - `absorb name: [T; N]`, generating a method `name(&mut self, &[T; N])` that
+///   absorbs `N` elements, or
+/// - `squeeze name: V`, generating a method `name(&mut self) -> Result<V, _>`
+///   that squeezes a value of a type implementing
+///   [`SqueezeInto`](crate::SqueezeInto), see
+///   [`Sponge::squeeze_typed`](crate::Sponge::squeeze_typed).
+///
+/// The generated struct is generic over the [`Safe`](crate::Safe)
+/// implementation `S` and the state width `W`, and additionally has a
+/// `start(safe, domain_sep)` and a `finish()` method. The IO-pattern is
+/// validated at compile time, while the order of the calls is still checked
+/// against it at runtime.
+///
+/// # Example
+///
+/// ```
+/// # use dusk_bls12_381::BlsScalar;
+/// # use dusk_safe::Safe;
+/// # const W: usize = 3;
+/// # #[derive(Default)]
+/// # struct Mix;
+/// # impl Safe<BlsScalar, W> for Mix {
+/// #     fn permute(&mut self, state: &mut [BlsScalar; W]) {
+/// #         let sum: BlsScalar = state.iter().sum();
+/// #         state.iter_mut().for_each(|s| *s = (*s + sum).square());
+/// #     }
+/// #     fn tag(&mut self, input: &[u8]) -> BlsScalar {
+/// #         BlsScalar::hash_to_scalar(input)
+/// #     }
+/// #     fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+/// #         right + left
+/// #     }
+/// # }
+/// use dusk_safe::{transcript, Call};
+///
+/// transcript! {
+///     /// The IO-pattern of the schnorr transcript.
+///     pub const SCHNORR_IO_PATTERN;
+///     /// The transcript of a schnorr signature.
+///     pub struct Schnorr: BlsScalar {
+///         absorb public_key: [BlsScalar; 2];
+///         absorb commitment: [BlsScalar; 2];
+///         squeeze challenge: [BlsScalar; 1];
+///     }
+/// }
+///
+/// assert_eq!(
+///     SCHNORR_IO_PATTERN,
+///     [Call::Absorb(2), Call::Absorb(2), Call::Squeeze(1)]
+/// );
+///
+/// let mut transcript = Schnorr::<_, W>::start(Mix, 42)?;
+/// transcript.public_key(&[BlsScalar::from(1), BlsScalar::from(2)])?;
+/// transcript.commitment(&[BlsScalar::from(3), BlsScalar::from(4)])?;
+/// let [challenge] = transcript.challenge()?;
+/// assert_eq!(transcript.finish()?[..], [challenge]);
+/// # Ok::<(), dusk_safe::Error>(())
+/// ```
+#[macro_export]
+macro_rules! transcript {
+    (
+        $(#[$pattern_meta:meta])*
+        $pattern_vis:vis const $pattern:ident;
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident : $elem:ty {
+            $(
+                $(#[$step_meta:meta])*
+                $kind:ident $step:ident : $ty:ty;
+            )*
+        }
+    ) => {
+        $(#[$pattern_meta])*
+        $pattern_vis const $pattern: &[$crate::Call] = &[
+            $($crate::transcript!(@call $kind $ty, $elem)),*
+        ];
+        const _: () = assert!(
+            $crate::is_valid_io_pattern($pattern),
+            "invalid IO-pattern",
+        );
+
+        $(#[$meta])*
+        $vis struct $name<S, const W: usize>
+        where
+            S: $crate::Safe<$elem, W>,
+        {
+            sponge: $crate::Sponge<S, $elem, W>,
+        }
+
+        impl<S, const W: usize> $name<S, W>
+        where
+            S: $crate::Safe<$elem, W>,
+        {
+            /// Starts the transcript with the given domain-separator.
+            pub fn start(
+                safe: S,
+                domain_sep: u64,
+            ) -> Result<Self, $crate::Error> {
+                Ok(Self {
+                    sponge: $crate::Sponge::start_static(
+                        safe, $pattern, domain_sep,
+                    )?,
+                })
+            }
+
+            $(
+                $crate::transcript!(
+                    @method $(#[$step_meta])* $kind $step $ty, $elem
+                );
+            )*
+
+            /// Finishes the transcript and returns everything squeezed.
+            pub fn finish(
+                self,
+            ) -> Result<$crate::Output<$elem>, $crate::Error> {
+                self.sponge.finish()
+            }
+        }
+    };
+
+    (@call absorb $ty:ty, $elem:ty) => {
+        $crate::Call::Absorb(<$ty as $crate::__private::ArrayLen>::LEN)
+    };
+    (@call squeeze $ty:ty, $elem:ty) => {
+        $crate::Call::Squeeze(<$ty as $crate::SqueezeInto<$elem>>::LEN)
+    };
+
+    (@method $(#[$meta:meta])* absorb $step:ident $ty:ty, $elem:ty) => {
+        $(#[$meta])*
+        pub fn $step(&mut self, input: &$ty) -> Result<(), $crate::Error> {
+            self.sponge
+                .absorb(<$ty as $crate::__private::ArrayLen>::LEN, input)
+        }
+    };
+    (@method $(#[$meta:meta])* squeeze $step:ident $ty:ty, $elem:ty) => {
+        $(#[$meta])*
+        pub fn $step(&mut self) -> Result<$ty, $crate::Error> {
+            self.sponge.squeeze_typed::<$ty>()
+        }
+    };
+}
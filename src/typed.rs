@@ -0,0 +1,232 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{Call, Error, Output, Safe, Sponge, SpongeElement};
+
+/// An IO-pattern encoded in the type system, for a [`TypedSponge`].
+///
+/// Patterns are built from [`Absorbing`] and [`Squeezing`] and end with
+/// [`Finished`], e.g. `Absorbing<2, Squeezing<1, Finished>>` for the
+/// IO-pattern `[Call::Absorb(2), Call::Squeeze(1)]`.
+pub trait TypedPattern {
+    /// Appends the calls of the pattern to the IO-pattern.
+    fn push_calls(calls: &mut Vec<Call>);
+}
+
+/// A call to absorb `N` elements, followed by the calls of `Rest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Absorbing<const N: usize, Rest>(PhantomData<Rest>);
+
+/// A call to squeeze `N` elements, followed by the calls of `Rest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Squeezing<const N: usize, Rest>(PhantomData<Rest>);
+
+/// The end of a [`TypedPattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Finished;
+
+impl<const N: usize, Rest: TypedPattern> TypedPattern for Absorbing<N, Rest> {
+    fn push_calls(calls: &mut Vec<Call>) {
+        calls.push(Call::Absorb(N));
+        Rest::push_calls(calls);
+    }
+}
+
+impl<const N: usize, Rest: TypedPattern> TypedPattern for Squeezing<N, Rest> {
+    fn push_calls(calls: &mut Vec<Call>) {
+        calls.push(Call::Squeeze(N));
+        Rest::push_calls(calls);
+    }
+}
+
+impl TypedPattern for Finished {
+    fn push_calls(_calls: &mut Vec<Call>) {}
+}
+
+/// The elements squeezed by a [`TypedSponge`], together with the sponge
+/// with the rest of the pattern.
+type Squeezed<S, T, const W: usize, const N: usize, Rest> =
+    ([T; N], TypedSponge<S, T, W, Rest>);
+
+/// A [`Sponge`] whose remaining IO-pattern `P` is part of its type, so that
+/// calling absorb or squeeze out of order or with the wrong length fails to
+/// compile instead of returning [`Error::IOPatternViolation`].
+///
+/// Every call consumes the sponge and returns it with the rest of the
+/// pattern, and only a sponge at the end of its pattern can be finished:
+///
+/// ```
+/// # use dusk_bls12_381::BlsScalar;
+/// # use dusk_safe::Safe;
+/// # const W: usize = 3;
+/// # #[derive(Default)]
+/// # struct Mix;
+/// # impl Safe<BlsScalar, W> for Mix {
+/// #     fn permute(&mut self, state: &mut [BlsScalar; W]) {
+/// #         let sum: BlsScalar = state.iter().sum();
+/// #         state.iter_mut().for_each(|s| *s = (*s + sum).square());
+/// #     }
+/// #     fn tag(&mut self, input: &[u8]) -> BlsScalar {
+/// #         BlsScalar::hash_to_scalar(input)
+/// #     }
+/// #     fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+/// #         right + left
+/// #     }
+/// # }
+/// use dusk_safe::{Absorbing, Finished, Squeezing, TypedSponge};
+///
+/// type Pattern = Absorbing<2, Squeezing<1, Finished>>;
+///
+/// let (a, b) = (BlsScalar::from(1), BlsScalar::from(2));
+/// let sponge = TypedSponge::<_, _, W, Pattern>::start(Mix, 42)?;
+/// let sponge = sponge.absorb(&[a, b])?;
+/// let (hash, sponge) = sponge.squeeze()?;
+/// sponge.finish()?;
+/// # let _ = hash;
+/// # Ok::<(), dusk_safe::Error>(())
+/// ```
+///
+/// Skipping the squeeze fails to compile, since only a sponge at the end of
+/// its pattern has a `finish` method:
+///
+/// ```compile_fail
+/// # use dusk_bls12_381::BlsScalar;
+/// # use dusk_safe::Safe;
+/// # const W: usize = 3;
+/// # #[derive(Default)]
+/// # struct Mix;
+/// # impl Safe<BlsScalar, W> for Mix {
+/// #     fn permute(&mut self, state: &mut [BlsScalar; W]) {
+/// #         let sum: BlsScalar = state.iter().sum();
+/// #         state.iter_mut().for_each(|s| *s = (*s + sum).square());
+/// #     }
+/// #     fn tag(&mut self, input: &[u8]) -> BlsScalar {
+/// #         BlsScalar::hash_to_scalar(input)
+/// #     }
+/// #     fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+/// #         right + left
+/// #     }
+/// # }
+/// # use dusk_safe::{Absorbing, Finished, Squeezing, TypedSponge};
+/// # type Pattern = Absorbing<2, Squeezing<1, Finished>>;
+/// let sponge = TypedSponge::<_, _, W, Pattern>::start(Mix, 42)?;
+/// let sponge = sponge.absorb(&[BlsScalar::from(1), BlsScalar::from(2)])?;
+/// sponge.finish()?;
+/// # Ok::<(), dusk_safe::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct TypedSponge<S, T, const W: usize, P>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    sponge: Sponge<S, T, W>,
+    pattern: PhantomData<P>,
+}
+
+impl<S, T, const W: usize, P> TypedSponge<S, T, W, P>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+    P: TypedPattern,
+{
+    /// Starts the sponge with the IO-pattern `P`, see [`Sponge::start`].
+    ///
+    /// # Parameters
+    ///
+    /// - `safe`: The sponge safe implementation.
+    /// - `domain_sep`: The domain separator to be used.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the initialized sponge on success, or an `Error`
+    /// if the IO-pattern is invalid, e.g. because it doesn't end with a call
+    /// to squeeze.
+    pub fn start(safe: S, domain_sep: u64) -> Result<Self, Error> {
+        let mut iopattern = Vec::new();
+        P::push_calls(&mut iopattern);
+        Ok(Self {
+            sponge: Sponge::start(safe, iopattern, domain_sep)?,
+            pattern: PhantomData,
+        })
+    }
+}
+
+impl<S, T, const W: usize, const N: usize, Rest>
+    TypedSponge<S, T, W, Absorbing<N, Rest>>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    /// Absorbs the `N` elements of the next call, see [`Sponge::absorb`].
+    ///
+    /// # Parameters
+    ///
+    /// - `input`: The elements to absorb.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the sponge with the rest of the pattern on
+    /// success, or an `Error` if the sponge failed.
+    pub fn absorb(
+        mut self,
+        input: &[T; N],
+    ) -> Result<TypedSponge<S, T, W, Rest>, Error> {
+        self.sponge.absorb(N, input)?;
+        Ok(TypedSponge {
+            sponge: self.sponge,
+            pattern: PhantomData,
+        })
+    }
+}
+
+impl<S, T, const W: usize, const N: usize, Rest>
+    TypedSponge<S, T, W, Squeezing<N, Rest>>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    /// Squeezes the `N` elements of the next call, see [`Sponge::squeeze`].
+    ///
+    /// The squeezed elements are still added to the output of the sponge.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the squeezed elements and the sponge with the rest
+    /// of the pattern on success, or an `Error` if the sponge failed.
+    pub fn squeeze(mut self) -> Result<Squeezed<S, T, W, N, Rest>, Error> {
+        self.sponge.squeeze(N)?;
+        let mut elements = [T::default(); N];
+        let start = self.sponge.output.len() - N;
+        elements.copy_from_slice(&self.sponge.output[start..]);
+        Ok((
+            elements,
+            TypedSponge {
+                sponge: self.sponge,
+                pattern: PhantomData,
+            },
+        ))
+    }
+}
+
+impl<S, T, const W: usize> TypedSponge<S, T, W, Finished>
+where
+    S: Safe<T, W>,
+    T: SpongeElement,
+{
+    /// Finishes the sponge at the end of its pattern, see [`Sponge::finish`].
+    ///
+    /// # Returns
+    ///
+    /// A result containing the [`Output`] on success, or an `Error` if the
+    /// sponge failed.
+    pub fn finish(self) -> Result<Output<T>, Error> {
+        self.sponge.finish()
+    }
+}
@@ -0,0 +1,227 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+// The `audit` and `test-utils` features record every call of a sponge on the
+// heap, so these tests only run without them, see the
+// `test_default_features` and `test_alloc_free` jobs of the CI.
+#![cfg(not(any(feature = "audit", feature = "test-utils")))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{Call, Error, Safe, Sponge};
+
+// counts the allocations made by every thread of this test binary, so that
+// tests running in parallel don't count each other's allocations
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const W: usize = 5;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct HashState();
+
+impl Safe<BlsScalar, W> for HashState {
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        for i in 0..W {
+            state[i] =
+                state[i] * state[(i + 1) % W] + BlsScalar::from(i as u64);
+        }
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        input.iter().fold(BlsScalar::one(), |acc, b| {
+            acc * BlsScalar::from(*b as u64 + 3)
+        })
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl dusk_safe::Encryption<BlsScalar, W> for HashState {
+    fn subtract(
+        &mut self,
+        minuend: &BlsScalar,
+        subtrahend: &BlsScalar,
+    ) -> BlsScalar {
+        minuend - subtrahend
+    }
+
+    fn is_equal(&mut self, lhs: &BlsScalar, rhs: &BlsScalar) -> bool {
+        lhs == rhs
+    }
+}
+
+const IOPATTERN: [Call; 4] = [
+    Call::Absorb(6),
+    Call::Squeeze(3),
+    Call::Absorb(2),
+    Call::Squeeze(2),
+];
+
+#[test]
+fn alloc_free_sponge() -> Result<(), Error> {
+    let input = [BlsScalar::from(42); 6];
+    let mut first = [BlsScalar::zero(); 3];
+    let mut second = [BlsScalar::zero(); 2];
+
+    let before = allocations();
+    let mut sponge = Sponge::start_static(HashState(), &IOPATTERN, 0x10)?;
+    sponge.absorb(6, input)?;
+    sponge.squeeze_into(3, &mut first)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze_into(2, &mut second)?;
+    let output = sponge.finish()?;
+    let after = allocations();
+
+    assert_eq!(before, after, "the sponge allocated");
+    assert!(output.is_empty());
+
+    // the output equals the one of a sponge using the heap
+    #[cfg(feature = "alloc")]
+    {
+        let mut sponge = Sponge::start(HashState(), IOPATTERN.to_vec(), 0x10)?;
+        sponge.absorb(6, input)?;
+        sponge.squeeze(3)?;
+        sponge.absorb(2, input)?;
+        sponge.squeeze(2)?;
+        let output = sponge.finish()?;
+        assert_eq!(output[..3], first);
+        assert_eq!(output[3..], second);
+    }
+
+    Ok(())
+}
+
+// a backend with a capacity of two elements, whose second element is tagged
+// with the tag input followed by its index
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct WideCapacity();
+
+impl Safe<BlsScalar, W> for WideCapacity {
+    const CAPACITY: usize = 2;
+
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        HashState().permute(state)
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        HashState().tag(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
+
+#[test]
+fn alloc_free_wide_capacity() -> Result<(), Error> {
+    let input = [BlsScalar::from(42); 6];
+    let mut first = [BlsScalar::zero(); 3];
+    let mut second = [BlsScalar::zero(); 2];
+
+    let before = allocations();
+    let mut sponge = Sponge::start_static(WideCapacity(), &IOPATTERN, 0x10)?;
+    sponge.absorb(6, input)?;
+    sponge.squeeze_into(3, &mut first)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze_into(2, &mut second)?;
+    sponge.finish()?;
+    let after = allocations();
+
+    assert_eq!(before, after, "the sponge allocated");
+
+    // both elements of the capacity are tagged, also for tag inputs that
+    // don't fit on the stack when the heap is available
+    let lens: &[usize] = match cfg!(feature = "alloc") {
+        true => &[16, 300],
+        false => &[16],
+    };
+    for &len in lens {
+        let tag_input = vec![0x10; len];
+        let mut indexed = tag_input.clone();
+        indexed.extend(1u64.to_be_bytes());
+
+        let state = WideCapacity().tagged_state(&tag_input);
+        assert_eq!(state[0], HashState().tag(&tag_input));
+        assert_eq!(state[1], HashState().tag(&indexed));
+        assert_eq!(state[2..], [BlsScalar::zero(); W - 2]);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn finish_moves_output() -> Result<(), Error> {
+    // the output spills onto the heap
+    let iopattern = vec![Call::Absorb(1), Call::Squeeze(16)];
+    let mut sponge = Sponge::start(HashState(), iopattern, 0x10)?;
+    sponge.absorb(1, [BlsScalar::one()])?;
+    sponge.squeeze(16)?;
+
+    // finishing the sponge moves the output out instead of copying it
+    let before = allocations();
+    let output = sponge.finish()?;
+    assert_eq!(allocations(), before, "the output was copied");
+    assert_eq!(output.len(), 16);
+
+    Ok(())
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn alloc_free_encryption() -> Result<(), Error> {
+    use dusk_safe::{decrypt_sized, encrypt, encrypt_sized};
+
+    // long enough for the key-stream to exceed the inline output
+    let message: [BlsScalar; 9] = core::array::from_fn(|i| (i as u64).into());
+    let shared_secret = [BlsScalar::from(1), BlsScalar::from(2)];
+    let nonce = BlsScalar::from(3);
+
+    let before = allocations();
+    let cipher =
+        encrypt_sized(HashState(), 0x10u64, &message, &shared_secret, &nonce)?;
+    let decrypted =
+        decrypt_sized(HashState(), 0x10u64, &cipher, &shared_secret, &nonce)?;
+    let after = allocations();
+
+    assert_eq!(before, after, "the encryption allocated");
+    assert_eq!(*decrypted, message);
+
+    // the cipher-text equals the one of the encryption using the heap
+    let expected =
+        encrypt(HashState(), 0x10u64, message, &shared_secret, &nonce)?;
+    assert_eq!(cipher.payload[..], expected[..9]);
+    assert_eq!(cipher.tag, expected[9]);
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "merkle")]
+
+mod common;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{Error, VectorCommitment};
+
+const W: usize = 3;
+
+type HashState = common::HashState<W>;
+
+#[test]
+fn open_and_verify() -> Result<(), Error> {
+    let elements: Vec<BlsScalar> = (0..11).map(BlsScalar::from).collect();
+    let vc = VectorCommitment::new(HashState::new(), &elements)?;
+    let commitment = vc.commitment();
+
+    for (position, element) in elements.iter().enumerate() {
+        let opening = vc.open(position as u64)?;
+        assert!(opening.verify(&HashState::new(), &commitment, element)?);
+        assert!(!opening.verify(
+            &HashState::new(),
+            &commitment,
+            &BlsScalar::from(1234)
+        )?);
+    }
+
+    assert_eq!(vc.open(11).unwrap_err(), Error::InvalidPosition);
+
+    Ok(())
+}
+
+#[test]
+fn commitment_binds_position_and_length() -> Result<(), Error> {
+    let elements = [BlsScalar::from(1), BlsScalar::from(2)];
+    let swapped = [BlsScalar::from(2), BlsScalar::from(1)];
+    let padded = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::zero()];
+
+    let commitment = VectorCommitment::new(HashState::new(), elements)?;
+    let swapped = VectorCommitment::new(HashState::new(), swapped)?;
+    let padded = VectorCommitment::new(HashState::new(), padded)?;
+
+    assert_ne!(commitment.commitment(), swapped.commitment());
+    assert_ne!(commitment.commitment(), padded.commitment());
+
+    // an opening of one vector doesn't verify against another
+    let opening = commitment.open(0)?;
+    assert!(!opening.verify(
+        &HashState::new(),
+        &padded.commitment(),
+        &elements[0]
+    )?);
+
+    Ok(())
+}
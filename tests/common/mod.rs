@@ -0,0 +1,44 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::Safe;
+
+/// A sponge of width `W` whose permutation hashes the state, shared by the
+/// tests of the constructions built on top of the sponge.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct HashState<const W: usize>();
+
+impl<const W: usize> HashState<W> {
+    pub fn new() -> Self {
+        Self()
+    }
+}
+
+impl<const W: usize> Safe<BlsScalar, W> for HashState<W> {
+    // the permuted state is the previous state hashed with the index of each
+    // element
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        let mut state_bytes: Vec<u8> =
+            state.iter().flat_map(|s| s.to_bytes()).collect();
+
+        state.iter_mut().enumerate().for_each(|(i, s)| {
+            state_bytes.push(i as u8);
+            *s = BlsScalar::hash_to_scalar(&state_bytes[..]);
+            state_bytes.pop();
+        });
+    }
+
+    // hash the tag input so that different domains and io-patterns result in
+    // different sponge outputs
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        BlsScalar::hash_to_scalar(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
@@ -0,0 +1,117 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "derive")]
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::Absorbable;
+
+#[derive(Absorbable)]
+#[absorbable(element = BlsScalar)]
+struct Note {
+    value: BlsScalar,
+    owner: [BlsScalar; 2],
+}
+
+#[derive(Absorbable)]
+#[absorbable(element = BlsScalar)]
+struct Coin(BlsScalar, [BlsScalar; 2]);
+
+#[derive(Absorbable)]
+#[absorbable(element = BlsScalar)]
+struct Transfer {
+    input: Note,
+    output: Note,
+}
+
+#[test]
+fn fields_in_declaration_order() {
+    let note = Note {
+        value: BlsScalar::from(42),
+        owner: [BlsScalar::from(1), BlsScalar::from(2)],
+    };
+
+    assert_eq!(note.elements_len(), 4);
+    let elements = note.to_elements();
+    assert_eq!(elements.len(), 4);
+    assert_eq!(&elements[1..], &[note.value, note.owner[0], note.owner[1]]);
+}
+
+#[test]
+fn type_tag() {
+    let note = Note {
+        value: BlsScalar::from(42),
+        owner: [BlsScalar::from(1), BlsScalar::from(2)],
+    };
+    let coin = Coin(note.value, note.owner);
+
+    // the same fields absorbed for different types differ in the tag only
+    let note_elements = note.to_elements();
+    let coin_elements = coin.to_elements();
+    assert_ne!(note_elements[0], coin_elements[0]);
+    assert_eq!(note_elements[1..], coin_elements[1..]);
+}
+
+mod other {
+    use super::*;
+
+    // a type sharing the name of another type in a different module
+    #[derive(Absorbable)]
+    #[absorbable(element = BlsScalar)]
+    pub struct Note {
+        pub value: BlsScalar,
+        pub owner: [BlsScalar; 2],
+    }
+
+    // a type with a stable tag given by the user
+    #[derive(Absorbable)]
+    #[absorbable(element = BlsScalar, tag = "dusk.note.v1")]
+    pub struct TaggedNote {
+        pub value: BlsScalar,
+    }
+}
+
+#[test]
+fn type_tag_path() {
+    let note = Note {
+        value: BlsScalar::from(42),
+        owner: [BlsScalar::from(1), BlsScalar::from(2)],
+    };
+    let other = other::Note {
+        value: note.value,
+        owner: note.owner,
+    };
+
+    // the tag depends on the full path and not only on the name of the type
+    assert_ne!(note.to_elements()[0], other.to_elements()[0]);
+
+    // the tag given by the user is the FNV-1a hash of its string
+    let tagged = other::TaggedNote { value: note.value };
+    let fnv1a = b"dusk.note.v1"
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+            (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    assert_eq!(tagged.to_elements()[0], BlsScalar::from(fnv1a));
+}
+
+#[test]
+fn nested() {
+    let note = |value: u64| Note {
+        value: BlsScalar::from(value),
+        owner: [BlsScalar::from(1), BlsScalar::from(2)],
+    };
+    let transfer = Transfer {
+        input: note(42),
+        output: note(41),
+    };
+
+    assert_eq!(transfer.elements_len(), 9);
+    let elements = transfer.to_elements();
+    assert_eq!(elements.len(), 9);
+    assert_eq!(elements[1..5], transfer.input.to_elements()[..]);
+    assert_eq!(elements[5..], transfer.output.to_elements()[..]);
+}
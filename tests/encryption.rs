@@ -5,10 +5,29 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 #![cfg(feature = "encryption")]
+#![allow(clippy::op_ref)]
+// `needless_borrow` was split into `needless_borrows_for_generic_args` after
+// the pinned toolchain
+#![allow(unknown_lints)]
+#![allow(clippy::needless_borrow, clippy::needless_borrows_for_generic_args)]
 
 use dusk_bls12_381::BlsScalar;
-use dusk_jubjub::{JubJubExtended, JubJubScalar, GENERATOR_EXTENDED};
-use dusk_safe::{decrypt, encrypt, Encryption, Error, Safe};
+use dusk_jubjub::{
+    JubJubAffine, JubJubExtended, JubJubScalar, GENERATOR_EXTENDED,
+};
+use dusk_safe::{
+    combine_keystream, combine_tag_key, decrypt_shared, embed_shared_secret,
+    encrypt_shared, keystream_share, tag_key_share, Embed,
+};
+use dusk_safe::{
+    decrypt, decrypt_at, decrypt_into, decrypt_sized, decrypt_with_ad,
+    decrypt_with_wide_nonce, encrypt, encrypt_at, encrypt_into, encrypt_sized,
+    encrypt_with_ad, encrypt_with_wide_nonce, encryption_io_pattern,
+    encryption_io_pattern_with_ad, encryption_public_inputs, mac,
+    position_encryption_io_pattern, self_test, self_test_encryption,
+    squeeze_len, tag_input, unwrap_key, verify_cipher, verify_mac, wrap_key,
+    Call, CipherText, Encryption, Error, Safe,
+};
 use ff::Field;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
@@ -103,77 +122,1023 @@ fn encrypt_decrypt() -> Result<(), Error> {
         &nonce,
     )?;
 
+    assert_eq!(*decrypted_message, message);
+
+    Ok(())
+}
+
+#[test]
+fn encrypt_decrypt_at() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 42usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+
+    let cipher = encrypt_at(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &shared_secret,
+        &nonce,
+        100,
+        3,
+    )?;
+
+    let decrypted_message = decrypt_at(
+        HashState::new(),
+        DOMAIN,
+        &cipher,
+        &shared_secret,
+        &nonce,
+        100,
+        3,
+    )?;
+    assert_eq!(*decrypted_message, message);
+
+    // the cipher-text is bound to its position
+    assert_ne!(
+        cipher,
+        encrypt(HashState::new(), DOMAIN, &message, &shared_secret, &nonce)?
+    );
+    assert_eq!(
+        decrypt_at(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &shared_secret,
+            &nonce,
+            100,
+            4,
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+
+    Ok(())
+}
+
+#[test]
+fn encrypt_into_buffer() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 42usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+
+    let mut cipher = vec![BlsScalar::zero(); message_len + 1];
+    encrypt_into(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &mut cipher,
+        &shared_secret,
+        &nonce,
+    )?;
+    assert_eq!(
+        cipher,
+        encrypt(HashState::new(), DOMAIN, &message, &shared_secret, &nonce)?
+    );
+
+    // the buffer needs to fit the cipher-text exactly
+    let mut buffer = vec![BlsScalar::zero(); message_len];
+    assert_eq!(
+        encrypt_into(
+            HashState::new(),
+            DOMAIN,
+            &message,
+            &mut buffer,
+            &shared_secret,
+            &nonce,
+        ),
+        Err(Error::OutputLenMismatch)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn decrypt_into_buffer() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 42usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+
+    let cipher =
+        encrypt(HashState::new(), DOMAIN, &message, &shared_secret, &nonce)?;
+
+    let mut decrypted_message = vec![BlsScalar::zero(); message_len];
+    decrypt_into(
+        HashState::new(),
+        DOMAIN,
+        &cipher,
+        &mut decrypted_message,
+        &shared_secret,
+        &nonce,
+    )?;
     assert_eq!(decrypted_message, message);
 
+    // the buffer needs to fit the message exactly
+    let mut buffer = vec![BlsScalar::zero(); message_len + 1];
+    assert_eq!(
+        decrypt_into(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &mut buffer,
+            &shared_secret,
+            &nonce,
+        ),
+        Err(Error::OutputLenMismatch)
+    );
+
+    // the buffer is erased when the decryption fails
+    let wrong_nonce = nonce + BlsScalar::one();
+    assert_eq!(
+        decrypt_into(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &mut decrypted_message,
+            &shared_secret,
+            &wrong_nonce,
+        ),
+        Err(Error::DecryptionFailed)
+    );
+    assert!(decrypted_message.iter().all(|e| *e == BlsScalar::zero()));
+
+    Ok(())
+}
+
+#[test]
+fn verify_cipher_without_decrypt() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 42usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+
+    let mut cipher =
+        encrypt(HashState::new(), DOMAIN, &message, &shared_secret, &nonce)?;
+    verify_cipher(HashState::new(), DOMAIN, &cipher, &shared_secret, &nonce)?;
+
+    // a wrong nonce or a modified cipher-text fail the verification
+    let wrong_nonce = nonce + BlsScalar::one();
+    assert_eq!(
+        verify_cipher(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &shared_secret,
+            &wrong_nonce
+        ),
+        Err(Error::DecryptionFailed)
+    );
+    cipher[1] += BlsScalar::one();
+    assert_eq!(
+        verify_cipher(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &shared_secret,
+            &nonce
+        ),
+        Err(Error::DecryptionFailed)
+    );
+    assert_eq!(
+        verify_cipher(HashState::new(), DOMAIN, [], &shared_secret, &nonce),
+        Err(Error::DecryptionFailed)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn encrypt_decrypt_sized() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+
+    let (message, shared_secret, nonce) = encryption_variables(&mut rng, 4);
+    let shared_secret = shared_secret.to_hash_inputs();
+    let message: [BlsScalar; 4] = message.try_into().unwrap();
+
+    let cipher = encrypt_sized(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &shared_secret,
+        &nonce,
+    )?;
+
+    // the sized cipher-text is the same as the unsized one
+    let unsized_cipher =
+        encrypt(HashState::new(), DOMAIN, message, &shared_secret, &nonce)?;
+    assert_eq!(cipher.to_vec(), unsized_cipher);
+    assert_eq!(CipherText::from_slice(&unsized_cipher), Some(cipher));
+    assert_eq!(
+        CipherText::<BlsScalar, 3>::from_slice(&unsized_cipher),
+        None
+    );
+
+    let decrypted_message = decrypt_sized(
+        HashState::new(),
+        DOMAIN,
+        &cipher,
+        &shared_secret,
+        &nonce,
+    )?;
+    assert_eq!(*decrypted_message, message);
+
+    Ok(())
+}
+
+#[test]
+fn encrypt_decrypt_with_ad() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 42usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+    let ad = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::from(3)];
+    let segments: [&[BlsScalar]; 2] = [&ad[..2], &ad[2..]];
+
+    let cipher = encrypt_with_ad(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &segments,
+        &shared_secret,
+        &nonce,
+    )?;
+    let decrypted_message = decrypt_with_ad(
+        HashState::new(),
+        DOMAIN,
+        &cipher,
+        &segments,
+        &shared_secret,
+        &nonce,
+    )?;
+    assert_eq!(*decrypted_message, message);
+
+    // the cipher-text is bound to how the associated data is segmented
+    let other_segments: [&[BlsScalar]; 2] = [&ad[..1], &ad[1..]];
+    assert_eq!(
+        decrypt_with_ad(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &other_segments,
+            &shared_secret,
+            &nonce,
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+    let empty_segment: [&[BlsScalar]; 3] = [&ad[..2], &ad[2..], &[]];
+    assert_eq!(
+        decrypt_with_ad(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &empty_segment,
+            &shared_secret,
+            &nonce,
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+
+    // without associated data, the cipher-text is the one of `encrypt`
+    assert_eq!(
+        encrypt_with_ad(
+            HashState::new(),
+            DOMAIN,
+            &message,
+            &[],
+            &shared_secret,
+            &nonce,
+        )?,
+        encrypt(HashState::new(), DOMAIN, &message, &shared_secret, &nonce)?
+    );
+    assert_eq!(
+        encryption_io_pattern_with_ad(message_len, &[]),
+        encryption_io_pattern(message_len)
+    );
+    assert_eq!(
+        encryption_io_pattern_with_ad(message_len, &[2, 0]),
+        [
+            Call::Absorb(2),
+            Call::Absorb(1),
+            Call::Label(0),
+            Call::Absorb(2),
+            Call::Label(1),
+            Call::Squeeze(message_len),
+            Call::Absorb(message_len),
+            Call::Squeeze(1),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn mac_verify() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let ad_len = 5usize;
+
+    let (associated_data, shared_secret, nonce) =
+        encryption_variables(&mut rng, ad_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+    let segments: [&[BlsScalar]; 2] =
+        [&associated_data[..2], &associated_data[2..]];
+
+    let tag = mac(HashState::new(), DOMAIN, &segments, &shared_secret, &nonce)?;
+    verify_mac(
+        HashState::new(),
+        DOMAIN,
+        &segments,
+        &shared_secret,
+        &nonce,
+        &tag,
+    )?;
+
+    // the tag is the cipher-text of an empty message with the associated data
+    let cipher = encrypt_with_ad(
+        HashState::new(),
+        DOMAIN,
+        [],
+        &segments,
+        &shared_secret,
+        &nonce,
+    )?;
+    assert_eq!(cipher, [tag]);
+    assert!(decrypt_with_ad(
+        HashState::new(),
+        DOMAIN,
+        &cipher,
+        &segments,
+        &shared_secret,
+        &nonce,
+    )?
+    .is_empty());
+
+    // the key-stream and the message are omitted from the IO-pattern
+    assert_eq!(
+        encryption_io_pattern_with_ad(0, &[2, 3]),
+        [
+            Call::Absorb(2),
+            Call::Absorb(1),
+            Call::Label(0),
+            Call::Absorb(2),
+            Call::Label(1),
+            Call::Absorb(3),
+            Call::Squeeze(1),
+        ]
+    );
+
+    // a different nonce or segmentation of the associated data fails
+    // verification
+    let wrong_nonce = nonce + BlsScalar::one();
+    assert_eq!(
+        verify_mac(
+            HashState::new(),
+            DOMAIN,
+            &segments,
+            &shared_secret,
+            &wrong_nonce,
+            &tag,
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+    assert_eq!(
+        verify_mac(
+            HashState::new(),
+            DOMAIN,
+            &[&associated_data],
+            &shared_secret,
+            &nonce,
+            &tag,
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+
+    // a tag differs from the one of a cipher-text under the same key and nonce
+    let cipher = encrypt_with_ad(
+        HashState::new(),
+        DOMAIN,
+        &associated_data[..1],
+        &segments,
+        &shared_secret,
+        &nonce,
+    )?;
+    assert_ne!(cipher[1], tag);
+
+    // associated data that is empty is rejected, as is an empty message
+    // without associated data
+    let empty: &[BlsScalar] = &[];
+    assert!(mac(HashState::new(), DOMAIN, &[], &shared_secret, &nonce).is_err());
+    assert!(
+        mac(HashState::new(), DOMAIN, &[empty], &shared_secret, &nonce)
+            .is_err()
+    );
+    assert!(
+        encrypt(HashState::new(), DOMAIN, empty, &shared_secret, &nonce)
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn incorrect_shared_secret_fails() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 21usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+
+    let cipher = encrypt(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &shared_secret.to_hash_inputs(),
+        &nonce,
+    )?;
+
+    let wrong_shared_secret =
+        GENERATOR_EXTENDED * &JubJubScalar::random(&mut rng);
+    assert_ne!(shared_secret, wrong_shared_secret);
+
+    assert_eq!(
+        decrypt(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &wrong_shared_secret.to_hash_inputs(),
+            &nonce,
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+
+    Ok(())
+}
+
+#[test]
+fn incorrect_nonce_fails() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 21usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+
+    let cipher = encrypt(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &shared_secret.to_hash_inputs(),
+        &nonce,
+    )?;
+
+    let wrong_nonce = BlsScalar::random(&mut rng);
+    assert_ne!(nonce, wrong_nonce);
+
+    assert_eq!(
+        decrypt(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &shared_secret.to_hash_inputs(),
+            &wrong_nonce,
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+
+    Ok(())
+}
+
+/// Verifies an encryption like a circuit would, following the IO-pattern on
+/// the state directly, with the public inputs in the order of
+/// `encryption_public_inputs` and the shared secret and the message as the
+/// private inputs.
+fn verify_in_circuit<S>(
+    mut safe: S,
+    iopattern: &[Call],
+    public_inputs: &[BlsScalar],
+    shared_secret: &[BlsScalar; 2],
+    message: &[BlsScalar],
+) -> bool
+where
+    S: Safe<BlsScalar, W>,
+{
+    let capacity = S::CAPACITY;
+    let rate = W - capacity;
+
+    let (cipher, rest) = public_inputs.split_at(message.len() + 1);
+    let (absorbed, tagged) = rest.split_at(rest.len() - capacity);
+
+    let mut state = [BlsScalar::zero(); W];
+    state[..capacity].copy_from_slice(tagged);
+    let (mut pos_absorb, mut pos_squeeze) = (0, 0);
+    let mut inputs = shared_secret.iter().chain(absorbed).chain(message);
+    let mut outputs = Vec::new();
+    for call in iopattern {
+        match call {
+            Call::Absorb(len) => {
+                for element in inputs.by_ref().take(*len) {
+                    if pos_absorb == rate {
+                        safe.permute(&mut state);
+                        pos_absorb = 0;
+                    }
+                    let pos = capacity + pos_absorb;
+                    state[pos] = safe.add(&state[pos], element);
+                    pos_absorb += 1;
+                }
+                pos_squeeze = rate;
+            }
+            Call::Squeeze(len) => {
+                for _ in 0..*len {
+                    if pos_squeeze == rate {
+                        safe.permute(&mut state);
+                        pos_absorb = 0;
+                        pos_squeeze = 0;
+                    }
+                    outputs.push(state[capacity + pos_squeeze]);
+                    pos_squeeze += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // the cipher-text is the key-stream with the message added to it,
+    // followed by the tag
+    let (tag, keystream) = outputs.split_last().expect("squeezed the tag");
+    let payload = keystream.iter().zip(message).map(|(k, m)| k + m);
+    payload.eq(cipher[..message.len()].iter().copied())
+        && tag == &cipher[message.len()]
+}
+
+#[test]
+fn public_inputs() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 5usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+
+    let cipher =
+        encrypt(HashState::new(), DOMAIN, &message, &shared_secret, &nonce)?;
+    let iopattern = encryption_io_pattern(message_len);
+
+    let inputs = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        iopattern,
+        &cipher,
+        &[&[nonce]],
+    )?;
+    assert_eq!(inputs.len(), cipher.len() + 2);
+    assert_eq!(&inputs[..cipher.len()], &cipher[..]);
+    assert_eq!(inputs[cipher.len()], nonce);
+    assert!(verify_in_circuit(
+        HashState::new(),
+        &iopattern,
+        &inputs,
+        &shared_secret,
+        &message,
+    ));
+
+    // the sized cipher-text has the same public inputs
+    let sized = encrypt_sized(
+        HashState::new(),
+        DOMAIN,
+        &[message[0], message[1], message[2], message[3], message[4]],
+        &shared_secret,
+        &nonce,
+    )?;
+    assert_eq!(
+        encryption_public_inputs(
+            HashState::new(),
+            DOMAIN,
+            iopattern,
+            sized.to_vec(),
+            &[&[nonce]],
+        )?,
+        inputs
+    );
+
+    // a wrong message or domain-separator fails the verification
+    let mut wrong_message = message.clone();
+    wrong_message[0] += BlsScalar::one();
+    assert!(!verify_in_circuit(
+        HashState::new(),
+        &iopattern,
+        &inputs,
+        &shared_secret,
+        &wrong_message,
+    ));
+    let other_domain = encryption_public_inputs(
+        HashState::new(),
+        1u64,
+        iopattern,
+        &cipher,
+        &[&[nonce]],
+    )?;
+    assert_ne!(inputs.last(), other_domain.last());
+    assert!(!verify_in_circuit(
+        HashState::new(),
+        &iopattern,
+        &other_domain,
+        &shared_secret,
+        &message,
+    ));
+
+    // the cipher-text and the absorbed elements need to match the IO-pattern
+    let too_short = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        iopattern,
+        &cipher[1..],
+        &[&[nonce]],
+    );
+    assert_eq!(too_short.unwrap_err(), Error::InvalidInput);
+    let wide_nonce = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        iopattern,
+        &cipher,
+        &[&[nonce, nonce]],
+    );
+    assert_eq!(wide_nonce.unwrap_err(), Error::InvalidInput);
+    let no_nonce = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        iopattern,
+        &cipher,
+        &[],
+    );
+    assert_eq!(no_nonce.unwrap_err(), Error::InvalidInput);
+    assert_eq!(
+        encryption_public_inputs::<_, BlsScalar, W>(
+            HashState::new(),
+            DOMAIN,
+            iopattern,
+            [],
+            &[&[nonce]],
+        )
+        .unwrap_err(),
+        Error::InvalidInput
+    );
+
+    Ok(())
+}
+
+#[test]
+fn public_inputs_variants() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 5usize;
+
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
+
+    // at a position
+    let (block_height, output_index) = (42, 7);
+    let cipher = encrypt_at(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &shared_secret,
+        &nonce,
+        block_height,
+        output_index,
+    )?;
+    let iopattern = position_encryption_io_pattern(message_len);
+    let position =
+        [BlsScalar::from(block_height), BlsScalar::from(output_index)];
+    let inputs = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        iopattern,
+        &cipher,
+        &[&[nonce], &position],
+    )?;
+    assert!(verify_in_circuit(
+        HashState::new(),
+        &iopattern,
+        &inputs,
+        &shared_secret,
+        &message,
+    ));
+
+    // with associated data, of which the empty segments are only labeled
+    let sender = [BlsScalar::from(1)];
+    let memo = [BlsScalar::from(2), BlsScalar::from(3)];
+    let segments: [&[BlsScalar]; 3] = [&sender, &[], &memo];
+    let cipher = encrypt_with_ad(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &segments,
+        &shared_secret,
+        &nonce,
+    )?;
+    let iopattern = encryption_io_pattern_with_ad(message_len, &[1, 0, 2]);
+    let inputs = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        &iopattern,
+        &cipher,
+        &[&[nonce], &sender, &memo],
+    )?;
+    assert!(verify_in_circuit(
+        HashState::new(),
+        &iopattern,
+        &inputs,
+        &shared_secret,
+        &message,
+    ));
+
+    // authenticating the associated data only
+    let tag = mac(HashState::new(), DOMAIN, &segments, &shared_secret, &nonce)?;
+    let iopattern = encryption_io_pattern_with_ad(0, &[1, 0, 2]);
+    let inputs = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        &iopattern,
+        [tag],
+        &[&[nonce], &sender, &memo],
+    )?;
+    assert!(verify_in_circuit(
+        HashState::new(),
+        &iopattern,
+        &inputs,
+        &shared_secret,
+        &[],
+    ));
+
+    // with a wide nonce
+    let wide_nonce = [nonce, BlsScalar::from(4)];
+    let cipher = encrypt_with_wide_nonce(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &shared_secret,
+        &wide_nonce,
+    )?;
+    let mut iopattern = encryption_io_pattern(message_len);
+    iopattern[1] = Call::Absorb(wide_nonce.len());
+    let inputs = encryption_public_inputs(
+        HashState::new(),
+        DOMAIN,
+        iopattern,
+        &cipher,
+        &[&wide_nonce],
+    )?;
+    assert!(verify_in_circuit(
+        HashState::new(),
+        &iopattern,
+        &inputs,
+        &shared_secret,
+        &message,
+    ));
+
     Ok(())
 }
 
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct WideCapacity(HashState);
+
+impl Safe<BlsScalar, W> for WideCapacity {
+    const CAPACITY: usize = 2;
+
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        self.0.permute(state)
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        self.0.tag(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        self.0.add(right, left)
+    }
+}
+
+impl Encryption<BlsScalar, W> for WideCapacity {
+    fn subtract(
+        &mut self,
+        minuend: &BlsScalar,
+        subtrahend: &BlsScalar,
+    ) -> BlsScalar {
+        self.0.subtract(minuend, subtrahend)
+    }
+
+    fn is_equal(&mut self, lhs: &BlsScalar, rhs: &BlsScalar) -> bool {
+        self.0.is_equal(lhs, rhs)
+    }
+}
+
 #[test]
-fn incorrect_shared_secret_fails() -> Result<(), Error> {
+fn public_inputs_wide_capacity() -> Result<(), Error> {
     let mut rng = StdRng::seed_from_u64(0x42424242);
-    let message_len = 21usize;
+    let message_len = 5usize;
 
     let (message, shared_secret, nonce) =
         encryption_variables(&mut rng, message_len);
 
     let cipher = encrypt(
-        HashState::new(),
+        WideCapacity::default(),
         DOMAIN,
         &message,
         &shared_secret.to_hash_inputs(),
         &nonce,
     )?;
 
-    let wrong_shared_secret =
-        GENERATOR_EXTENDED * &JubJubScalar::random(&mut rng);
-    assert_ne!(shared_secret, wrong_shared_secret);
+    // the public inputs end with every element of the capacity of the
+    // initial state
+    let iopattern = encryption_io_pattern(message_len);
+    let inputs = encryption_public_inputs(
+        WideCapacity::default(),
+        DOMAIN,
+        iopattern,
+        &cipher,
+        &[&[nonce]],
+    )?;
+    let state =
+        WideCapacity::default().tagged_state(&tag_input(iopattern, DOMAIN)?);
+    assert_eq!(inputs.len(), cipher.len() + 3);
+    assert_eq!(inputs[cipher.len() + 1..], state[..2]);
+    assert!(verify_in_circuit(
+        WideCapacity::default(),
+        &iopattern,
+        &inputs,
+        &shared_secret.to_hash_inputs(),
+        &message,
+    ));
+
+    Ok(())
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct StuckState();
+
+impl Safe<BlsScalar, W> for StuckState {
+    // a faulty permutation that is stuck at zero
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        *state = [BlsScalar::zero(); W];
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        BlsScalar::hash_to_scalar(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
 
+impl Encryption<BlsScalar, W> for StuckState {
+    fn subtract(
+        &mut self,
+        minuend: &BlsScalar,
+        subtrahend: &BlsScalar,
+    ) -> BlsScalar {
+        minuend - subtrahend
+    }
+
+    fn is_equal(&mut self, lhs: &BlsScalar, rhs: &BlsScalar) -> bool {
+        lhs == rhs
+    }
+}
+
+#[test]
+fn backend_self_test() {
+    // the known answer of the backend, computed once from the reference
+    // implementation
+    let expected = [
+        BlsScalar::from_raw([
+            0xe8b3722b6b62dac9,
+            0x49965c330160fd16,
+            0x1049ed46b076dca7,
+            0x5f0f100a16cca689,
+        ]),
+        BlsScalar::from_raw([
+            0x28c1653b16e0fca0,
+            0xa84bb67ccacd3355,
+            0x3b4a942649ebc6b8,
+            0x4e5c017114c0ba4c,
+        ]),
+        BlsScalar::from_raw([
+            0xe51a495d7737c696,
+            0xe0779c45cb167e3f,
+            0x155826ec607e870b,
+            0x61c62129fa03beca,
+        ]),
+    ];
+    assert_eq!(self_test(HashState::new(), &expected), Ok(()));
+    assert_eq!(self_test_encryption(HashState::new(), &expected), Ok(()));
+
+    // a backend computing another permutation doesn't match the known answer
     assert_eq!(
-        decrypt(
-            HashState::new(),
-            DOMAIN,
-            &cipher,
-            &wrong_shared_secret.to_hash_inputs(),
-            &nonce,
-        )
-        .unwrap_err(),
-        Error::DecryptionFailed
+        self_test_encryption(StuckState(), &expected),
+        Err(Error::SelfTestFailed)
+    );
+    let mut wrong = expected;
+    wrong[2] += BlsScalar::one();
+    assert_eq!(
+        self_test(HashState::new(), &wrong),
+        Err(Error::SelfTestFailed)
     );
-
-    Ok(())
 }
 
 #[test]
-fn incorrect_nonce_fails() -> Result<(), Error> {
+fn encrypt_decrypt_with_wide_nonce() -> Result<(), Error> {
     let mut rng = StdRng::seed_from_u64(0x42424242);
-    let message_len = 21usize;
+    let message_len = 7usize;
 
     let (message, shared_secret, nonce) =
         encryption_variables(&mut rng, message_len);
+    let shared_secret = shared_secret.to_hash_inputs();
 
-    let cipher = encrypt(
+    // a nonce of a single element is the same as the one of `encrypt`
+    let cipher =
+        encrypt(HashState::new(), DOMAIN, &message, &shared_secret, &nonce)?;
+    assert_eq!(
+        encrypt_with_wide_nonce(
+            HashState::new(),
+            DOMAIN,
+            &message,
+            &shared_secret,
+            &[nonce],
+        )?,
+        cipher
+    );
+
+    let wide_nonce = [nonce, BlsScalar::random(&mut rng)];
+    let cipher = encrypt_with_wide_nonce(
         HashState::new(),
         DOMAIN,
         &message,
-        &shared_secret.to_hash_inputs(),
-        &nonce,
+        &shared_secret,
+        &wide_nonce,
     )?;
+    let decrypted = decrypt_with_wide_nonce(
+        HashState::new(),
+        DOMAIN,
+        &cipher,
+        &shared_secret,
+        &wide_nonce,
+    )?;
+    assert_eq!(decrypted[..], message[..]);
 
-    let wrong_nonce = BlsScalar::random(&mut rng);
-    assert_ne!(nonce, wrong_nonce);
-
+    // every element of the nonce is bound to the cipher-text
     assert_eq!(
-        decrypt(
+        decrypt_with_wide_nonce(
             HashState::new(),
             DOMAIN,
             &cipher,
-            &shared_secret.to_hash_inputs(),
-            &wrong_nonce,
+            &shared_secret,
+            &[nonce],
+        )
+        .unwrap_err(),
+        Error::DecryptionFailed
+    );
+    assert_eq!(
+        decrypt_with_wide_nonce(
+            HashState::new(),
+            DOMAIN,
+            &cipher,
+            &shared_secret,
+            &[nonce, BlsScalar::one()],
         )
         .unwrap_err(),
         Error::DecryptionFailed
     );
 
+    // the nonce needs at least one element
+    assert_eq!(
+        encrypt_with_wide_nonce(
+            HashState::new(),
+            DOMAIN,
+            &message,
+            &shared_secret,
+            &[],
+        )
+        .unwrap_err(),
+        Error::InvalidIOPattern
+    );
+
     Ok(())
 }
 
@@ -238,7 +1203,7 @@ fn incorrect_cipher_fails() -> Result<(), Error> {
         Error::DecryptionFailed
     );
 
-    let mut wrong_cipher = cipher.clone();
+    let mut wrong_cipher = cipher;
     wrong_cipher[0] += BlsScalar::from(42);
     assert_eq!(
         decrypt(
@@ -254,3 +1219,384 @@ fn incorrect_cipher_fails() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn encryption_io_pattern_matches() -> Result<(), Error> {
+    let message_len = 5usize;
+
+    // the published io-pattern yields the cipher length
+    let iopattern = encryption_io_pattern(message_len);
+    assert_eq!(iopattern[2], Call::Squeeze(message_len));
+    assert_eq!(iopattern[4], Call::Squeeze(1));
+    assert_eq!(squeeze_len(&iopattern[3..]), 1);
+
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let (message, shared_secret, nonce) =
+        encryption_variables(&mut rng, message_len);
+    let cipher = encrypt(
+        HashState::new(),
+        DOMAIN,
+        &message,
+        &shared_secret.to_hash_inputs(),
+        &nonce,
+    )?;
+    assert_eq!(cipher.len(), message_len + squeeze_len(&iopattern[3..]));
+
+    Ok(())
+}
+
+#[test]
+fn shared_keystream() -> Result<(), Error> {
+    let message = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::from(3)];
+    let secret_shares = [[BlsScalar::from(10)], [BlsScalar::from(20)]];
+    let nonce = BlsScalar::from(42);
+
+    let shares = |secret_shares: &[[BlsScalar; 1]]| -> Result<_, Error> {
+        let keystream_shares = secret_shares
+            .iter()
+            .map(|secret_share| {
+                keystream_share(HashState::new(), 1, secret_share, &nonce, 3)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let tag_key_shares = secret_shares
+            .iter()
+            .map(|secret_share| {
+                tag_key_share(HashState::new(), 1, secret_share, &nonce)
+                    .map(|share| *share)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok((
+            combine_keystream(HashState::new(), keystream_shares)?,
+            combine_tag_key(HashState::new(), tag_key_shares)?,
+        ))
+    };
+    let (keystream, tag_key) = shares(&secret_shares)?;
+    let cipher = encrypt_shared(
+        HashState::new(),
+        1,
+        message,
+        &keystream,
+        &tag_key,
+        &nonce,
+    )?;
+    assert_eq!(cipher.len(), message.len() + 1);
+
+    // the parties decrypt by combining their shares
+    let (combined, combined_tag_key) = shares(&secret_shares)?;
+    let decrypted = decrypt_shared(
+        HashState::new(),
+        1,
+        &cipher,
+        &combined,
+        &combined_tag_key,
+        &nonce,
+    )?;
+    assert_eq!(decrypted[..], message[..]);
+
+    // a wrong share or a tampered cipher-text is detected
+    let wrong = [[BlsScalar::from(10)], [BlsScalar::from(21)]];
+    let (combined, combined_tag_key) = shares(&wrong)?;
+    assert_eq!(
+        decrypt_shared(
+            HashState::new(),
+            1,
+            &cipher,
+            &combined,
+            &combined_tag_key,
+            &nonce
+        ),
+        Err(Error::DecryptionFailed)
+    );
+    let mut tampered = cipher.clone();
+    tampered[0] += BlsScalar::one();
+    assert_eq!(
+        decrypt_shared(
+            HashState::new(),
+            1,
+            &tampered,
+            &keystream,
+            &tag_key,
+            &nonce
+        ),
+        Err(Error::DecryptionFailed)
+    );
+
+    // the tag is bound to the nonce
+    let other = BlsScalar::from(43);
+    assert_eq!(
+        decrypt_shared(
+            HashState::new(),
+            1,
+            &cipher,
+            &keystream,
+            &tag_key,
+            &other
+        ),
+        Err(Error::DecryptionFailed)
+    );
+
+    // the keystream, which follows from a known message and its cipher-text,
+    // doesn't key the tag, so it can't be used for forging a cipher-text
+    let known: Vec<BlsScalar> = cipher[..3]
+        .iter()
+        .zip(message)
+        .map(|(c, m)| c - m)
+        .collect();
+    assert_eq!(known[..], keystream[..]);
+    let forged_message = [BlsScalar::from(7); 3];
+    let forged = encrypt_shared(
+        HashState::new(),
+        1,
+        forged_message,
+        &known,
+        &known[0],
+        &nonce,
+    )?;
+    assert_eq!(
+        decrypt_shared(
+            HashState::new(),
+            1,
+            &forged,
+            &keystream,
+            &tag_key,
+            &nonce
+        ),
+        Err(Error::DecryptionFailed)
+    );
+
+    // the shares need to be present and have the same length
+    let shares = [vec![BlsScalar::one(); 3], vec![BlsScalar::one(); 2]];
+    assert_eq!(
+        combine_keystream(HashState::new(), shares),
+        Err(Error::InvalidInput)
+    );
+    assert_eq!(
+        combine_keystream(HashState::new(), Vec::<Vec<BlsScalar>>::new()),
+        Err(Error::InvalidInput)
+    );
+    assert_eq!(
+        combine_tag_key(HashState::new(), []),
+        Err(Error::InvalidInput)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn foreign_shared_secret() -> Result<(), Error> {
+    // the encodings embed into little endian limbs of 7 bytes
+    let generator = JubJubAffine::from(GENERATOR_EXTENDED).to_bytes();
+    let limbs: Vec<BlsScalar> = generator.embed();
+    assert_eq!(
+        limbs,
+        [
+            BlsScalar::from(0x12),
+            BlsScalar::zero(),
+            BlsScalar::zero(),
+            BlsScalar::zero(),
+            BlsScalar::zero(),
+        ]
+    );
+    let scalar = JubJubScalar::from_raw([1, 2, 3, 4]).to_bytes();
+    let limbs: Vec<BlsScalar> = scalar.embed();
+    assert_eq!(
+        limbs,
+        [
+            BlsScalar::from(1),
+            BlsScalar::from(2 << 8),
+            BlsScalar::from(3 << 16),
+            BlsScalar::from(4 << 24),
+            BlsScalar::zero(),
+        ]
+    );
+
+    // both parties of the exchange derive the same key
+    let a = JubJubScalar::from(42u64);
+    let b = JubJubScalar::from(43u64);
+    let secret_a = JubJubAffine::from(GENERATOR_EXTENDED * b * a).to_bytes();
+    let secret_b = JubJubAffine::from(GENERATOR_EXTENDED * a * b).to_bytes();
+    let key = embed_shared_secret(HashState::new(), &secret_a)?;
+    assert_eq!(key, embed_shared_secret(HashState::new(), &secret_b)?);
+    assert_ne!(key, embed_shared_secret(HashState::new(), &generator)?);
+
+    let message = [BlsScalar::from(1), BlsScalar::from(2)];
+    let nonce = BlsScalar::from(3);
+    let cipher = encrypt(HashState::new(), DOMAIN, message, &key, &nonce)?;
+    let decrypted = decrypt(HashState::new(), DOMAIN, &cipher, &key, &nonce)?;
+    assert_eq!(decrypted[..], message[..]);
+
+    assert_eq!(
+        embed_shared_secret(HashState::new(), &[0u8; 0]),
+        Err(Error::InvalidIOPattern)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn wrap_unwrap_key() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+
+    let (key, wrapping_key, nonce) = encryption_variables(&mut rng, 2);
+    let wrapping_key = wrapping_key.to_hash_inputs();
+
+    let wrapped = wrap_key(HashState::new(), &key, &wrapping_key, &nonce)?;
+    assert_eq!(wrapped.len(), key.len() + 2);
+
+    let unwrapped =
+        unwrap_key(HashState::new(), &wrapped, &wrapping_key, &nonce)?;
+    assert_eq!(*unwrapped, key);
+
+    // the wrapped key is not a plain cipher-text of the key
+    let cipher =
+        encrypt(HashState::new(), DOMAIN, &key, &wrapping_key, &nonce)?;
+    assert_ne!(wrapped[1..], cipher[..]);
+
+    // unwrapping with another key fails
+    let wrong_key =
+        (GENERATOR_EXTENDED * JubJubScalar::random(&mut rng)).to_hash_inputs();
+    let error =
+        unwrap_key(HashState::new(), &wrapped, &wrong_key, &nonce).unwrap_err();
+    assert_eq!(error, Error::DecryptionFailed);
+
+    // a tampered commitment fails
+    let mut tampered = wrapped;
+    tampered[0] += BlsScalar::one();
+    let error = unwrap_key(HashState::new(), &tampered, &wrapping_key, &nonce)
+        .unwrap_err();
+    assert_eq!(error, Error::DecryptionFailed);
+
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn encrypt_decrypt_stream() -> std::io::Result<()> {
+    use dusk_safe::{decrypt_stream, encrypt_stream, STREAM_CHUNK_LEN};
+    use rand::RngCore;
+
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let (_, shared_secret, nonce) = encryption_variables(&mut rng, 0);
+    let shared_secret = shared_secret.to_hash_inputs();
+    let encode = |element: &BlsScalar| element.to_bytes();
+    let decode = |bytes: &[u8; 32]| {
+        Option::<BlsScalar>::from(BlsScalar::from_bytes(bytes))
+    };
+
+    let chunk_bytes = STREAM_CHUNK_LEN * 31;
+    for len in [0, 10, chunk_bytes, 2 * chunk_bytes + 100] {
+        let mut plain = vec![0u8; len];
+        rng.fill_bytes(&mut plain);
+
+        let mut cipher = Vec::new();
+        encrypt_stream(
+            HashState::new(),
+            DOMAIN,
+            &plain[..],
+            &mut cipher,
+            &shared_secret,
+            &nonce,
+            encode,
+            decode,
+        )?;
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(
+            HashState::new(),
+            DOMAIN,
+            &cipher[..],
+            &mut decrypted,
+            &shared_secret,
+            &nonce,
+            encode,
+            decode,
+        )?;
+        assert_eq!(plain, decrypted);
+    }
+
+    // truncating the cipher-text at a chunk boundary fails
+    let plain = vec![42u8; 2 * chunk_bytes];
+    let mut cipher = Vec::new();
+    encrypt_stream(
+        HashState::new(),
+        DOMAIN,
+        &plain[..],
+        &mut cipher,
+        &shared_secret,
+        &nonce,
+        encode,
+        decode,
+    )?;
+    let truncated = &cipher[..cipher.len() / 2];
+    let result = decrypt_stream(
+        HashState::new(),
+        DOMAIN,
+        truncated,
+        &mut Vec::new(),
+        &shared_secret,
+        &nonce,
+        encode,
+        decode,
+    );
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn try_decrypt_batch() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+    let message_len = 4usize;
+    let nonce = |i: usize| BlsScalar::from(i as u64);
+
+    // encrypt every other message to the right shared secret
+    let (_, shared_secret, _) = encryption_variables(&mut rng, 0);
+    let (_, other_secret, _) = encryption_variables(&mut rng, 0);
+    let shared_secret = shared_secret.to_hash_inputs();
+    let other_secret = other_secret.to_hash_inputs();
+    let mut ciphers = Vec::new();
+    for i in 0..10 {
+        let (message, _, _) = encryption_variables(&mut rng, message_len);
+        let secret = match i % 2 {
+            0 => &shared_secret,
+            _ => &other_secret,
+        };
+        ciphers.push(encrypt(
+            HashState::new(),
+            DOMAIN,
+            &message,
+            secret,
+            &nonce(i),
+        )?);
+    }
+
+    let shared_secrets = vec![shared_secret; ciphers.len()];
+    let results = dusk_safe::try_decrypt_batch(
+        &HashState::new(),
+        DOMAIN,
+        &ciphers,
+        &shared_secrets,
+        nonce,
+    )?;
+    assert_eq!(results.len(), ciphers.len());
+    for (i, result) in results.into_iter().enumerate() {
+        match i % 2 {
+            0 => assert_eq!(result, Ok(())),
+            _ => assert_eq!(result, Err(Error::DecryptionFailed)),
+        }
+    }
+
+    assert_eq!(
+        dusk_safe::try_decrypt_batch(
+            &HashState::new(),
+            DOMAIN,
+            &ciphers,
+            &shared_secrets[1..],
+            nonce,
+        ),
+        Err(Error::OutputLenMismatch)
+    );
+
+    Ok(())
+}
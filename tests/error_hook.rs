@@ -0,0 +1,82 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "error-hook")]
+
+use std::sync::Mutex;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{set_error_hook, Call, Error, ErrorContext, Safe, Sponge};
+
+const W: usize = 7;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct Rotate();
+
+impl Safe<BlsScalar, W> for Rotate {
+    // rotate every item one item to the left, first item becomes last
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        state.rotate_left(1);
+    }
+
+    // Setting the tag to a constant zero here so that the sponge output
+    // is predictable, this should *not* be done in production as it makes the
+    // resulting hash vulnerable to collisions attacks.
+    fn tag(&mut self, _input: &[u8]) -> BlsScalar {
+        BlsScalar::zero()
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
+
+static REPORTED: Mutex<Vec<ErrorContext>> = Mutex::new(Vec::new());
+
+fn hook(context: &ErrorContext) {
+    REPORTED.lock().unwrap().push(*context);
+}
+
+#[test]
+fn error_hook() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 42;
+
+    set_error_hook(Some(hook));
+
+    let iopattern = vec![Call::Absorb(2), Call::Squeeze(1)];
+    let input = [BlsScalar::one(); 2];
+
+    // successful sponges don't report anything
+    let mut sponge = Sponge::start(Rotate(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(1)?;
+    sponge.finish()?;
+    assert!(REPORTED.lock().unwrap().is_empty());
+
+    // violating the IO-pattern reports the context of the error
+    let mut sponge = Sponge::start(Rotate(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(2, input)?;
+    assert!(sponge.squeeze(2).is_err());
+    assert_eq!(
+        REPORTED.lock().unwrap()[..],
+        [ErrorContext {
+            error: Error::IOPatternViolation,
+            call: Some(Call::Squeeze(2)),
+            expected: Some(Call::Squeeze(1)),
+            io_count: 1,
+            domain_sep,
+        }]
+    );
+
+    // no more reports after removing the hook
+    set_error_hook(None);
+    let sponge = Sponge::start(Rotate(), iopattern, domain_sep)?;
+    assert!(sponge.finish().is_err());
+    assert_eq!(REPORTED.lock().unwrap().len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "keccak")]
+
+use dusk_safe::{Call, Error, Keccak, Safe, Sponge, KECCAK_CAPACITY, KECCAK_W};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+fn hash(
+    domain_sep: u64,
+    input: &[u8],
+    output_len: usize,
+) -> Result<Vec<u8>, Error> {
+    let iopattern = vec![Call::Absorb(input.len()), Call::Squeeze(output_len)];
+    let mut sponge = Sponge::start(Keccak::new(), iopattern, domain_sep)?;
+    sponge.absorb(input.len(), input)?;
+    sponge.squeeze(output_len)?;
+    Ok(sponge.finish()?.to_vec())
+}
+
+#[test]
+fn tag_is_shake256() {
+    let input = b"tag input";
+
+    let mut shake = Shake256::default();
+    shake.update(input);
+    let mut expected = [0u8; KECCAK_CAPACITY];
+    shake.finalize_xof().read(&mut expected);
+
+    // the digest initializes the whole capacity, and the rate is empty
+    let mut keccak = Keccak::new();
+    assert_eq!(keccak.tag(input), expected[0]);
+    let state = keccak.tagged_state(input);
+    assert_eq!(state[..KECCAK_CAPACITY], expected);
+    assert_eq!(state[KECCAK_CAPACITY..], [0u8; KECCAK_W - KECCAK_CAPACITY]);
+    assert_eq!(Sponge::<Keccak, u8, KECCAK_W>::CAPACITY, KECCAK_CAPACITY);
+}
+
+#[test]
+fn keccak_sponge() -> Result<(), Error> {
+    // absorb and squeeze more than the rate of 136 bytes
+    let input: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    let output = hash(42, &input, 200)?;
+    assert_eq!(output.len(), 200);
+
+    assert_eq!(output, hash(42, &input, 200)?);
+    assert_ne!(output, hash(43, &input, 200)?);
+    assert_ne!(output[..32], hash(42, &input[1..], 32)?[..]);
+
+    Ok(())
+}
+
+#[test]
+fn keccak_suspend() -> Result<(), Error> {
+    let input: Vec<u8> = (0..200).map(|i| i as u8).collect();
+    let iopattern = vec![Call::Absorb(200), Call::Squeeze(32)];
+
+    // the whole capacity is part of the serialized state, so that a resumed
+    // sponge squeezes the same output
+    let mut sponge = Sponge::start(Keccak::new(), iopattern, 42)?;
+    sponge.absorb(200, &input)?;
+    let bytes = sponge.suspend(|byte| [*byte]);
+    let mut resumed =
+        Sponge::resume(Keccak::new(), &bytes, |byte: &[u8; 1]| Some(byte[0]))?;
+    resumed.squeeze(32)?;
+
+    assert_eq!(resumed.finish()?.to_vec(), hash(42, &input, 32)?);
+
+    Ok(())
+}
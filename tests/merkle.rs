@@ -0,0 +1,336 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "merkle")]
+
+mod common;
+
+use dusk_bls12_381::BlsScalar;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dusk_safe::{
+    merkle_hash, merkle_hash_batch, Error, Frontier, IncrementalMerkleTree,
+    MerkleMultiOpening, MerkleOpening, Safe, SparseMerkleTree,
+};
+
+const W: usize = 3;
+const DOMAIN: u64 = 0x10;
+
+type HashState = common::HashState<W>;
+
+#[test]
+fn sparse_root() -> Result<(), Error> {
+    let depth = 2;
+    let mut tree = SparseMerkleTree::new(HashState::new(), DOMAIN, depth)?;
+    let empty_root = tree.root();
+
+    let leaf = BlsScalar::from(42);
+    tree.insert(2, leaf)?;
+
+    // recompute the root by hand
+    let zero = BlsScalar::zero();
+    let left = merkle_hash(HashState::new(), DOMAIN, 0, &zero, &zero)?;
+    let right = merkle_hash(HashState::new(), DOMAIN, 0, &leaf, &zero)?;
+    let root = merkle_hash(HashState::new(), DOMAIN, 1, &left, &right)?;
+    assert_eq!(tree.root(), root);
+    assert_eq!(tree.get(2), leaf);
+
+    // removing the leaf restores the empty root
+    tree.remove(2)?;
+    assert_eq!(tree.root(), empty_root);
+
+    Ok(())
+}
+
+#[test]
+fn sparse_openings() -> Result<(), Error> {
+    let mut tree = SparseMerkleTree::new(HashState::new(), DOMAIN, 32)?;
+    let positions = [0u64, 1, 42, 0xffff_ffff];
+
+    for (i, position) in positions.iter().enumerate() {
+        tree.insert(*position, BlsScalar::from(i as u64 + 1))?;
+    }
+
+    for (i, position) in positions.iter().enumerate() {
+        let opening = tree.prove(*position)?;
+        let leaf = BlsScalar::from(i as u64 + 1);
+        assert!(tree.verify(&opening, &leaf)?);
+        assert!(!tree.verify(&opening, &BlsScalar::from(1234))?);
+    }
+
+    // non-membership is proven by opening an empty leaf
+    let opening = tree.prove(43)?;
+    assert!(tree.verify(&opening, &BlsScalar::zero())?);
+
+    // updating a leaf invalidates old openings
+    let opening = tree.prove(42)?;
+    tree.insert(42, BlsScalar::from(1234))?;
+    assert!(!tree.verify(&opening, &BlsScalar::from(3))?);
+    assert!(tree.verify(&tree.prove(42)?, &BlsScalar::from(1234))?);
+
+    Ok(())
+}
+
+#[test]
+fn sparse_fails() -> Result<(), Error> {
+    let mut tree = SparseMerkleTree::new(HashState::new(), DOMAIN, 8)?;
+    assert_eq!(
+        tree.insert(1 << 8, BlsScalar::one()).unwrap_err(),
+        Error::InvalidPosition
+    );
+    assert_eq!(tree.prove(1 << 8).unwrap_err(), Error::InvalidPosition);
+    assert_eq!(
+        SparseMerkleTree::new(HashState::new(), DOMAIN, 65).unwrap_err(),
+        Error::InvalidDepth
+    );
+
+    Ok(())
+}
+
+#[test]
+fn opening_fails() -> Result<(), Error> {
+    let depth = 8;
+    let mut tree = SparseMerkleTree::new(HashState::new(), DOMAIN, depth)?;
+    let leaf = BlsScalar::from(42);
+    tree.insert(3, leaf)?;
+    let root = tree.root();
+    let opening = tree.prove(3)?;
+    assert!(opening.verify(&HashState::new(), DOMAIN, depth, &root, &leaf)?);
+
+    // the depth is given by the verifier, not by the opening
+    let verify = |opening: &MerkleOpening<BlsScalar>, depth| {
+        opening.verify(&HashState::new(), DOMAIN, depth, &root, &leaf)
+    };
+    assert_eq!(verify(&opening, depth - 1), Err(Error::InvalidEncoding));
+    let short = MerkleOpening::new(3, opening.siblings()[1..].to_vec());
+    assert_eq!(verify(&short, depth), Err(Error::InvalidEncoding));
+    assert_eq!(verify(&opening, 65), Err(Error::InvalidDepth));
+
+    // openings with more than 64 siblings are rejected instead of panicking
+    let long = MerkleOpening::new(3, vec![BlsScalar::zero(); 70]);
+    assert_eq!(verify(&long, 70), Err(Error::InvalidDepth));
+    assert_eq!(verify(&long, depth), Err(Error::InvalidEncoding));
+
+    // positions outside of the tree are rejected
+    let outside = MerkleOpening::new(1 << depth, opening.siblings().to_vec());
+    assert_eq!(verify(&outside, depth), Err(Error::InvalidPosition));
+
+    Ok(())
+}
+
+#[test]
+fn leaves_and_nodes_are_separated() -> Result<(), Error> {
+    let depth = 2;
+    let mut tree = SparseMerkleTree::new(HashState::new(), DOMAIN, depth)?;
+    tree.insert(0, BlsScalar::from(1))?;
+    tree.insert(1, BlsScalar::from(2))?;
+    let root = tree.root();
+
+    // the leaves and the internal nodes are compressed under different tags
+    let (left, right) = (BlsScalar::from(1), BlsScalar::from(2));
+    assert_ne!(
+        merkle_hash(HashState::new(), DOMAIN, 0, &left, &right)?,
+        merkle_hash(HashState::new(), DOMAIN, 1, &left, &right)?
+    );
+
+    // an internal node can't be opened as a leaf of a shallower tree
+    let node = merkle_hash(HashState::new(), DOMAIN, 0, &left, &right)?;
+    let sibling = tree.prove(0)?.siblings()[1];
+    let forged = MerkleOpening::new(0, vec![sibling]);
+    assert!(forged
+        .verify(&HashState::new(), DOMAIN, depth, &root, &node)
+        .is_err());
+    assert!(!forged.verify(&HashState::new(), DOMAIN, 1, &root, &node)?);
+
+    Ok(())
+}
+
+#[test]
+fn incremental_matches_sparse() -> Result<(), Error> {
+    let depth = 4;
+    let mut sparse = SparseMerkleTree::new(HashState::new(), DOMAIN, depth)?;
+    let mut incremental =
+        IncrementalMerkleTree::new(HashState::new(), DOMAIN, depth)?;
+    assert_eq!(incremental.root(), sparse.root());
+
+    for i in 0..(1 << depth) {
+        let leaf = BlsScalar::from(i + 1);
+        assert_eq!(incremental.append(leaf)?, i);
+        sparse.insert(i, leaf)?;
+        assert_eq!(incremental.root(), sparse.root());
+    }
+
+    // the tree is full
+    assert_eq!(
+        incremental.append(BlsScalar::one()).unwrap_err(),
+        Error::InvalidPosition
+    );
+
+    Ok(())
+}
+
+#[test]
+fn incremental_resume() -> Result<(), Error> {
+    let depth = 20;
+    let mut tree = IncrementalMerkleTree::new(HashState::new(), DOMAIN, depth)?;
+    for i in 0..5 {
+        tree.append(BlsScalar::from(i))?;
+    }
+
+    // persist the frontier and resume the tree from it
+    let bytes = tree.frontier().to_bytes(BlsScalar::to_bytes);
+    let frontier = Frontier::from_bytes(&bytes, |bytes| {
+        Option::from(BlsScalar::from_bytes(bytes))
+    })?;
+    assert_eq!(&frontier, tree.frontier());
+    let mut resumed = IncrementalMerkleTree::from_frontier(
+        HashState::new(),
+        DOMAIN,
+        frontier,
+    )?;
+    assert_eq!(resumed.root(), tree.root());
+    assert_eq!(resumed.len(), 5);
+
+    // both trees evolve in the same way
+    for i in 5..10 {
+        tree.append(BlsScalar::from(i))?;
+        resumed.append(BlsScalar::from(i))?;
+    }
+    assert_eq!(resumed.root(), tree.root());
+
+    // truncated encodings fail
+    let error =
+        Frontier::<BlsScalar>::from_bytes(&bytes[..bytes.len() - 1], |bytes| {
+            Option::from(BlsScalar::from_bytes(bytes))
+        })
+        .unwrap_err();
+    assert_eq!(error, Error::InvalidEncoding);
+
+    Ok(())
+}
+
+#[test]
+fn multi_openings() -> Result<(), Error> {
+    let depth = 10;
+    let mut tree = SparseMerkleTree::new(HashState::new(), DOMAIN, depth)?;
+    for position in 0..40 {
+        tree.insert(position * 7, BlsScalar::from(position + 1))?;
+    }
+    let root = tree.root();
+
+    let positions = [14u64, 0, 7, 8, 1023, 21, 14];
+    let opening: MerkleMultiOpening<BlsScalar> = tree.prove_many(positions)?;
+    assert_eq!(opening.positions(), &[0, 7, 8, 14, 21, 1023]);
+
+    // the multi-opening is smaller than the individual openings combined
+    assert!(opening.siblings().len() < opening.positions().len() * depth);
+
+    let leaves: Vec<BlsScalar> =
+        opening.positions().iter().map(|p| tree.get(*p)).collect();
+    assert!(opening.verify(
+        &HashState::new(),
+        DOMAIN,
+        depth,
+        &root,
+        &leaves
+    )?);
+
+    // a wrong leaf fails the verification
+    let mut wrong_leaves = leaves.clone();
+    wrong_leaves[2] = BlsScalar::from(1234);
+    assert!(!opening.verify(
+        &HashState::new(),
+        DOMAIN,
+        depth,
+        &root,
+        &wrong_leaves
+    )?);
+
+    // a wrong amount of leaves is rejected
+    assert_eq!(
+        opening
+            .verify(&HashState::new(), DOMAIN, depth, &root, &leaves[1..])
+            .unwrap_err(),
+        Error::InvalidEncoding
+    );
+
+    // the depth is given by the verifier, not by the opening
+    let verify = |depth| {
+        opening.verify(&HashState::new(), DOMAIN, depth, &root, &leaves)
+    };
+    assert_eq!(verify(depth - 1), Err(Error::InvalidPosition));
+    assert_eq!(verify(depth + 1), Err(Error::InvalidEncoding));
+    assert_eq!(verify(65), Err(Error::InvalidDepth));
+
+    Ok(())
+}
+
+#[test]
+fn batched_compression() -> Result<(), Error> {
+    static BATCHES: AtomicUsize = AtomicUsize::new(0);
+
+    // a backend counting the batches of permutations
+    #[derive(Default, Debug, Clone, Copy, PartialEq)]
+    struct Batching(HashState);
+
+    impl Safe<BlsScalar, W> for Batching {
+        fn permute(&mut self, state: &mut [BlsScalar; W]) {
+            self.0.permute(state);
+        }
+
+        fn permute_batch(&mut self, states: &mut [[BlsScalar; W]]) {
+            BATCHES.fetch_add(1, Ordering::Relaxed);
+            states.iter_mut().for_each(|state| self.0.permute(state));
+        }
+
+        fn tag(&mut self, input: &[u8]) -> BlsScalar {
+            self.0.tag(input)
+        }
+
+        fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+            self.0.add(right, left)
+        }
+    }
+
+    // the batch compresses every pair like the sponge
+    let pairs: Vec<(BlsScalar, BlsScalar)> = (0..5)
+        .map(|i| (BlsScalar::from(i), BlsScalar::from(i + 10)))
+        .collect();
+    for height in [0, 3] {
+        let batch = merkle_hash_batch(
+            &mut Batching::default(),
+            DOMAIN,
+            height,
+            &pairs,
+        )?;
+        for ((left, right), parent) in pairs.iter().zip(batch) {
+            let expected =
+                merkle_hash(HashState::new(), DOMAIN, height, left, right)?;
+            assert_eq!(parent, expected);
+        }
+    }
+
+    // the multi-opening permutes every level of the tree in one batch
+    let depth = 6;
+    let mut tree = SparseMerkleTree::new(Batching::default(), DOMAIN, depth)?;
+    for position in [1, 5, 17, 40] {
+        tree.insert(position, BlsScalar::from(position))?;
+    }
+    let opening = tree.prove_many([1, 5, 17, 40])?;
+    let leaves: Vec<BlsScalar> =
+        opening.positions().iter().map(|p| tree.get(*p)).collect();
+    let before = BATCHES.load(Ordering::Relaxed);
+    assert!(opening.verify(
+        &Batching::default(),
+        DOMAIN,
+        depth,
+        &tree.root(),
+        &leaves
+    )?);
+    assert_eq!(BATCHES.load(Ordering::Relaxed) - before, depth);
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+mod common;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{
+    hash_padded, Call, Error, Padding, PaddingScheme, Safe, Sponge,
+};
+
+const W: usize = 7;
+const DOMAIN: u64 = 42;
+
+type HashState = common::HashState<W>;
+
+#[test]
+fn padding_schemes_differ() -> Result<(), Error> {
+    // with one-padding the input [4, 1, 2, 3] is absorbed as [4, 1, 2, 3, 1]
+    let one_input = [
+        BlsScalar::from(4),
+        BlsScalar::from(1),
+        BlsScalar::from(2),
+        BlsScalar::from(3),
+    ];
+    // with length-padding the input [1, 2, 3, 1] is absorbed as [4, 1, 2, 3, 1]
+    let length_input = [
+        BlsScalar::from(1),
+        BlsScalar::from(2),
+        BlsScalar::from(3),
+        BlsScalar::from(1),
+    ];
+
+    let one_output =
+        hash_padded(HashState::new(), Padding::One, DOMAIN, one_input, 1)?;
+    let length_output = hash_padded(
+        HashState::new(),
+        Padding::Length,
+        DOMAIN,
+        length_input,
+        1,
+    )?;
+
+    // the domain-separator binds the padding scheme
+    assert_ne!(one_output, length_output);
+
+    Ok(())
+}
+
+#[test]
+fn padding_lengths() -> Result<(), Error> {
+    let input = [BlsScalar::one(); 10];
+
+    for padding in [Padding::One, Padding::Length] {
+        // inputs of different length never collide
+        let mut outputs = Vec::new();
+        for len in 0..input.len() {
+            let output = hash_padded(
+                HashState::new(),
+                padding,
+                DOMAIN,
+                &input[..len],
+                2,
+            )?;
+            assert_eq!(output.len(), 2);
+            assert!(!outputs.contains(&output));
+            outputs.push(output);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn reserved_domain_fails() {
+    let input = [BlsScalar::one(); 2];
+
+    let error = hash_padded(HashState::new(), Padding::One, 1 << 63, input, 1)
+        .unwrap_err();
+    assert_eq!(error, Error::InvalidDomain);
+
+    // the padding scheme is stored in the reserved bits
+    let domain_sep = Padding::Length.domain_sep(42).unwrap();
+    assert_eq!(domain_sep & !Padding::DOMAIN_MASK, 42);
+    assert_ne!(domain_sep & Padding::DOMAIN_MASK, 0);
+}
+
+// appends an element encoding the length of the input, padding every input
+// with one element to a multiple of three
+#[derive(Debug, Clone, Copy)]
+struct TrailingLength;
+
+impl PaddingScheme<BlsScalar> for TrailingLength {
+    fn id(&self) -> u8 {
+        0x7f
+    }
+
+    fn absorb_calls(&self, input_len: usize) -> Vec<Call> {
+        let padded = (input_len + 3) / 3 * 3;
+        vec![Call::Absorb(padded)]
+    }
+
+    fn absorb<S, const W: usize>(
+        &self,
+        sponge: &mut Sponge<S, BlsScalar, W>,
+        input: &[BlsScalar],
+    ) -> Result<(), Error>
+    where
+        S: Safe<BlsScalar, W>,
+    {
+        let mut padded = input.to_vec();
+        padded.resize(padded.len() + 1, BlsScalar::from(input.len() as u64));
+        padded.resize((padded.len() + 2) / 3 * 3, BlsScalar::zero());
+        sponge.absorb(padded.len(), padded)
+    }
+}
+
+#[test]
+fn custom_padding_scheme() -> Result<(), Error> {
+    let input = [1, 2, 3, 4].map(BlsScalar::from);
+
+    // the standard schemes implement the trait
+    assert_eq!(
+        PaddingScheme::<BlsScalar>::absorb_calls(&Padding::One, 4),
+        vec![Call::Absorb(4), Call::Absorb(1)]
+    );
+
+    // a custom scheme plugs into the hashing of variable-length inputs
+    let output =
+        hash_padded(HashState::new(), TrailingLength, DOMAIN, input, 1)?;
+    assert_ne!(
+        output,
+        hash_padded(HashState::new(), Padding::One, DOMAIN, input, 1)?
+    );
+
+    // which is equivalent to absorbing the padded input
+    let padded = [1, 2, 3, 4, 4, 0].map(BlsScalar::from);
+    let domain_sep = TrailingLength.domain_sep(DOMAIN)?;
+    let mut sponge = Sponge::start(
+        HashState::new(),
+        vec![Call::Absorb(6), Call::Squeeze(1)],
+        domain_sep,
+    )?;
+    sponge.absorb(6, padded)?;
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.finish()?, output);
+
+    // the sponge applies the padding at the end of every absorb phase
+    let mut iopattern = TrailingLength.absorb_calls(2);
+    iopattern.push(Call::Squeeze(1));
+    iopattern.extend(TrailingLength.absorb_calls(4));
+    iopattern.push(Call::Squeeze(1));
+    let mut sponge = Sponge::start(HashState::new(), iopattern, domain_sep)?;
+    sponge.absorb_padded(&TrailingLength, &input[..2])?;
+    sponge.squeeze(1)?;
+    sponge.absorb_padded(&TrailingLength, input)?;
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.finish()?.len(), 2);
+
+    Ok(())
+}
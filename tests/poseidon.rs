@@ -0,0 +1,182 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Compatibility tests asserting that this crate's cipher, run with the Hades
+//! permutation, reproduces the ciphertexts of `dusk-poseidon`.
+
+#![cfg(feature = "encryption")]
+
+use dusk_bls12_381::BlsScalar;
+use dusk_jubjub::{dhke, JubJubAffine, JubJubScalar, GENERATOR_EXTENDED};
+use dusk_poseidon::{Domain, HADES_WIDTH};
+use dusk_safe::{decrypt, encrypt, Encryption, Error, Safe};
+use ff::Field;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const W: usize = HADES_WIDTH;
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 60;
+const ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+
+// The round constants and the MDS matrix are the assets shipped with
+// `dusk-poseidon`, stored as little endian limbs of 32 bytes per scalar.
+const ARC: &[u8] = include_bytes!("assets/arc.bin");
+const MDS: &[u8] = include_bytes!("assets/mds.bin");
+
+fn scalar_at(bytes: &[u8], index: usize) -> BlsScalar {
+    let mut limbs = [0u64; 4];
+    limbs.iter_mut().enumerate().for_each(|(i, limb)| {
+        let start = index * 32 + i * 8;
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&bytes[start..start + 8]);
+        *limb = u64::from_le_bytes(buf);
+    });
+    BlsScalar::from_raw(limbs)
+}
+
+struct Hades {
+    round_constants: Vec<[BlsScalar; W]>,
+    mds: [[BlsScalar; W]; W],
+}
+
+impl Hades {
+    fn new() -> Self {
+        let round_constants = (0..ROUNDS)
+            .map(|round| {
+                core::array::from_fn(|i| scalar_at(ARC, round * W + i))
+            })
+            .collect();
+        let mds = core::array::from_fn(|k| {
+            core::array::from_fn(|j| scalar_at(MDS, k * W + j))
+        });
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+
+    fn round(&self, round: usize, state: &mut [BlsScalar; W], full: bool) {
+        state
+            .iter_mut()
+            .zip(self.round_constants[round].iter())
+            .for_each(|(s, c)| *s += c);
+
+        let quintic = |s: &mut BlsScalar| *s = s.square().square() * *s;
+        if full {
+            state.iter_mut().for_each(quintic);
+        } else {
+            quintic(&mut state[W - 1]);
+        }
+
+        let mut result = [BlsScalar::zero(); W];
+        for (j, value) in state.iter().enumerate() {
+            for (k, r) in result.iter_mut().enumerate() {
+                *r += self.mds[k][j] * value;
+            }
+        }
+        *state = result;
+    }
+}
+
+impl Safe<BlsScalar, W> for Hades {
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        let half = FULL_ROUNDS / 2;
+        (0..half).for_each(|r| self.round(r, state, true));
+        (half..half + PARTIAL_ROUNDS).for_each(|r| self.round(r, state, false));
+        (half + PARTIAL_ROUNDS..ROUNDS)
+            .for_each(|r| self.round(r, state, true));
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        BlsScalar::hash_to_scalar(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
+
+impl Encryption<BlsScalar, W> for Hades {
+    fn subtract(
+        &mut self,
+        minuend: &BlsScalar,
+        subtrahend: &BlsScalar,
+    ) -> BlsScalar {
+        minuend - subtrahend
+    }
+
+    fn is_equal(&mut self, lhs: &BlsScalar, rhs: &BlsScalar) -> bool {
+        lhs == rhs
+    }
+}
+
+fn shared_secret(rng: &mut StdRng) -> JubJubAffine {
+    let secret = JubJubScalar::random(&mut *rng);
+    let public = GENERATOR_EXTENDED * JubJubScalar::random(&mut *rng);
+    dhke(&secret, &public)
+}
+
+#[test]
+fn encrypt_matches_dusk_poseidon() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x42424242);
+
+    for len in [1, 2, 3, 4, 5, 11] {
+        let message: Vec<BlsScalar> =
+            (0..len).map(|_| BlsScalar::random(&mut rng)).collect();
+        let secret = shared_secret(&mut rng);
+        let nonce = BlsScalar::random(&mut rng);
+
+        let expected = dusk_poseidon::encrypt(&message, &secret, &nonce)
+            .expect("dusk-poseidon encryption should pass");
+        let cipher = encrypt(
+            Hades::new(),
+            Domain::Encryption,
+            &message,
+            &[secret.get_u(), secret.get_v()],
+            &nonce,
+        )?;
+
+        assert_eq!(cipher, expected, "message length {len}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn decrypt_dusk_poseidon_cipher() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0x1618);
+
+    let message: Vec<BlsScalar> =
+        (0..7).map(|_| BlsScalar::random(&mut rng)).collect();
+    let secret = shared_secret(&mut rng);
+    let nonce = BlsScalar::random(&mut rng);
+
+    let cipher = dusk_poseidon::encrypt(&message, &secret, &nonce)
+        .expect("dusk-poseidon encryption should pass");
+    let decrypted = decrypt(
+        Hades::new(),
+        Domain::Encryption,
+        cipher,
+        &[secret.get_u(), secret.get_v()],
+        &nonce,
+    )?;
+    assert_eq!(*decrypted, message);
+
+    // and the other way around
+    let cipher = encrypt(
+        Hades::new(),
+        Domain::Encryption,
+        &message,
+        &[secret.get_u(), secret.get_v()],
+        &nonce,
+    )?;
+    let decrypted = dusk_poseidon::decrypt(cipher, &secret, &nonce)
+        .expect("dusk-poseidon decryption should pass");
+    assert_eq!(decrypted, message);
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+mod common;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{
+    derive_id, derive_nonce, hash_cascade, hash_padded, hash_password,
+    kdf_expand, kdf_extract, prf, signing_nonce, Error, KeyChain, Padding,
+};
+
+const W: usize = 5;
+const DOMAIN: u64 = 42;
+
+type HashState = common::HashState<W>;
+
+#[test]
+fn prf_is_deterministic() -> Result<(), Error> {
+    let key = [BlsScalar::from(1), BlsScalar::from(2)];
+    let input = [BlsScalar::from(3)];
+
+    let output1 = prf(HashState::new(), DOMAIN, key, input, 3)?;
+    let output2 = prf(HashState::new(), DOMAIN, key, input, 3)?;
+    assert_eq!(output1, output2);
+    assert_eq!(output1.len(), 3);
+
+    // the output depends on the key, input and domain
+    let other_key = [BlsScalar::from(1), BlsScalar::from(3)];
+    assert_ne!(output1, prf(HashState::new(), DOMAIN, other_key, input, 3)?);
+    assert_ne!(output1, prf(HashState::new(), DOMAIN, key, key, 3)?);
+    assert_ne!(output1, prf(HashState::new(), DOMAIN + 1, key, input, 3)?);
+
+    Ok(())
+}
+
+#[test]
+fn prf_key_boundary() -> Result<(), Error> {
+    let elements = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::from(3)];
+
+    // moving elements between key and input changes the output
+    let output1 =
+        prf(HashState::new(), DOMAIN, &elements[..1], &elements[1..], 1)?;
+    let output2 =
+        prf(HashState::new(), DOMAIN, &elements[..2], &elements[2..], 1)?;
+    assert_ne!(output1, output2);
+
+    // the prf is separated from the padded hash of the same elements
+    let input = [BlsScalar::from(1), BlsScalar::from(1), BlsScalar::from(2)];
+    let hash =
+        hash_padded(HashState::new(), Padding::Length, DOMAIN, &input[1..], 1)?;
+    assert_ne!(output1, hash);
+
+    Ok(())
+}
+
+#[test]
+fn prf_fails() {
+    let key = [BlsScalar::one()];
+
+    // the domain uses reserved bits
+    let error = prf(HashState::new(), 1 << 56, key, key, 1).unwrap_err();
+    assert_eq!(error, Error::InvalidDomain);
+
+    // the key is empty
+    let error = prf(HashState::new(), DOMAIN, [], key, 1).unwrap_err();
+    assert_eq!(error, Error::InvalidIOPattern);
+}
+
+#[test]
+fn password_hashing() -> Result<(), Error> {
+    let salt = [BlsScalar::from(0x5a17)];
+    let password = [BlsScalar::from(1234)];
+
+    let hash = hash_password(HashState::new(), DOMAIN, salt, password, 100, 2)?;
+    assert_eq!(hash.len(), 2);
+    assert_eq!(
+        hash,
+        hash_password(HashState::new(), DOMAIN, salt, password, 100, 2)?
+    );
+
+    // the iteration count, salt and password are all bound into the output
+    assert_ne!(
+        hash,
+        hash_password(HashState::new(), DOMAIN, salt, password, 101, 2)?
+    );
+    assert_ne!(
+        hash,
+        hash_password(HashState::new(), DOMAIN, password, password, 100, 2)?
+    );
+    assert_ne!(
+        hash,
+        hash_password(HashState::new(), DOMAIN, salt, salt, 100, 2)?
+    );
+
+    // moving elements between salt and password changes the output
+    let elements = [salt[0], password[0]];
+    assert_ne!(
+        hash_password(HashState::new(), DOMAIN, elements, [], 100, 2)?,
+        hash_password(HashState::new(), DOMAIN, [], elements, 100, 2)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn extract_then_expand() -> Result<(), Error> {
+    let salt = [BlsScalar::from(7)];
+    let ikm = [BlsScalar::from(1), BlsScalar::from(2)];
+    let info = [BlsScalar::from(3)];
+
+    let prk = kdf_extract(HashState::new(), DOMAIN, salt, ikm)?;
+    assert_eq!(prk, kdf_extract(HashState::new(), DOMAIN, salt, ikm)?);
+    assert_ne!(prk, kdf_extract(HashState::new(), DOMAIN, [], ikm)?);
+
+    let okm = kdf_expand(HashState::new(), DOMAIN, &prk, info, 4)?;
+    assert_eq!(okm.len(), 4);
+
+    // the output length is bound into the tag, so shorter outputs are not a
+    // prefix of longer ones
+    let short = kdf_expand(HashState::new(), DOMAIN, &prk, info, 2)?;
+    assert_ne!(okm[..2], short[..]);
+    assert_ne!(okm, kdf_expand(HashState::new(), DOMAIN, &prk, [], 4)?);
+
+    // the two phases are domain separated
+    let extracted = kdf_extract(HashState::new(), DOMAIN, [], [*prk])?;
+    assert_ne!(
+        *extracted,
+        kdf_expand(HashState::new(), DOMAIN, &prk, [], 1)?[0]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn deterministic_signing_nonce() -> Result<(), Error> {
+    let secret_key = [BlsScalar::from(0x5ec)];
+    let message = [BlsScalar::from(1), BlsScalar::from(2)];
+
+    let nonce = signing_nonce(HashState::new(), DOMAIN, secret_key, message)?;
+    assert_eq!(
+        nonce,
+        signing_nonce(HashState::new(), DOMAIN, secret_key, message)?
+    );
+
+    // another message or key results in another nonce
+    assert_ne!(
+        nonce,
+        signing_nonce(HashState::new(), DOMAIN, secret_key, &message[..1])?
+    );
+    assert_ne!(
+        nonce,
+        signing_nonce(HashState::new(), DOMAIN, message, message)?
+    );
+
+    // the nonce is separated from the prf with the same inputs
+    assert_ne!(
+        *nonce,
+        prf(HashState::new(), DOMAIN, secret_key, message, 1)?[0]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn counter_nonce() -> Result<(), Error> {
+    let master_secret = [BlsScalar::from(0x5ec), BlsScalar::from(0x2e7)];
+
+    let nonce = derive_nonce(HashState::new(), DOMAIN, master_secret, 0)?;
+    assert_eq!(
+        nonce,
+        derive_nonce(HashState::new(), DOMAIN, master_secret, 0)?
+    );
+
+    // every counter value results in another nonce
+    let nonces = (0..16)
+        .map(|counter| {
+            derive_nonce(HashState::new(), DOMAIN, master_secret, counter)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    for (i, nonce) in nonces.iter().enumerate() {
+        assert!(nonces[i + 1..].iter().all(|other| other != nonce));
+    }
+
+    // the nonce is separated from the prf with the same inputs
+    let input = [BlsScalar::from(0)];
+    assert_ne!(
+        *nonce,
+        prf(HashState::new(), DOMAIN, master_secret, input, 1)?[0]
+    );
+
+    assert_eq!(
+        derive_nonce(HashState::new(), DOMAIN, [], 0),
+        Err(Error::InvalidIOPattern)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn key_chain() -> Result<(), Error> {
+    let initial_key = BlsScalar::from(0x5eed);
+
+    let mut chain = KeyChain::new(HashState::new(), DOMAIN, initial_key)?;
+    let mut keys = Vec::new();
+    for epoch in 0..5 {
+        assert_eq!(chain.epoch(), epoch);
+        let key = chain.advance()?;
+        assert!(!keys.contains(&key));
+        keys.push(key);
+    }
+
+    // the same initial key results in the same chain
+    let mut chain = KeyChain::new(HashState::new(), DOMAIN, initial_key)?;
+    for key in keys {
+        assert_eq!(chain.advance()?, key);
+    }
+
+    // reserved domains are rejected
+    assert_eq!(
+        KeyChain::new(HashState::new(), 1 << 60, initial_key).unwrap_err(),
+        Error::InvalidDomain
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cascade_hashing() -> Result<(), Error> {
+    let input: Vec<BlsScalar> = (0..9).map(BlsScalar::from).collect();
+    let output = hash_cascade(HashState::new(), DOMAIN, &input, 2)?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(output, hash_cascade(HashState::new(), DOMAIN, &input, 2)?);
+
+    // the output depends on every chunk and on the length of the input
+    let mut other = input.clone();
+    other[8] = BlsScalar::from(42);
+    assert_ne!(output, hash_cascade(HashState::new(), DOMAIN, &other, 2)?);
+    assert_ne!(
+        output,
+        hash_cascade(HashState::new(), DOMAIN, &input[..8], 2)?
+    );
+    assert_ne!(
+        output,
+        hash_cascade(HashState::new(), DOMAIN + 1, &input, 2)?
+    );
+
+    assert_eq!(
+        hash_cascade::<_, BlsScalar, W>(HashState::new(), DOMAIN, [], 1),
+        Err(Error::InvalidIOPattern)
+    );
+    assert_eq!(
+        hash_cascade(HashState::new(), 1 << 60, &input, 1),
+        Err(Error::InvalidDomain)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn identifier_derivation() -> Result<(), Error> {
+    let inputs = [BlsScalar::from(1), BlsScalar::from(2)];
+    let id = derive_id(HashState::new(), DOMAIN, &inputs)?;
+    assert_eq!(id, derive_id(HashState::new(), DOMAIN, &inputs)?);
+    assert_eq!(id, derive_id(HashState::new(), DOMAIN, &inputs.to_vec())?);
+
+    // the identifier depends on the inputs and the domain
+    let other = [BlsScalar::from(2), BlsScalar::from(1)];
+    assert_ne!(id, derive_id(HashState::new(), DOMAIN, &other)?);
+    assert_ne!(id, derive_id(HashState::new(), DOMAIN + 1, &inputs)?);
+    assert_ne!(id, prf(HashState::new(), DOMAIN, inputs, [], 1)?[0]);
+
+    assert_eq!(
+        derive_id::<_, BlsScalar, W>(HashState::new(), DOMAIN, &[]),
+        Err(Error::InvalidIOPattern)
+    );
+    assert_eq!(
+        derive_id(HashState::new(), 1 << 60, &inputs),
+        Err(Error::InvalidDomain)
+    );
+
+    Ok(())
+}
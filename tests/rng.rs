@@ -0,0 +1,76 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+mod common;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{Error, SpongeRng};
+
+const W: usize = 5;
+
+type HashState = common::HashState<W>;
+
+#[test]
+fn sponge_rng_known_answer() -> Result<(), Error> {
+    // the elements are part of the API, they must not change across
+    // platforms or versions of the crate
+    let expected = [
+        BlsScalar::from_raw([
+            0x859c1d9c9438261e,
+            0xc50061f76f9ef8f3,
+            0x5b95da97050a36ed,
+            0x6bba8eba493c7a62,
+        ]),
+        BlsScalar::from_raw([
+            0x6801bd18f026a217,
+            0x16f2bcd587d0f81f,
+            0x6fb9904ea9eef62c,
+            0x3fb2bbec246578ae,
+        ]),
+    ];
+    let mut rng = SpongeRng::from_seed(HashState::new(), 42)?;
+    assert_eq!(rng.next_element()?, expected[0]);
+    assert_eq!(rng.next_element()?, expected[1]);
+
+    let expected = BlsScalar::from_raw([
+        0x843be8d9ab8afe59,
+        0x4c9038b6a055cf3d,
+        0x4b465cfb23f678f7,
+        0x2041a2d78c79a5b2,
+    ]);
+    let mut rng = SpongeRng::from_bytes(HashState::new(), b"dusk")?;
+    assert_eq!(rng.next_element()?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn sponge_rng_seeds() -> Result<(), Error> {
+    let mut rng = SpongeRng::from_seed(HashState::new(), 42)?;
+    let mut elements = [BlsScalar::zero(); 4];
+    rng.fill(&mut elements)?;
+    for (i, element) in elements.iter().enumerate() {
+        assert!(!elements[..i].contains(element));
+    }
+
+    // a u64 seed is its big endian bytes
+    let mut rng = SpongeRng::from_bytes(HashState::new(), 42u64.to_be_bytes())?;
+    for element in elements {
+        assert_eq!(rng.next_element()?, element);
+    }
+
+    // the seed is bound with its length, so trailing zeros matter
+    let mut rng = SpongeRng::from_bytes(HashState::new(), [1])?;
+    let mut padded = SpongeRng::from_bytes(HashState::new(), [1, 0])?;
+    assert_ne!(rng.next_element()?, padded.next_element()?);
+
+    // an empty seed is accepted
+    let mut rng = SpongeRng::from_bytes(HashState::new(), [])?;
+    let mut zero = SpongeRng::from_bytes(HashState::new(), [0])?;
+    assert_ne!(rng.next_element()?, zero.next_element()?);
+
+    Ok(())
+}
@@ -4,8 +4,21 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+// `needless_borrow` was split into `needless_borrows_for_generic_args` after
+// the pinned toolchain
+#![allow(unknown_lints)]
+#![allow(clippy::needless_borrow, clippy::needless_borrows_for_generic_args)]
+
 use dusk_bls12_381::BlsScalar;
-use dusk_safe::{Call, Error, Safe, Sponge};
+use dusk_jubjub::JubJubScalar;
+use dusk_safe::{
+    aggregate, embed_bytes, optimize_pattern, pattern_cost, pattern_digest,
+    self_test, squeeze_len, tag_input, AbsorbMode, Absorbable, Absorbing, Call,
+    CostModel, Embed, Error, FieldWriter, Finished, LenMismatch, OutputMode,
+    PatternSchedule, Safe, SafeCtx, ScheduledPermutation, SecurityLevel,
+    Sponge, SqueezeInto, Squeezing, TagVersion, TypedSponge, WithCtx,
+    EMBED_LIMB_BYTES,
+};
 
 const W: usize = 7;
 
@@ -139,11 +152,11 @@ fn absorb_fails() -> Result<(), Error> {
     let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
 
     // input-slice smaller than len
-    let error = sponge.clone().absorb(6, &input[..4]).unwrap_err();
+    let error = sponge.checkpoint().absorb(6, &input[..4]).unwrap_err();
     assert_eq!(error, Error::TooFewInputElements);
 
     // absorb len is not as io-pattern specifies
-    let error = sponge.clone().absorb(4, &input[..4]).unwrap_err();
+    let error = sponge.checkpoint().absorb(4, &input[..4]).unwrap_err();
     assert_eq!(error, Error::IOPatternViolation);
 
     // unexpected call to squeeze
@@ -169,7 +182,7 @@ fn squeeze_fails() -> Result<(), Error> {
     sponge.absorb(6, &input[..6])?;
 
     // squeeze 4 elements when io-pattern expects 1
-    let error = sponge.clone().squeeze(4).unwrap_err();
+    let error = sponge.checkpoint().squeeze(4).unwrap_err();
     assert_eq!(error, Error::IOPatternViolation);
 
     // unexpected call to absorb when io-pattern expects squeeze
@@ -201,7 +214,7 @@ fn finish_fails() -> Result<(), Error> {
     sponge.squeeze(1)?;
 
     // try to finalize before the io-pattern is exhausted
-    let error = sponge.clone().finish().unwrap_err();
+    let error = sponge.checkpoint().finish().unwrap_err();
     assert_eq!(error, Error::IOPatternViolation);
 
     // absorb 1 element as specified by the io-pattern
@@ -215,3 +228,1680 @@ fn finish_fails() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn static_iopattern() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    // a fixed io-pattern that is borrowed instead of being copied into the
+    // sponge
+    const IOPATTERN: &[Call] = &[Call::Absorb(2), Call::Squeeze(1)];
+
+    let input = [BlsScalar::one(); 2];
+
+    let mut sponge =
+        Sponge::start_static(Rotate::new(), IOPATTERN, domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(1)?;
+    let output_static = sponge.finish()?;
+
+    let mut sponge = Sponge::start(Rotate::new(), IOPATTERN, domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(1)?;
+    let output_owned = sponge.finish()?;
+
+    assert_eq!(output_static, output_owned);
+
+    // const arrays are borrowed the same way
+    const IOPATTERN_ARRAY: [Call; 2] = [Call::Absorb(2), Call::Squeeze(1)];
+    let mut sponge =
+        Sponge::start_static(Rotate::new(), &IOPATTERN_ARRAY, domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.finish()?, output_owned);
+
+    // owned patterns are moved into the sponge
+    let iopattern = vec![Call::Absorb(2), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.finish()?, output_owned);
+
+    Ok(())
+}
+
+#[test]
+fn output_spills() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let input = [BlsScalar::one(); 2];
+
+    // short outputs are kept inline
+    let iopattern = vec![Call::Absorb(2), Call::Squeeze(4)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(4)?;
+    let short_output = sponge.finish()?;
+    assert!(short_output.is_inline());
+    assert_eq!(short_output.len(), 4);
+
+    // longer outputs spill onto the heap without losing any elements
+    let iopattern = vec![Call::Absorb(2), Call::Squeeze(4), Call::Squeeze(3)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(4)?;
+    sponge.squeeze(3)?;
+    let long_output = sponge.finish()?;
+    assert!(!long_output.is_inline());
+    assert_eq!(long_output.len(), 7);
+    assert_eq!(long_output[..4], short_output[..]);
+
+    Ok(())
+}
+
+#[test]
+fn finish_array() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    const IOPATTERN: &[Call] =
+        &[Call::Absorb(2), Call::Squeeze(2), Call::Squeeze(1)];
+    const OUTPUT_LEN: usize = squeeze_len(IOPATTERN);
+
+    let input = [BlsScalar::one(); 2];
+
+    let mut sponge = Sponge::start(Rotate::new(), IOPATTERN, domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(2)?;
+    sponge.squeeze(1)?;
+    let output_vec = sponge.checkpoint().finish()?;
+    let output_array: [BlsScalar; OUTPUT_LEN] =
+        sponge.checkpoint().finish_array()?;
+    assert_eq!(output_vec[..], output_array[..]);
+
+    // requesting an array of the wrong size fails
+    let error = sponge.finish_array::<2>().unwrap_err();
+    assert_eq!(error, Error::OutputLenMismatch);
+
+    Ok(())
+}
+
+#[test]
+fn rate_and_capacity() {
+    assert_eq!(Sponge::<Rotate, BlsScalar, W>::CAPACITY, 1);
+    assert_eq!(Sponge::<Rotate, BlsScalar, W>::RATE, W - 1);
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct WideCapacity();
+
+impl Safe<BlsScalar, W> for WideCapacity {
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        Rotate().permute(state);
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        BlsScalar::hash_to_scalar(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+
+    const CAPACITY: usize = 2;
+}
+
+#[test]
+fn tag_fills_capacity() -> Result<(), Error> {
+    assert_eq!(Sponge::<WideCapacity, BlsScalar, W>::CAPACITY, 2);
+    assert_eq!(Sponge::<WideCapacity, BlsScalar, W>::RATE, W - 2);
+
+    let pattern = [Call::Absorb(1), Call::Squeeze(1)];
+    let other_pattern = [Call::Absorb(2), Call::Squeeze(1)];
+    let state = |iopattern: &[Call], domain_sep| -> Result<_, Error> {
+        let input = tag_input(iopattern, domain_sep)?;
+        Ok((WideCapacity().tagged_state(&input), input))
+    };
+
+    // the first element is the tag, the second one the tag of the input
+    // followed by its index
+    let (tagged, input) = state(&pattern, 0)?;
+    assert_eq!(tagged[0], BlsScalar::hash_to_scalar(&input));
+    let mut indexed = input.clone();
+    indexed.extend_from_slice(&1u64.to_be_bytes());
+    assert_eq!(tagged[1], BlsScalar::hash_to_scalar(&indexed));
+    assert_eq!(tagged[2..], [BlsScalar::zero(); W - 2]);
+
+    // the second element of the capacity depends on the IO-pattern and the
+    // domain-separator
+    assert_ne!(tagged[1], state(&other_pattern, 0)?.0[1]);
+    assert_ne!(tagged[1], state(&pattern, 1)?.0[1]);
+
+    // backends with a single element of capacity keep their initial state
+    assert_eq!(
+        Rotate().tagged_state(&input),
+        Rotate::initialized_state(Rotate().tag(&input))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn backend_self_test() {
+    // the known answer of the rotating permutation with a zero tag
+    let expected = [BlsScalar::from(2), BlsScalar::from(3), BlsScalar::zero()];
+    assert_eq!(self_test(Rotate::new(), &expected), Ok(()));
+
+    let wrong = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::from(3)];
+    assert_eq!(self_test(Rotate::new(), &wrong), Err(Error::SelfTestFailed));
+}
+
+#[test]
+fn session_hash() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![
+        Call::Absorb(2),
+        Call::Squeeze(1),
+        Call::Absorb(2),
+        Call::Squeeze(1),
+    ];
+    let input1 = [BlsScalar::from(1), BlsScalar::from(2)];
+    let input2 = [BlsScalar::from(3), BlsScalar::from(4)];
+
+    // run the sponge without taking session hashes
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(2, input1)?;
+    sponge.squeeze(1)?;
+    sponge.absorb(2, input2)?;
+    sponge.squeeze(1)?;
+    let expected = sponge.finish()?;
+
+    // run the sponge again, taking session hashes in between
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    let hash0 = sponge.session_hash()?;
+    sponge.absorb(2, input1)?;
+    let hash1 = sponge.session_hash()?;
+    sponge.squeeze(1)?;
+    sponge.absorb(2, input2)?;
+    let hash2 = sponge.session_hash()?;
+    assert_eq!(hash2, sponge.session_hash()?);
+    sponge.squeeze(1)?;
+
+    // the session hash is bound to what was absorbed so far
+    assert_ne!(hash0, hash1);
+    assert_ne!(hash1, hash2);
+
+    // taking session hashes doesn't change the output
+    assert_eq!(sponge.finish()?, expected);
+
+    // the session hash is bound to the position even if absorbing didn't
+    // change the state, which needs a tag depending on its input and a
+    // permutation mixing all elements
+    #[derive(Clone)]
+    struct Mixing();
+
+    impl Safe<BlsScalar, W> for Mixing {
+        fn permute(&mut self, state: &mut [BlsScalar; W]) {
+            let sum: BlsScalar = state.iter().sum();
+            state.iter_mut().for_each(|element| *element += sum);
+        }
+
+        fn tag(&mut self, input: &[u8]) -> BlsScalar {
+            input.iter().map(|byte| BlsScalar::from(*byte as u64)).sum()
+        }
+
+        fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+            right + left
+        }
+    }
+
+    let iopattern = vec![Call::Absorb(1), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Mixing(), iopattern, domain_sep)?;
+    let hash0 = sponge.session_hash()?;
+    sponge.absorb(1, [BlsScalar::zero()])?;
+    assert_ne!(hash0, sponge.session_hash()?);
+
+    Ok(())
+}
+
+struct Note {
+    value: u64,
+    owner: [BlsScalar; 2],
+}
+
+impl Absorbable<BlsScalar> for Note {
+    type Elements = [BlsScalar; 3];
+
+    fn elements_len(&self) -> usize {
+        3
+    }
+
+    fn to_elements(&self) -> Self::Elements {
+        [BlsScalar::from(self.value), self.owner[0], self.owner[1]]
+    }
+}
+
+#[test]
+fn absorb_value() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let note = Note {
+        value: 42,
+        owner: [BlsScalar::from(1), BlsScalar::from(2)],
+    };
+    let iopattern = vec![Call::Absorb(3), Call::Squeeze(1)];
+
+    // absorbing the value is the same as absorbing its elements
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb_value(&note)?;
+    sponge.squeeze(1)?;
+    let output = sponge.finish()?;
+
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(3, note.to_elements())?;
+    sponge.squeeze(1)?;
+    assert_eq!(output, sponge.finish()?);
+
+    // a value of the wrong length violates the IO-pattern
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    let result = sponge.absorb_value(&vec![BlsScalar::one(); 2]);
+    assert_eq!(result, Err(Error::IOPatternViolation));
+
+    Ok(())
+}
+
+// A challenge is the lower 128 bits of the little endian encoding of the
+// squeezed element.
+#[derive(Debug, PartialEq)]
+struct Challenge(u128);
+
+impl SqueezeInto<BlsScalar> for Challenge {
+    const LEN: usize = 1;
+
+    fn from_elements(elements: &[BlsScalar]) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&elements[0].to_bytes()[..16]);
+        Self(u128::from_le_bytes(bytes))
+    }
+}
+
+#[test]
+fn squeeze_typed() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![Call::Absorb(1), Call::Squeeze(1), Call::Squeeze(2)];
+    let input = [BlsScalar::from(42)];
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(1, input)?;
+    let challenge: Challenge = sponge.squeeze_typed()?;
+    let pair = sponge.squeeze_typed::<[BlsScalar; 2]>()?;
+    let output = sponge.finish()?;
+
+    // the typed values are constructed from the squeezed elements
+    assert_eq!(challenge, Challenge::from_elements(&output[..1]));
+    assert_eq!(pair, [output[1], output[2]]);
+
+    // squeezing a type of the wrong length violates the IO-pattern
+    let iopattern = vec![Call::Absorb(1), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(1, input)?;
+    let result = sponge.squeeze_typed::<[BlsScalar; 2]>();
+    assert_eq!(result, Err(Error::IOPatternViolation));
+
+    Ok(())
+}
+
+#[test]
+fn security_level() {
+    let level = SecurityLevel::Bits128;
+    assert_eq!(level.capacity(256), Ok(1));
+    assert_eq!(level.capacity(255), Ok(1));
+    assert_eq!(level.capacity(254), Ok(2));
+    assert_eq!(level.output_len(64), Ok(4));
+    assert_eq!(level.tag_len(255), Ok(1));
+    assert_eq!(level.tag_len(64), Ok(2));
+    assert_eq!(SecurityLevel::Bits256.capacity(256), Ok(2));
+    assert_eq!(level.capacity(0), Err(Error::InvalidFieldSize));
+    assert_eq!(level.tag_len(0), Err(Error::InvalidFieldSize));
+
+    // a keyed sponge may output up to 2^(c * n - bits) elements
+    assert_eq!(level.max_squeeze_len(64, 4), Ok(usize::MAX));
+    assert_eq!(level.max_squeeze_len(40, 4), Ok(1 << 32));
+    assert_eq!(level.max_squeeze_len(64, 2), Ok(1));
+    assert_eq!(
+        level.max_squeeze_len(63, 2),
+        Err(Error::InsufficientCapacity)
+    );
+    assert_eq!(level.max_squeeze_len(0, 2), Err(Error::InvalidFieldSize));
+
+    // the capacity of the sponge is a single element of the scalar field
+    assert_eq!(level.check::<Rotate, BlsScalar, W>(255), Ok(()));
+    assert_eq!(
+        level.check::<Rotate, BlsScalar, W>(254),
+        Err(Error::InsufficientCapacity)
+    );
+    assert_eq!(
+        SecurityLevel::Bits256.check::<Rotate, BlsScalar, W>(255),
+        Err(Error::InsufficientCapacity)
+    );
+}
+
+#[test]
+fn start_with_level() {
+    const IOPATTERN: &[Call] = &[Call::Absorb(1), Call::Squeeze(1)];
+    let level = SecurityLevel::Bits128;
+    assert!(Sponge::<Rotate, BlsScalar, W>::start_with_level(
+        Rotate::new(),
+        IOPATTERN,
+        0,
+        level,
+        255
+    )
+    .is_ok());
+    assert!(matches!(
+        Sponge::<Rotate, BlsScalar, W>::start_with_level(
+            Rotate::new(),
+            IOPATTERN,
+            0,
+            SecurityLevel::Bits192,
+            255
+        ),
+        Err(Error::InsufficientCapacity)
+    ));
+}
+
+#[cfg(feature = "audit")]
+#[test]
+fn audit_log() -> Result<(), Error> {
+    use dusk_safe::{audit_digest, AuditEntry};
+
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![Call::Absorb(2), Call::Absorb(8), Call::Squeeze(3)];
+    let input1 = [BlsScalar::from(1), BlsScalar::from(2)];
+    let input2 = [BlsScalar::from(3); 8];
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(2, input1)?;
+    sponge.absorb(8, input2)?;
+    assert_eq!(sponge.audit_log().len(), 2);
+    sponge.squeeze(3)?;
+    let (output, log) = sponge.finish_with_audit_log()?;
+
+    // every call is recorded with the digest of its elements
+    let mut safe = Rotate::new();
+    assert_eq!(
+        log,
+        vec![
+            AuditEntry {
+                call: Call::Absorb(2),
+                digest: audit_digest(&mut safe, input1)?,
+            },
+            AuditEntry {
+                call: Call::Absorb(8),
+                digest: audit_digest(&mut safe, input2)?,
+            },
+            AuditEntry {
+                call: Call::Squeeze(3),
+                digest: audit_digest(&mut safe, output.iter().copied())?,
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[cfg(all(feature = "audit", feature = "inspect"))]
+#[test]
+fn audit_log_failure() -> Result<(), Error> {
+    let iopattern = vec![Call::Absorb(2), Call::Absorb(3), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+    sponge.absorb(2, [BlsScalar::from(1), BlsScalar::from(2)])?;
+    assert_ne!(sponge.state(), &[BlsScalar::zero(); W]);
+
+    // an empty chunk has no digest, failing to record it erases the sponge
+    assert_eq!(
+        sponge.absorb_partial([]).unwrap_err(),
+        Error::InvalidIOPattern
+    );
+    assert_eq!(sponge.state(), &[BlsScalar::zero(); W]);
+    assert_eq!(sponge.positions(), (0, 0));
+    assert_eq!(sponge.audit_log().len(), 1);
+
+    Ok(())
+}
+
+#[cfg(feature = "subtle")]
+#[test]
+fn ct_eq() -> Result<(), Error> {
+    use subtle::ConstantTimeEq;
+
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![Call::Absorb(2), Call::Squeeze(1)];
+
+    let mut sponge1 =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    let mut sponge2 = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    assert!(bool::from(sponge1.ct_eq(&sponge2)));
+
+    sponge1.absorb(2, [BlsScalar::one(); 2])?;
+    assert!(!bool::from(sponge1.ct_eq(&sponge2)));
+
+    sponge2.absorb(2, [BlsScalar::zero(); 2])?;
+    assert!(!bool::from(sponge1.ct_eq(&sponge2)));
+
+    let sponge3 = sponge1.checkpoint();
+    assert!(bool::from(sponge1.ct_eq(&sponge3)));
+
+    Ok(())
+}
+
+#[test]
+fn tag_version() -> Result<(), Error> {
+    // the tag is the first byte of the tag input, and the permutation copies
+    // it into the rate
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct FirstByte();
+
+    impl Safe<BlsScalar, W> for FirstByte {
+        fn permute(&mut self, state: &mut [BlsScalar; W]) {
+            state[1] = state[0];
+        }
+
+        fn tag(&mut self, input: &[u8]) -> BlsScalar {
+            BlsScalar::from(input[0] as u64)
+        }
+
+        fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+            right + left
+        }
+    }
+
+    let iopattern = vec![Call::Absorb(1), Call::Squeeze(1)];
+
+    let mut sponge = Sponge::start(FirstByte(), iopattern.clone(), 0)?;
+    assert_eq!(sponge.tag_version(), TagVersion::V0);
+    sponge.absorb(1, [BlsScalar::zero()])?;
+    sponge.squeeze(1)?;
+    // the original encoding starts with the absorb mask
+    assert_eq!(sponge.finish()?[..], [BlsScalar::from(0x80)]);
+
+    let mut sponge =
+        Sponge::start_with_version(FirstByte(), iopattern, 0, TagVersion::V1)?;
+    assert_eq!(sponge.tag_version(), TagVersion::V1);
+    sponge.absorb(1, [BlsScalar::zero()])?;
+    sponge.squeeze(1)?;
+    // the versioned encoding starts with the version byte
+    assert_eq!(sponge.finish()?[..], [BlsScalar::from(0x01)]);
+
+    Ok(())
+}
+
+#[test]
+fn absorb_domain() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![
+        Call::Absorb(1),
+        Call::Absorb(2),
+        Call::Absorb(1),
+        Call::Absorb(2),
+        Call::Squeeze(1),
+    ];
+    let input1 = [BlsScalar::from(1), BlsScalar::from(2)];
+    let input2 = [BlsScalar::from(3), BlsScalar::from(4)];
+
+    let hash = |label1, label2| -> Result<_, Error> {
+        let mut sponge =
+            Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+        sponge.absorb_domain(label1)?;
+        sponge.absorb(2, input1)?;
+        sponge.absorb_domain(label2)?;
+        sponge.absorb(2, input2)?;
+        sponge.squeeze(1)?;
+        sponge.finish()
+    };
+
+    // the labels are absorbed like any other element
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(1, [BlsScalar::from(1)])?;
+    sponge.absorb(2, input1)?;
+    sponge.absorb(1, [BlsScalar::from(2)])?;
+    sponge.absorb(2, input2)?;
+    sponge.squeeze(1)?;
+    assert_eq!(hash(1, 2)?, sponge.finish()?);
+
+    Ok(())
+}
+
+#[test]
+fn labeled_iopattern() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let input = [BlsScalar::from(1), BlsScalar::from(2)];
+    let iopattern = vec![
+        Call::Label(1),
+        Call::Absorb(1),
+        Call::Label(2),
+        Call::Absorb(1),
+        Call::Squeeze(1),
+    ];
+
+    // labels aren't called at runtime
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(1, &input[..1])?;
+    sponge.absorb(1, &input[1..])?;
+    sponge.squeeze(1)?;
+    let output = sponge.finish()?;
+    assert_eq!(output.len(), 1);
+
+    // a call not matching the pattern still fails
+    let iopattern = vec![Call::Label(1), Call::Absorb(2), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    assert!(sponge.squeeze(1).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn sponge_element() {
+    use dusk_safe::SpongeElement;
+
+    fn erase<T: SpongeElement>(mut element: T) -> T {
+        element.zeroize();
+        element
+    }
+
+    assert_eq!(erase(BlsScalar::from(42)), BlsScalar::default());
+}
+
+#[test]
+fn sync_point() -> Result<(), Error> {
+    use dusk_safe::SyncPoint;
+
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![Call::Absorb(2), Call::Absorb(1), Call::Squeeze(1)];
+    let input = [BlsScalar::from(1), BlsScalar::from(2)];
+
+    // prover and verifier absorb the same values
+    let mut prover =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    let mut verifier =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    prover.absorb(2, input)?;
+    verifier.absorb(2, input)?;
+    let sync_point = prover.sync_point()?;
+    assert_eq!(sync_point, verifier.sync_point()?);
+    assert_eq!(sync_point.io_count, 1);
+    assert_eq!(
+        sync_point.pattern_digest,
+        pattern_digest(Rotate::new(), &iopattern, domain_sep)?
+    );
+
+    // the sync point survives serialization
+    let bytes = sync_point.to_bytes(BlsScalar::to_bytes);
+    assert_eq!(bytes.len(), 16 + 2 * 32);
+    let decode = |bytes: &[u8; 32]| BlsScalar::from_bytes(bytes).into();
+    assert_eq!(SyncPoint::from_bytes(&bytes, decode)?, sync_point);
+    assert_eq!(
+        SyncPoint::from_bytes(&bytes[1..], decode),
+        Err(Error::InvalidEncoding)
+    );
+
+    // the sync points diverge when the absorbed values do
+    let mut verifier = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    verifier.absorb(2, [BlsScalar::from(3), BlsScalar::from(4)])?;
+    assert_ne!(sync_point, verifier.sync_point()?);
+
+    Ok(())
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn snapshot_divergence() -> Result<(), Error> {
+    use dusk_safe::{first_divergence, Divergence};
+
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![Call::Absorb(2), Call::Absorb(1), Call::Squeeze(1)];
+    let run = |second: u64| -> Result<Vec<_>, Error> {
+        let mut sponge =
+            Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+        sponge.absorb(2, [BlsScalar::from(1), BlsScalar::from(second)])?;
+        sponge.absorb(1, [BlsScalar::from(3)])?;
+        sponge.squeeze(1)?;
+        let snapshots = sponge.snapshots().to_vec();
+        sponge.finish()?;
+        Ok(snapshots)
+    };
+
+    // one snapshot at the start and one after every call
+    let snapshots = run(2)?;
+    assert_eq!(snapshots.len(), 4);
+    assert_eq!(snapshots[0].call_index, None);
+    assert_eq!(snapshots[3].call_index, Some(2));
+    assert_eq!(first_divergence(&snapshots, &run(2)?), None);
+
+    // the second element absorbed by the first call lands at position 2
+    assert_eq!(
+        first_divergence(&snapshots, &run(5)?),
+        Some(Divergence {
+            snapshot: 1,
+            call_index: Some(0),
+            position: Some(2),
+        })
+    );
+
+    // a run that stops early diverges where it stops
+    assert_eq!(
+        first_divergence(&snapshots, &snapshots[..2]),
+        Some(Divergence {
+            snapshot: 2,
+            call_index: Some(1),
+            position: None,
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn pattern_schedule() -> Result<(), Error> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // counts the permutations of the sponge
+    static PERMUTATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Default, Debug, Clone, Copy, PartialEq)]
+    struct Counting(Rotate);
+
+    impl Safe<BlsScalar, W> for Counting {
+        fn permute(&mut self, state: &mut [BlsScalar; W]) {
+            PERMUTATIONS.fetch_add(1, Ordering::Relaxed);
+            self.0.permute(state);
+        }
+
+        fn tag(&mut self, input: &[u8]) -> BlsScalar {
+            self.0.tag(input)
+        }
+
+        fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+            self.0.add(right, left)
+        }
+    }
+
+    // the rate is 6 elements
+    let iopattern = vec![
+        Call::Absorb(8),
+        Call::Squeeze(3),
+        Call::Label(1),
+        Call::Absorb(2),
+        Call::Squeeze(7),
+    ];
+    let schedule = PatternSchedule::new::<W>(&iopattern)?;
+
+    let permutation =
+        |call_index, offset| ScheduledPermutation { call_index, offset };
+    assert_eq!(
+        schedule.permutations(),
+        [
+            permutation(0, 6),
+            permutation(1, 0),
+            permutation(4, 0),
+            permutation(4, 6),
+        ]
+    );
+    assert_eq!(schedule.call_permutations(4), 2);
+    assert_eq!(schedule.call_permutations(3), 0);
+
+    // the sponge permutes as often as scheduled
+    let mut sponge = Sponge::start(Counting::default(), iopattern, 0)?;
+    sponge.absorb(8, [BlsScalar::one(); 8])?;
+    sponge.squeeze(3)?;
+    sponge.absorb(2, [BlsScalar::one(); 2])?;
+    sponge.squeeze(7)?;
+    sponge.finish()?;
+    // the audit log permutes additional states for its digests
+    #[cfg(not(feature = "audit"))]
+    assert_eq!(
+        PERMUTATIONS.load(Ordering::Relaxed),
+        schedule.permutation_count()
+    );
+
+    // ratchets permute the state once and force a permutation before the
+    // next element is squeezed
+    let iopattern = vec![
+        Call::Absorb(2),
+        Call::Ratchet,
+        Call::Absorb(1),
+        Call::Squeeze(1),
+    ];
+    let schedule = PatternSchedule::new::<W>(&iopattern)?;
+    assert_eq!(
+        schedule.permutations(),
+        [permutation(1, 0), permutation(3, 0)]
+    );
+
+    #[cfg(not(feature = "audit"))]
+    let before = PERMUTATIONS.load(Ordering::Relaxed);
+    let mut sponge = Sponge::start(Counting::default(), iopattern, 0)?;
+    sponge.absorb(2, [BlsScalar::one(); 2])?;
+    sponge.ratchet()?;
+    sponge.absorb(1, [BlsScalar::one()])?;
+    sponge.squeeze(1)?;
+    sponge.finish()?;
+    #[cfg(not(feature = "audit"))]
+    assert_eq!(
+        PERMUTATIONS.load(Ordering::Relaxed) - before,
+        schedule.permutation_count()
+    );
+
+    // in full duplex mode, elements absorbed after squeezing share the
+    // permutation window with the squeezed elements
+    let iopattern = vec![
+        Call::Absorb(6),
+        Call::Squeeze(1),
+        Call::Absorb(6),
+        Call::Squeeze(1),
+    ];
+    assert_eq!(
+        PatternSchedule::new::<W>(&iopattern)?.permutations(),
+        [permutation(1, 0), permutation(3, 0)]
+    );
+    let schedule = PatternSchedule::new_duplex::<W>(&iopattern)?;
+    assert_eq!(
+        schedule.permutations(),
+        [permutation(1, 0), permutation(2, 5), permutation(3, 0)]
+    );
+
+    #[cfg(not(feature = "audit"))]
+    let before = PERMUTATIONS.load(Ordering::Relaxed);
+    let mut sponge = Sponge::start_duplex(Counting::default(), iopattern, 0)?;
+    sponge.absorb(6, [BlsScalar::one(); 6])?;
+    sponge.squeeze(1)?;
+    sponge.absorb(6, [BlsScalar::one(); 6])?;
+    sponge.squeeze(1)?;
+    sponge.finish()?;
+    #[cfg(not(feature = "audit"))]
+    assert_eq!(
+        PERMUTATIONS.load(Ordering::Relaxed) - before,
+        schedule.permutation_count()
+    );
+
+    assert_eq!(
+        PatternSchedule::new::<W>([Call::Squeeze(1)]),
+        Err(Error::InvalidIOPattern)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn pattern_optimization() -> Result<(), Error> {
+    // a model counting the constraints of a circuit
+    struct Constraints;
+
+    impl CostModel for Constraints {
+        fn permutation_cost(&self) -> u64 {
+            100
+        }
+
+        fn element_cost(&self) -> u64 {
+            1
+        }
+    }
+
+    // the rate is 6 elements
+    let iopattern = vec![
+        Call::Absorb(2),
+        Call::Squeeze(1),
+        Call::Label(1),
+        Call::Absorb(2),
+        Call::Squeeze(1),
+    ];
+    assert_eq!(pattern_cost::<W>(&iopattern, &Constraints)?, 2 * 100 + 6);
+
+    // absorbing the second input before squeezing saves a permutation
+    let suggestions = optimize_pattern::<W>(&iopattern, &Constraints)?;
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].call_index, 3);
+    assert_eq!(suggestions[0].insert_at, 1);
+    assert_eq!(suggestions[0].saving, 100);
+
+    // nothing can be saved when the inputs exceed the rate
+    let iopattern = vec![
+        Call::Absorb(6),
+        Call::Squeeze(1),
+        Call::Absorb(6),
+        Call::Squeeze(1),
+    ];
+    assert!(optimize_pattern::<W>(&iopattern, &Constraints)?.is_empty());
+
+    assert_eq!(
+        pattern_cost::<W>([Call::Squeeze(1)], &Constraints),
+        Err(Error::InvalidIOPattern)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn squeeze_to_writer() -> Result<(), Error> {
+    // accumulates the squeezed elements without storing them
+    #[derive(Default)]
+    struct Sum(BlsScalar, usize);
+
+    impl FieldWriter<BlsScalar> for Sum {
+        fn write(&mut self, t: BlsScalar) {
+            self.0 += t;
+            self.1 += 1;
+        }
+    }
+
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![Call::Absorb(8), Call::Squeeze(2), Call::Squeeze(9)];
+    let input: Vec<BlsScalar> = (1..9).map(BlsScalar::from).collect();
+
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(8, &input)?;
+    sponge.squeeze(2)?;
+    sponge.squeeze(9)?;
+    let output = sponge.finish()?;
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(8, &input)?;
+    let mut streamed = Vec::new();
+    sponge.squeeze_to(&mut streamed, 2)?;
+    let mut sum = Sum::default();
+    sponge.squeeze_to(&mut sum, 9)?;
+
+    // the streamed elements are not part of the output
+    assert!(sponge.finish()?.is_empty());
+    assert_eq!(streamed[..], output[..2]);
+    assert_eq!(sum.0, output[2..].iter().sum());
+    assert_eq!(sum.1, 9);
+
+    Ok(())
+}
+
+#[test]
+fn pattern_progress() -> Result<(), Error> {
+    let iopattern = vec![
+        Call::Label(3),
+        Call::Absorb(4),
+        Call::Squeeze(1),
+        Call::Ratchet,
+        Call::Absorb(2),
+        Call::Squeeze(2),
+    ];
+    let input: Vec<BlsScalar> = (1..5).map(BlsScalar::from).collect();
+
+    // a generic prover loop drives the sponge along its IO-pattern
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    assert_eq!(sponge.remaining_pattern(), &iopattern[..]);
+    let mut calls = Vec::new();
+    while let Some(call) = sponge.expected_next_call() {
+        calls.push(call);
+        match call {
+            Call::Absorb(len) => sponge.absorb(len, &input)?,
+            Call::Squeeze(len) => sponge.squeeze(len)?,
+            Call::Ratchet => sponge.ratchet()?,
+            Call::Label(_) => unreachable!("labels are skipped"),
+            _ => unreachable!("no other calls are in the pattern"),
+        }
+    }
+    assert_eq!(calls, iopattern[1..]);
+    assert!(sponge.remaining_pattern().is_empty());
+    assert_eq!(sponge.finish()?.len(), 3);
+
+    // a partially absorbed call expects its remaining elements
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb_partial(&input[..1])?;
+    assert_eq!(sponge.expected_next_call(), Some(Call::Absorb(3)));
+    assert_eq!(sponge.remaining_pattern(), &iopattern[1..]);
+
+    // sponges without a fixed IO-pattern accept any call
+    let sponge = Sponge::<_, BlsScalar, W>::start_unbounded(Rotate::new(), 0)?;
+    assert_eq!(sponge.expected_next_call(), None);
+    assert!(sponge.remaining_pattern().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn consume_output_early() -> Result<(), Error> {
+    let iopattern = vec![
+        Call::Absorb(8),
+        Call::Squeeze(2),
+        Call::Absorb(1),
+        Call::Squeeze(3),
+    ];
+    let input: Vec<BlsScalar> = (1..10).map(BlsScalar::from).collect();
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb(8, &input)?;
+    sponge.squeeze(2)?;
+    sponge.absorb(1, &input[8..])?;
+    sponge.squeeze(3)?;
+    let output = sponge.finish()?;
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb(8, &input)?;
+    assert!(sponge.peek_output().is_empty());
+    sponge.squeeze(2)?;
+    assert_eq!(sponge.peek_output(), &output[..2]);
+
+    // the challenge can be used before the sponge is finished
+    let challenge = sponge.take_output();
+    assert_eq!(challenge[..], output[..2]);
+    assert!(sponge.peek_output().is_empty());
+
+    // and is no longer part of the output
+    sponge.absorb(1, &input[8..])?;
+    sponge.squeeze(3)?;
+    assert_eq!(sponge.finish()?[..], output[2..]);
+
+    // fixed size outputs only hold the elements squeezed afterwards
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+    sponge.absorb(8, &input)?;
+    sponge.squeeze(2)?;
+    sponge.take_output();
+    sponge.absorb(1, &input[8..])?;
+    sponge.squeeze(3)?;
+    assert_eq!(sponge.finish_array::<3>()?[..], output[2..]);
+
+    Ok(())
+}
+
+#[test]
+fn squeeze_into_buffer() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![Call::Absorb(8), Call::Squeeze(3), Call::Squeeze(9)];
+    let input: Vec<BlsScalar> = (1..9).map(BlsScalar::from).collect();
+
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(8, &input)?;
+    sponge.squeeze(3)?;
+    sponge.squeeze(9)?;
+    let output = sponge.finish()?;
+
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(8, &input)?;
+    let mut first = [BlsScalar::zero(); 3];
+    sponge.squeeze_into(3, &mut first)?;
+    // only the first `len` elements of the buffer are written
+    let mut second = [BlsScalar::from(42); 10];
+    sponge.squeeze_into(9, &mut second)?;
+
+    // the elements are not part of the output
+    assert!(sponge.finish()?.is_empty());
+    assert_eq!(first[..], output[..3]);
+    assert_eq!(second[..9], output[3..]);
+    assert_eq!(second[9], BlsScalar::from(42));
+
+    // the buffer needs to hold all squeezed elements
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(8, &input)?;
+    let mut short = [BlsScalar::zero(); 2];
+    assert_eq!(
+        sponge.squeeze_into(3, &mut short),
+        Err(Error::OutputLenMismatch)
+    );
+
+    // the sponge is left untouched and the call can be repeated
+    let mut first = [BlsScalar::zero(); 3];
+    let mut second = [BlsScalar::zero(); 9];
+    sponge.squeeze_into(3, &mut first)?;
+    sponge.squeeze_into(9, &mut second)?;
+    assert!(sponge.finish()?.is_empty());
+    assert_eq!(first[..], output[..3]);
+    assert_eq!(second[..], output[3..]);
+
+    Ok(())
+}
+
+#[test]
+fn fixed_size_output() -> Result<(), Error> {
+    let output = |len: usize| -> Result<_, Error> {
+        let iopattern = vec![Call::Absorb(1), Call::Squeeze(len)];
+        let mut sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+        sponge.absorb(1, [BlsScalar::one()])?;
+        sponge.squeeze(len)?;
+        sponge.finish()
+    };
+
+    let pair: [BlsScalar; 2] = output(2)?.try_into()?;
+    assert_eq!(pair[..], output(2)?[..]);
+    assert_eq!(output(1)?.single()?, output(1)?[0]);
+
+    // the error describes the mismatch
+    let mismatch = <[BlsScalar; 3]>::try_from(output(2)?).unwrap_err();
+    assert_eq!(
+        mismatch,
+        LenMismatch {
+            expected: 3,
+            actual: 2
+        }
+    );
+    assert_eq!(
+        mismatch.to_string(),
+        "expected an output of 3 elements, but it holds 2"
+    );
+    assert_eq!(
+        output(2)?.single(),
+        Err(LenMismatch {
+            expected: 1,
+            actual: 2
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn foreign_elements() -> Result<(), Error> {
+    let scalar = JubJubScalar::from_raw([0x0102_0304_0506_0708, 1, 0, 0]);
+    let bytes = scalar.to_bytes();
+
+    // the 32 bytes of a jubjub scalar embed into 5 limbs of 7 bytes
+    let limbs: Vec<BlsScalar> = bytes.embed();
+    assert_eq!(
+        limbs,
+        [
+            BlsScalar::from(0x02_0304_0506_0708),
+            BlsScalar::from(0x0101),
+            BlsScalar::zero(),
+            BlsScalar::zero(),
+            BlsScalar::zero(),
+        ]
+    );
+    assert_eq!(
+        embed_bytes::<BlsScalar>(&[1, 2, 3]),
+        [BlsScalar::from(0x030201)]
+    );
+    #[cfg(feature = "dusk-jubjub")]
+    assert_eq!(Embed::<BlsScalar>::embed(&scalar), limbs);
+
+    // every limb stays below 2^56, so the embedding is injective into fields
+    // of 64 bits as well
+    assert_eq!(
+        embed_bytes::<u64>(&[0xff; 2 * EMBED_LIMB_BYTES]),
+        [0x00ff_ffff_ffff_ffff; 2]
+    );
+
+    // absorbing the foreign element absorbs its limbs
+    let iopattern = vec![Call::Absorb(5), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb_foreign(&bytes)?;
+    sponge.squeeze(1)?;
+    let mut expected = Sponge::start(Rotate::new(), iopattern, 0)?;
+    expected.absorb(5, &limbs)?;
+    expected.squeeze(1)?;
+    assert_eq!(sponge.finish()?, expected.finish()?);
+
+    Ok(())
+}
+
+#[test]
+fn partial_absorb() -> Result<(), Error> {
+    let iopattern = vec![Call::Absorb(8), Call::Squeeze(2)];
+    let input: Vec<BlsScalar> = (1..9).map(BlsScalar::from).collect();
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb(8, &input)?;
+    sponge.squeeze(2)?;
+    let expected = sponge.finish()?;
+
+    // the call can be fed in chunks as they arrive
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    assert_eq!(sponge.absorb_partial(&input[..3])?, 5);
+    assert_eq!(sponge.absorb_partial(&input[3..7])?, 1);
+    assert_eq!(sponge.absorb_partial(&input[7..])?, 0);
+    sponge.squeeze(2)?;
+    assert_eq!(sponge.finish()?, expected);
+
+    // no other call is accepted before the call is complete
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb_partial(&input[..3])?;
+    assert_eq!(sponge.squeeze(2), Err(Error::IOPatternViolation));
+
+    // the chunks can't exceed the length of the call
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb_partial(&input[..3])?;
+    assert_eq!(
+        sponge.absorb_partial(&input[..6]),
+        Err(Error::IOPatternViolation)
+    );
+
+    // an incomplete call fails the sponge
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+    sponge.absorb_partial(&input[..3])?;
+    assert_eq!(sponge.finish(), Err(Error::IOPatternViolation));
+
+    Ok(())
+}
+
+#[test]
+fn absorb_from_iterator() -> Result<(), Error> {
+    let iopattern = vec![Call::Absorb(8), Call::Squeeze(2)];
+    let input: Vec<BlsScalar> = (1..9).map(BlsScalar::from).collect();
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb(8, &input)?;
+    sponge.squeeze(2)?;
+    let expected = sponge.finish()?;
+
+    // the elements can be produced lazily
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb_iter(8, (1..9).map(BlsScalar::from))?;
+    sponge.squeeze(2)?;
+    assert_eq!(sponge.finish()?, expected);
+
+    // elements past the declared length are not consumed
+    let mut elements = (1..).map(BlsScalar::from);
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb_iter(8, &mut elements)?;
+    assert_eq!(elements.next(), Some(BlsScalar::from(9)));
+    sponge.squeeze(2)?;
+    assert_eq!(sponge.finish()?, expected);
+
+    // the iterator needs to yield enough elements
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    assert_eq!(
+        sponge.absorb_iter(8, input.iter().copied().take(7)),
+        Err(Error::TooFewInputElements)
+    );
+
+    // the length is checked against the IO-pattern
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+    assert_eq!(
+        sponge.absorb_iter(7, input.iter().copied()),
+        Err(Error::IOPatternViolation)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn typed_sponge() -> Result<(), Error> {
+    type Pattern =
+        Absorbing<2, Squeezing<1, Absorbing<1, Squeezing<2, Finished>>>>;
+
+    let a = [BlsScalar::from(1), BlsScalar::from(2)];
+    let b = [BlsScalar::from(3)];
+
+    let iopattern = vec![
+        Call::Absorb(2),
+        Call::Squeeze(1),
+        Call::Absorb(1),
+        Call::Squeeze(2),
+    ];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+    sponge.absorb(2, a)?;
+    sponge.squeeze(1)?;
+    sponge.absorb(1, b)?;
+    sponge.squeeze(2)?;
+    let expected = sponge.finish()?;
+
+    // the typed sponge runs the same IO-pattern
+    let sponge = TypedSponge::<_, _, W, Pattern>::start(Rotate::new(), 0)?;
+    let sponge = sponge.absorb(&a)?;
+    let ([first], sponge) = sponge.squeeze()?;
+    let sponge = sponge.absorb(&b)?;
+    let (rest, sponge) = sponge.squeeze()?;
+    assert_eq!(first, expected[0]);
+    assert_eq!(rest[..], expected[1..]);
+    assert_eq!(sponge.finish()?, expected);
+
+    // invalid patterns are still rejected
+    assert!(matches!(
+        TypedSponge::<_, BlsScalar, W, Absorbing<1, Finished>>::start(
+            Rotate::new(),
+            0
+        ),
+        Err(Error::InvalidIOPattern)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn overwrite_output() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![
+        Call::Absorb(3),
+        Call::Squeeze(6),
+        Call::Squeeze(2),
+        Call::Squeeze(1),
+    ];
+    let input = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::from(3)];
+
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(3, input)?;
+    sponge.squeeze(6)?;
+    sponge.squeeze(2)?;
+    sponge.squeeze(1)?;
+    let accumulated = sponge.finish()?;
+
+    // every call to squeeze replaces the output of the previous one
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?
+        .with_output_mode(OutputMode::Overwrite);
+    sponge.absorb(3, input)?;
+    sponge.squeeze(6)?;
+    sponge.squeeze(2)?;
+    sponge.squeeze(1)?;
+    let output = sponge.finish()?;
+    assert_eq!(output[..], accumulated[8..]);
+
+    Ok(())
+}
+
+#[test]
+fn single_element_calls() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 0;
+
+    let iopattern = vec![
+        Call::Absorb(1),
+        Call::Squeeze(1),
+        Call::Absorb(1),
+        Call::Squeeze(1),
+    ];
+
+    let mut sponge =
+        Sponge::start(Rotate::new(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(1, [BlsScalar::from(3)])?;
+    sponge.squeeze(1)?;
+    sponge.absorb(1, [BlsScalar::from(5)])?;
+    sponge.squeeze(1)?;
+    let output = sponge.finish()?;
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb_element(&BlsScalar::from(3))?;
+    let first = sponge.squeeze_element()?;
+    sponge.absorb_element(&BlsScalar::from(5))?;
+    let second = sponge.squeeze_element()?;
+    assert_eq!([first, second][..], output[..]);
+    assert_eq!(sponge.finish()?, output);
+
+    // single-element calls still need to follow the IO-pattern
+    let iopattern = vec![Call::Absorb(2), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    assert_eq!(
+        sponge.absorb_element(&BlsScalar::one()),
+        Err(Error::IOPatternViolation)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn domain_hierarchy() -> Result<(), Error> {
+    use dusk_safe::domain::DomainSeparator;
+
+    const APP: DomainSeparator = match DomainSeparator::new(42) {
+        Ok(domain_sep) => domain_sep,
+        Err(_) => panic!("the domain-separator should be valid"),
+    };
+    // derived domains can be computed at compile time
+    const NOTES: DomainSeparator = APP.derive(b"notes");
+
+    // the derivation is fixed, so that other implementations can reproduce it
+    assert_eq!(APP.child(0).get(), 0x5cdd08a0d146);
+    assert_eq!(APP.derive(b"app").get(), 0xb38e0d62ea4064);
+
+    // derived domains are valid domain-separators for the user
+    assert_eq!(DomainSeparator::new(NOTES.get()), Ok(NOTES));
+    assert_eq!(DomainSeparator::new(u64::MAX), Err(Error::InvalidDomain));
+
+    // children, labels and parents separate the derived domains
+    assert_ne!(APP.child(0), APP.child(1));
+    assert_ne!(APP.derive(b"notes"), APP.derive(b"note"));
+    assert_ne!(APP.child(0), APP.derive(&[]));
+    assert_ne!(APP.child(0).child(1), APP.child(1).child(0));
+
+    let iopattern = vec![Call::Absorb(1), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, NOTES.into())?;
+    sponge.absorb(1, [BlsScalar::one()])?;
+    sponge.squeeze(1)?;
+    sponge.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn domain_from_label() {
+    use dusk_safe::domain::DomainSeparator;
+    use sha3::digest::{ExtendableOutput, Update, XofReader};
+    use sha3::Shake256;
+
+    // labels replace hand-picked numbers, also at compile time
+    const NOTE_ENCRYPTION: DomainSeparator =
+        DomainSeparator::from_label("dusk:phoenix-note-encryption:v1");
+
+    // the label is hashed with SHAKE256, also across the block boundaries of
+    // 136 bytes
+    let shake = |label: &str| {
+        let mut hasher = Shake256::default();
+        hasher.update(label.as_bytes());
+        let mut digest = [0u8; 8];
+        hasher.finalize_xof().read(&mut digest);
+        u64::from_be_bytes(digest) & 0x00ff_ffff_ffff_ffff
+    };
+    let long = "x".repeat(300);
+    for len in [0, 1, 7, 8, 135, 136, 137, 271, 272, 300] {
+        let label = &long[..len];
+        assert_eq!(DomainSeparator::from_label(label).get(), shake(label));
+    }
+    assert_eq!(
+        NOTE_ENCRYPTION.get(),
+        shake("dusk:phoenix-note-encryption:v1")
+    );
+    assert_eq!(
+        DomainSeparator::new(NOTE_ENCRYPTION.get()),
+        Ok(NOTE_ENCRYPTION)
+    );
+
+    // similar labels result in different domain-separators
+    assert_ne!(
+        NOTE_ENCRYPTION,
+        DomainSeparator::from_label("dusk:phoenix-note-encryption:v2")
+    );
+    assert_ne!(
+        DomainSeparator::from_label(""),
+        DomainSeparator::from_label("\0")
+    );
+}
+
+#[test]
+fn unbounded_pattern() -> Result<(), Error> {
+    let domain_sep = 42;
+    let run = |squeeze_len: usize| -> Result<Vec<BlsScalar>, Error> {
+        let mut sponge = Sponge::start_unbounded(Rotate::new(), domain_sep)?;
+        sponge.absorb(2, [BlsScalar::from(1), BlsScalar::from(2)])?;
+        sponge.squeeze(squeeze_len)?;
+        sponge.absorb(1, [BlsScalar::from(3)])?;
+        sponge.squeeze(1)?;
+        Ok(sponge.finish_unbounded()?.to_vec())
+    };
+
+    // the output ends with the element binding the executed IO-pattern
+    let output = run(1)?;
+    assert_eq!(output.len(), 3);
+    assert_eq!(output, run(1)?);
+    let longer = run(2)?;
+    assert_eq!(longer.len(), 4);
+    assert_eq!(output[0], longer[0]);
+    assert_ne!(output[2], longer[3]);
+
+    // an unbounded sponge can't be finished without binding its pattern
+    let mut sponge = Sponge::start_unbounded(Rotate::new(), domain_sep)?;
+    sponge.absorb(1, [BlsScalar::one()])?;
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.finish(), Err(Error::IOPatternViolation));
+
+    // the executed calls need to start with a call to absorb
+    let mut sponge = Sponge::start_unbounded(Rotate::new(), domain_sep)?;
+    assert_eq!(sponge.squeeze(1), Err(Error::IOPatternViolation));
+    let mut sponge = Sponge::start_unbounded(Rotate::new(), domain_sep)?;
+    let mut buf = [BlsScalar::zero(); 1];
+    assert_eq!(
+        sponge.squeeze_into(1, &mut buf),
+        Err(Error::IOPatternViolation)
+    );
+    assert_eq!(buf, [BlsScalar::zero()]);
+
+    // a sponge with a fixed IO-pattern can't be finished as unbounded
+    let iopattern = vec![Call::Absorb(1), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, domain_sep)?;
+    sponge.absorb(1, [BlsScalar::one()])?;
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.finish_unbounded(), Err(Error::IOPatternViolation));
+
+    assert!(matches!(
+        Sponge::<Rotate, BlsScalar, W>::start_unbounded(Rotate::new(), 1 << 56),
+        Err(Error::InvalidDomain)
+    ));
+
+    Ok(())
+}
+
+// A backend that runs `Rotate` and records its permutations in an external
+// log.
+struct LoggedRotate();
+
+impl SafeCtx<BlsScalar, W> for LoggedRotate {
+    type Ctx = Vec<[BlsScalar; W]>;
+
+    fn permute(&mut self, log: &mut Self::Ctx, state: &mut [BlsScalar; W]) {
+        Rotate::new().permute(state);
+        log.push(*state);
+    }
+
+    fn tag(&mut self, _log: &mut Self::Ctx, input: &[u8]) -> BlsScalar {
+        Rotate::new().tag(input)
+    }
+
+    fn add(
+        &mut self,
+        _log: &mut Self::Ctx,
+        right: &BlsScalar,
+        left: &BlsScalar,
+    ) -> BlsScalar {
+        right + left
+    }
+}
+
+#[test]
+fn external_context() -> Result<(), Error> {
+    let iopattern = vec![Call::Absorb(8), Call::Squeeze(1)];
+    let input: Vec<BlsScalar> = (1..9).map(BlsScalar::from).collect();
+
+    let mut sponge = Sponge::start(Rotate::new(), iopattern.clone(), 0)?;
+    sponge.absorb(8, &input)?;
+    sponge.squeeze(1)?;
+    let expected = sponge.finish()?;
+
+    // the context is available in between the calls to the sponge
+    let mut log = Vec::new();
+    let safe = WithCtx::new(LoggedRotate(), &mut log);
+    let mut sponge = Sponge::start(safe, iopattern, 0)?;
+    sponge.absorb(8, &input)?;
+    let permutations = sponge.ctx().len();
+    assert!(permutations > 0);
+    sponge.squeeze(1)?;
+    assert!(sponge.ctx().len() > permutations);
+    assert_eq!(sponge.finish()?, expected);
+    assert!(!log.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn aggregated_pattern() -> Result<(), Error> {
+    let iopattern = vec![
+        Call::Absorb(2),
+        Call::Absorb(4),
+        Call::Squeeze(1),
+        Call::Squeeze(1),
+    ];
+    let sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+    assert_eq!(
+        sponge.aggregated_iopattern(),
+        [Call::Absorb(6), Call::Squeeze(2)]
+    );
+
+    // sponges without a fixed IO-pattern normalize the executed calls
+    let mut sponge = Sponge::start_unbounded(Rotate::new(), 0)?;
+    sponge.absorb(1, [BlsScalar::one()])?;
+    sponge.absorb(1, [BlsScalar::one()])?;
+    assert_eq!(sponge.aggregated_iopattern(), aggregate([Call::Absorb(2)]));
+
+    Ok(())
+}
+
+#[test]
+fn overwrite_absorption() -> Result<(), Error> {
+    // permutes every element on its own, so that the capacity never depends
+    // on the absorbed elements
+    #[derive(Default, Debug, Clone, Copy, PartialEq)]
+    struct Increment {
+        overwrite: bool,
+    }
+
+    impl Safe<BlsScalar, W> for Increment {
+        fn permute(&mut self, state: &mut [BlsScalar; W]) {
+            state.iter_mut().for_each(|s| *s += BlsScalar::one());
+        }
+
+        fn tag(&mut self, _input: &[u8]) -> BlsScalar {
+            BlsScalar::zero()
+        }
+
+        fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+            right + left
+        }
+
+        fn combine(
+            &mut self,
+            state: &BlsScalar,
+            element: &BlsScalar,
+        ) -> BlsScalar {
+            match self.overwrite {
+                true => *element,
+                false => self.add(state, element),
+            }
+        }
+    }
+
+    // the rate is 6 elements
+    let iopattern = vec![
+        Call::Absorb(6),
+        Call::Squeeze(1),
+        Call::Absorb(6),
+        Call::Squeeze(2),
+    ];
+    let run = |overwrite: bool, first: u64| -> Result<_, Error> {
+        let safe = Increment { overwrite };
+        let mut sponge = Sponge::start(safe, iopattern.clone(), 0)?;
+        sponge.absorb(6, [BlsScalar::from(first); 6])?;
+        sponge.squeeze(1)?;
+        sponge.absorb(6, [BlsScalar::from(7); 6])?;
+        sponge.squeeze(2)?;
+        sponge.finish()
+    };
+
+    // absorbing the whole rate in overwrite-mode erases the earlier input
+    assert_eq!(run(true, 1)?[1..], run(true, 2)?[1..]);
+    // while by default the input is added to the state
+    assert_ne!(run(false, 1)?[1..], run(false, 2)?[1..]);
+
+    // overwrite-mode can also be selected without overriding the combination
+    struct Overwriting();
+
+    impl Safe<BlsScalar, W> for Overwriting {
+        fn permute(&mut self, state: &mut [BlsScalar; W]) {
+            Increment { overwrite: true }.permute(state)
+        }
+
+        fn tag(&mut self, _input: &[u8]) -> BlsScalar {
+            BlsScalar::zero()
+        }
+
+        fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+            right + left
+        }
+
+        const ABSORB_MODE: AbsorbMode = AbsorbMode::Overwrite;
+    }
+
+    let mut sponge = Sponge::start(Overwriting(), iopattern.clone(), 0)?;
+    sponge.absorb(6, [BlsScalar::from(1); 6])?;
+    sponge.squeeze(1)?;
+    sponge.absorb(6, [BlsScalar::from(7); 6])?;
+    sponge.squeeze(2)?;
+    assert_eq!(sponge.finish()?, run(true, 1)?);
+
+    Ok(())
+}
+
+#[cfg(feature = "inspect")]
+#[test]
+fn inspect_state() -> Result<(), Error> {
+    let state = |elements: [u64; W]| elements.map(BlsScalar::from);
+
+    let iopattern = vec![Call::Absorb(6), Call::Squeeze(1)];
+    let mut sponge = Sponge::start(Rotate::new(), iopattern, 0)?;
+    assert_eq!(sponge.state(), &[BlsScalar::zero(); W]);
+    assert_eq!(sponge.positions(), (0, 0));
+
+    let input = [1, 2, 3, 8, 5, 6].map(BlsScalar::from);
+    sponge.absorb(6, input)?;
+    assert_eq!(sponge.state(), &state([0, 1, 2, 3, 8, 5, 6]));
+    // the rate is full and the next squeeze permutes the state
+    assert_eq!(sponge.positions(), (6, 6));
+
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.state(), &state([1, 2, 3, 8, 5, 6, 0]));
+    assert_eq!(sponge.positions(), (0, 1));
+
+    Ok(())
+}
+
+#[cfg(feature = "inspect")]
+#[test]
+fn inspect_duplex() -> Result<(), Error> {
+    let state = |elements: [u64; W]| elements.map(BlsScalar::from);
+
+    let iopattern = vec![
+        Call::Absorb(1),
+        Call::Squeeze(2),
+        Call::Absorb(1),
+        Call::Squeeze(1),
+    ];
+    let mut sponge = Sponge::start_duplex(Rotate::new(), iopattern, 0)?;
+    sponge.absorb(1, [BlsScalar::from(1)])?;
+    sponge.squeeze(2)?;
+    assert_eq!(sponge.positions(), (2, 0));
+
+    // the absorbed element fills the rate after the squeezed elements
+    sponge.absorb(1, [BlsScalar::from(9)])?;
+    assert_eq!(sponge.state(), &state([1, 0, 0, 9, 0, 0, 0]));
+    assert_eq!(sponge.positions(), (3, 6));
+
+    // and the next squeeze permutes the state
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.state(), &state([0, 0, 9, 0, 0, 0, 1]));
+    assert_eq!(sponge.positions(), (1, 0));
+
+    Ok(())
+}
@@ -0,0 +1,578 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "digest")]
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{
+    pattern_digest, spec_tag_from_digest, tag_from_digest, tag_input,
+    ByteOrder, Call, DigestTag, EncodingProfile, Error, Handshake, Mismatch,
+    Safe, Sponge, TagVersion,
+};
+use sha2::{Digest, Sha256, Sha512};
+use sha3::Sha3_256;
+
+#[test]
+fn tag_from_digest_reduces_wide() {
+    let input = b"tag input";
+
+    // sha512 yields the 64 bytes in one digest
+    let wide: [u8; 64] = Sha512::new()
+        .chain_update([0])
+        .chain_update(input)
+        .finalize()
+        .into();
+    assert_eq!(
+        tag_from_digest::<Sha512, BlsScalar>(input),
+        BlsScalar::from_bytes_wide(&wide)
+    );
+
+    // sha256 needs two digests
+    let mut wide = [0u8; 64];
+    for i in 0..2 {
+        let digest = Sha256::new()
+            .chain_update([i as u8])
+            .chain_update(input)
+            .finalize();
+        wide[i * 32..(i + 1) * 32].copy_from_slice(&digest);
+    }
+    assert_eq!(
+        tag_from_digest::<Sha256, BlsScalar>(input),
+        BlsScalar::from_bytes_wide(&wide)
+    );
+
+    assert_ne!(
+        tag_from_digest::<Sha256, BlsScalar>(input),
+        tag_from_digest::<Sha256, BlsScalar>(b"other tag input")
+    );
+}
+
+const W: usize = 3;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct DigestTagged {
+    tag_hasher: DigestTag<Sha512>,
+}
+
+impl Safe<BlsScalar, W> for DigestTagged {
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        // mix the sum of all elements into each element
+        let sum: BlsScalar = state.iter().sum();
+        state.iter_mut().for_each(|s| *s = (*s + sum).square());
+    }
+
+    fn tag(&mut self, input: &[u8]) -> BlsScalar {
+        self.tag_hasher.tag(input)
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
+
+#[test]
+fn digest_tag_adapter() -> Result<(), Error> {
+    let input = b"tag input";
+    assert_eq!(
+        DigestTag::<Sha256>::new().tag::<BlsScalar>(input),
+        tag_from_digest::<Sha256, BlsScalar>(input),
+    );
+
+    // the tag separates sponges with different domains
+    let iopattern: &[Call] = &[Call::Absorb(1), Call::Squeeze(1)];
+    let hash = |domain_sep: u64| -> Result<Vec<BlsScalar>, Error> {
+        let mut sponge =
+            Sponge::start(DigestTagged::default(), iopattern, domain_sep)?;
+        sponge.absorb(1, [BlsScalar::from(42)])?;
+        sponge.squeeze(1)?;
+        Ok(sponge.finish()?.to_vec())
+    };
+    assert_ne!(hash(1)?, hash(2)?);
+
+    Ok(())
+}
+
+#[test]
+fn digest_of_pattern() -> Result<(), Error> {
+    let iopattern = [Call::Absorb(2), Call::Absorb(1), Call::Squeeze(1)];
+    let digest = pattern_digest(DigestTagged::default(), iopattern, 7)?;
+
+    // contiguous calls are aggregated in the encoding of the pattern
+    let aggregated = [Call::Absorb(3), Call::Squeeze(1)];
+    assert_eq!(
+        digest,
+        pattern_digest(DigestTagged::default(), aggregated, 7)?
+    );
+
+    // the digest depends on the pattern and the domain-separator
+    let other = [Call::Absorb(3), Call::Squeeze(2)];
+    assert_ne!(digest, pattern_digest(DigestTagged::default(), other, 7)?);
+    assert_ne!(
+        digest,
+        pattern_digest(DigestTagged::default(), iopattern, 8)?
+    );
+
+    assert_eq!(
+        pattern_digest(DigestTagged::default(), [Call::Squeeze(1)], 7),
+        Err(Error::InvalidIOPattern)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn handshake() -> Result<(), Error> {
+    let iopattern = [Call::Absorb(2), Call::Squeeze(1)];
+    let safe = DigestTagged::default();
+    let handshake = Handshake::new(safe, iopattern, 7, TagVersion::V1)?;
+
+    let encode = |element: &BlsScalar| element.to_bytes();
+    let decode = |bytes: &[u8; 32]| {
+        Option::<BlsScalar>::from(BlsScalar::from_bytes(bytes))
+    };
+    let bytes = handshake.to_bytes(encode);
+    let peer = Handshake::from_bytes(&bytes, decode)?;
+    assert_eq!(handshake.check(&peer), Ok(()));
+
+    // the mismatch is reported precisely
+    let other = Handshake::new(safe, iopattern, 7, TagVersion::V0)?;
+    assert_eq!(handshake.check(&other), Err(Mismatch::Version));
+    let other = Handshake::new(safe, iopattern, 8, TagVersion::V1)?;
+    assert_eq!(handshake.check(&other), Err(Mismatch::Domain));
+    let other_pattern = [Call::Absorb(1), Call::Squeeze(1)];
+    let other = Handshake::new(safe, other_pattern, 7, TagVersion::V1)?;
+    assert_eq!(handshake.check(&other), Err(Mismatch::Pattern));
+
+    assert_eq!(
+        Handshake::from_bytes(&bytes[1..], decode),
+        Err(Error::InvalidEncoding)
+    );
+    let mut unknown_version = bytes;
+    unknown_version[0] = 0xff;
+    assert_eq!(
+        Handshake::from_bytes(&unknown_version, decode),
+        Err(Error::InvalidEncoding)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn encoding_profile() -> Result<(), Error> {
+    let iopattern: &[Call] = &[Call::Absorb(1), Call::Squeeze(1)];
+    let hash = |profile: EncodingProfile| -> Result<Vec<BlsScalar>, Error> {
+        let mut sponge = Sponge::start_with_profile(
+            DigestTagged::default(),
+            iopattern,
+            42,
+            profile,
+        )?;
+        sponge.absorb(1, [BlsScalar::from(42)])?;
+        sponge.squeeze(1)?;
+        Ok(sponge.finish()?.to_vec())
+    };
+
+    // the default profile is the encoding of the SAFE specification
+    let mut sponge = Sponge::start(DigestTagged::default(), iopattern, 42)?;
+    sponge.absorb(1, [BlsScalar::from(42)])?;
+    sponge.squeeze(1)?;
+    assert_eq!(hash(EncodingProfile::default())?, sponge.finish()?.to_vec());
+
+    // other layouts of the tag input separate the sponges
+    let little_endian = EncodingProfile {
+        byte_order: ByteOrder::LittleEndian,
+        domain_first: false,
+    };
+    let domain_first = EncodingProfile {
+        byte_order: ByteOrder::BigEndian,
+        domain_first: true,
+    };
+    assert_ne!(hash(EncodingProfile::SAFE)?, hash(little_endian)?);
+    assert_ne!(hash(EncodingProfile::SAFE)?, hash(domain_first)?);
+
+    Ok(())
+}
+
+#[test]
+fn transcript_commitment() -> Result<(), Error> {
+    let domain_sep = 0;
+    let iopattern = vec![
+        Call::Absorb(2),
+        Call::Squeeze(1),
+        Call::Absorb(2),
+        Call::Squeeze(1),
+    ];
+    let run = |input: [BlsScalar; 2]| -> Result<_, Error> {
+        let mut sponge = Sponge::start(
+            DigestTagged::default(),
+            iopattern.clone(),
+            domain_sep,
+        )?;
+        sponge.absorb(2, input)?;
+        sponge.squeeze(1)?;
+        sponge.absorb(2, input)?;
+        sponge.squeeze(1)?;
+        sponge.finish_with_commitment()
+    };
+
+    let input = [BlsScalar::from(1), BlsScalar::from(2)];
+    let (output, commitment) = run(input)?;
+    assert_eq!((output.clone(), commitment), run(input)?);
+
+    // the output is the same as without the commitment
+    let mut sponge =
+        Sponge::start(DigestTagged::default(), iopattern.clone(), domain_sep)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(1)?;
+    sponge.absorb(2, input)?;
+    sponge.squeeze(1)?;
+    assert_eq!(sponge.finish()?, output);
+
+    // the commitment is bound to the absorbed elements
+    let (_, other) = run([BlsScalar::from(3), BlsScalar::from(4)])?;
+    assert_ne!(commitment, other);
+
+    // an incomplete transcript isn't committed to
+    let mut sponge =
+        Sponge::start(DigestTagged::default(), iopattern, domain_sep)?;
+    sponge.absorb(2, input)?;
+    assert_eq!(
+        sponge.finish_with_commitment(),
+        Err(Error::IOPatternViolation)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn chained_phases() -> Result<(), Error> {
+    let first: &[Call] = &[Call::Absorb(1), Call::Squeeze(1)];
+    let second = [Call::Absorb(1), Call::Squeeze(1)];
+    let run = |input: u64| -> Result<_, Error> {
+        let mut sponge = Sponge::start(DigestTagged::default(), first, 1)?;
+        sponge.absorb(1, [BlsScalar::from(input)])?;
+        sponge.squeeze(1)?;
+        let (output, mut next) = sponge.finish_into(second, 2)?;
+        next.absorb(1, [BlsScalar::from(42)])?;
+        next.squeeze(1)?;
+        Ok((output, next.finish()?))
+    };
+
+    // the next phase starts with the commitment to the previous one
+    let (output, next_output) = run(1)?;
+    let mut sponge = Sponge::start(DigestTagged::default(), first, 1)?;
+    sponge.absorb(1, [BlsScalar::from(1)])?;
+    sponge.squeeze(1)?;
+    let (_, commitment) = sponge.finish_with_commitment()?;
+    let mut next = Sponge::start(
+        DigestTagged::default(),
+        vec![Call::Absorb(2), Call::Squeeze(1)],
+        2,
+    )?;
+    next.absorb(2, [commitment, BlsScalar::from(42)])?;
+    next.squeeze(1)?;
+    assert_eq!(next.finish()?, next_output);
+
+    // the next phase is bound to the previous one
+    let (other_output, other_next) = run(2)?;
+    assert_ne!(output, other_output);
+    assert_ne!(next_output, other_next);
+
+    // the previous phase needs to be complete
+    let sponge = Sponge::start(DigestTagged::default(), first, 1)?;
+    assert!(matches!(
+        sponge.finish_into(second, 2),
+        Err(Error::IOPatternViolation)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn full_duplex() -> Result<(), Error> {
+    const IOPATTERN: &[Call] = &[
+        Call::Absorb(1),
+        Call::Squeeze(1),
+        Call::Absorb(1),
+        Call::Squeeze(1),
+    ];
+    let run = |duplex: bool, first: u64, second: u64| -> Result<_, Error> {
+        let mut sponge = match duplex {
+            true => {
+                Sponge::start_duplex(DigestTagged::default(), IOPATTERN, 7)?
+            }
+            false => Sponge::start(DigestTagged::default(), IOPATTERN, 7)?,
+        };
+        sponge.absorb(1, [BlsScalar::from(first)])?;
+        sponge.squeeze(1)?;
+        sponge.absorb(1, [BlsScalar::from(second)])?;
+        sponge.squeeze(1)?;
+        sponge.finish()
+    };
+
+    // both modes are domain separated
+    let duplex = run(true, 1, 2)?;
+    assert_ne!(duplex, run(false, 1, 2)?);
+    assert_eq!(duplex, run(true, 1, 2)?);
+
+    // in both modes, every squeezed element depends on all elements absorbed
+    // before it
+    for mode in [true, false] {
+        let output = run(mode, 1, 2)?;
+        let first = run(mode, 3, 2)?;
+        assert_ne!(output[0], first[0]);
+        assert_ne!(output[1], first[1]);
+        let second = run(mode, 1, 3)?;
+        assert_eq!(output[0], second[0]);
+        assert_ne!(output[1], second[1]);
+    }
+
+    // the first squeezed element doesn't leak the initial state
+    assert_ne!(duplex[0], BlsScalar::zero());
+
+    // the IO-pattern is still enforced
+    let mut sponge =
+        Sponge::start_duplex(DigestTagged::default(), IOPATTERN, 7)?;
+    assert_eq!(sponge.squeeze(1), Err(Error::IOPatternViolation));
+
+    Ok(())
+}
+
+#[test]
+fn ratchet() -> Result<(), Error> {
+    const RATCHETED: &[Call] = &[
+        Call::Absorb(3),
+        Call::Squeeze(1),
+        Call::Ratchet,
+        Call::Absorb(1),
+        Call::Squeeze(2),
+    ];
+    const PLAIN: &[Call] = &[
+        Call::Absorb(3),
+        Call::Squeeze(1),
+        Call::Absorb(1),
+        Call::Squeeze(2),
+    ];
+    let input = [BlsScalar::from(1), BlsScalar::from(2), BlsScalar::from(3)];
+
+    let mut sponge = Sponge::start(DigestTagged::default(), RATCHETED, 5)?;
+    sponge.absorb(3, input)?;
+    sponge.squeeze(1)?;
+    sponge.ratchet()?;
+    sponge.absorb(1, [BlsScalar::from(4)])?;
+    sponge.squeeze(2)?;
+    let ratcheted = sponge.finish()?;
+
+    let mut sponge = Sponge::start(DigestTagged::default(), PLAIN, 5)?;
+    sponge.absorb(3, input)?;
+    sponge.squeeze(1)?;
+    sponge.absorb(1, [BlsScalar::from(4)])?;
+    sponge.squeeze(2)?;
+    let plain = sponge.finish()?;
+
+    // the ratchet is part of the tag and changes the following output
+    assert_ne!(ratcheted[0], plain[0]);
+    assert_ne!(ratcheted[1..], plain[1..]);
+
+    // the ratchet is enforced by the IO-pattern
+    let mut sponge = Sponge::start(DigestTagged::default(), RATCHETED, 5)?;
+    sponge.absorb(3, input)?;
+    assert_eq!(sponge.ratchet(), Err(Error::IOPatternViolation));
+
+    let mut sponge = Sponge::start(DigestTagged::default(), RATCHETED, 5)?;
+    sponge.absorb(3, input)?;
+    sponge.squeeze(1)?;
+    assert_eq!(
+        sponge.absorb(1, [BlsScalar::from(4)]),
+        Err(Error::IOPatternViolation)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn suspend_and_resume() -> Result<(), Error> {
+    const IOPATTERN: &[Call] = &[
+        Call::Absorb(3),
+        Call::Squeeze(1),
+        Call::Absorb(2),
+        Call::Squeeze(2),
+    ];
+    let encode = |t: &BlsScalar| t.to_bytes();
+    let decode = |bytes: &[u8; 32]| BlsScalar::from_bytes(bytes).into();
+    let input = [1, 2, 3, 4, 5].map(BlsScalar::from);
+
+    let mut sponge = Sponge::start(DigestTagged::default(), IOPATTERN, 9)?;
+    sponge.absorb(3, &input[..3])?;
+    sponge.squeeze(1)?;
+    sponge.absorb(2, &input[3..])?;
+    sponge.squeeze(2)?;
+    let expected = sponge.finish()?;
+
+    // suspend in the middle of the transcript, including the squeezed element
+    // and a partially absorbed call
+    let mut sponge = Sponge::start(DigestTagged::default(), IOPATTERN, 9)?;
+    sponge.absorb(3, &input[..3])?;
+    sponge.squeeze(1)?;
+    sponge.absorb_partial(&input[3..4])?;
+    let bytes = sponge.suspend(encode);
+
+    let mut sponge = Sponge::resume(DigestTagged::default(), &bytes, decode)?;
+    sponge.absorb_partial(&input[4..])?;
+    sponge.squeeze(2)?;
+    assert_eq!(sponge.finish()?, expected);
+
+    // the bytes need to be a complete encoding
+    let resume = |bytes: &[u8]| {
+        Sponge::<_, BlsScalar, W>::resume(
+            DigestTagged::default(),
+            bytes,
+            decode,
+        )
+        .err()
+    };
+    assert_eq!(
+        resume(&bytes[..bytes.len() - 1]),
+        Some(Error::InvalidEncoding)
+    );
+    let mut extended = bytes.to_vec();
+    extended.push(0);
+    assert_eq!(resume(&extended), Some(Error::InvalidEncoding));
+
+    // the positions need to fit the rate
+    let mut invalid = bytes.to_vec();
+    invalid[W * 32] = 3;
+    assert_eq!(resume(&invalid), Some(Error::InvalidEncoding));
+
+    // the elements need to be canonical
+    let mut invalid = bytes.to_vec();
+    invalid[..32].fill(0xff);
+    assert_eq!(resume(&invalid), Some(Error::InvalidEncoding));
+
+    Ok(())
+}
+
+#[test]
+fn wide_domains() -> Result<(), Error> {
+    const IOPATTERN: &[Call] = &[Call::Absorb(1), Call::Squeeze(1)];
+    let hash = |sponge: Result<Sponge<_, BlsScalar, W>, Error>| {
+        let mut sponge = sponge?;
+        sponge.absorb(1, [BlsScalar::one()])?;
+        sponge.squeeze(1)?;
+        sponge.finish()
+    };
+    let bytes = |domain: &[u8]| {
+        hash(Sponge::start_with_domain_bytes(
+            DigestTagged::default(),
+            IOPATTERN,
+            domain,
+        ))
+    };
+
+    // structured identifiers separate the domains
+    let mainnet = bytes(b"network:1/protocol:transfer/v1")?;
+    assert_ne!(mainnet, bytes(b"network:2/protocol:transfer/v1")?);
+    assert_eq!(mainnet, bytes(b"network:1/protocol:transfer/v1")?);
+
+    // a 128-bit domain-separator is its big endian bytes
+    let wide = 1u128 << 100 | 42;
+    assert_eq!(
+        hash(Sponge::start_with_u128_domain(
+            DigestTagged::default(),
+            IOPATTERN,
+            wide,
+        ))?,
+        bytes(&wide.to_be_bytes())?
+    );
+
+    // wide domains are separated from the 64-bit ones
+    assert_ne!(
+        bytes(&[])?,
+        hash(Sponge::start(DigestTagged::default(), IOPATTERN, 0))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn public_tag_input() -> Result<(), Error> {
+    let iopattern = [
+        Call::Absorb(2),
+        Call::Absorb(1),
+        Call::Label(0x1_0000_0002),
+        Call::Squeeze(4),
+    ];
+    let domain_sep = 0x0102;
+
+    // the documented encoding of the preimage
+    let input = tag_input(iopattern, domain_sep)?;
+    assert_eq!(
+        input,
+        [
+            0x80, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x01, 0x02,
+        ]
+    );
+
+    // which results in the tag of the sponge
+    let mut safe = DigestTagged::default();
+    assert_eq!(
+        safe.tag(&input),
+        pattern_digest(DigestTagged::default(), iopattern, domain_sep)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn spec_tag() -> Result<(), Error> {
+    // a backend using the initial value of the SAFE specification
+    #[derive(Default, Debug, Clone, Copy, PartialEq)]
+    struct SpecTagged {
+        inner: DigestTagged,
+        tag_hasher: DigestTag<Sha3_256>,
+    }
+
+    impl Safe<BlsScalar, W> for SpecTagged {
+        fn permute(&mut self, state: &mut [BlsScalar; W]) {
+            self.inner.permute(state);
+        }
+
+        fn tag(&mut self, input: &[u8]) -> BlsScalar {
+            self.tag_hasher.spec_tag(input)
+        }
+
+        fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+            right + left
+        }
+    }
+
+    let iopattern = [Call::Absorb(2), Call::Squeeze(1)];
+    let input = tag_input(iopattern, 42)?;
+
+    // the tag is the first 128 bits of the SHA3-256 digest, little endian
+    let digest = Sha3_256::digest(&input);
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(&digest[..16]);
+    let expected = BlsScalar::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        spec_tag_from_digest::<Sha3_256, BlsScalar>(&input),
+        expected
+    );
+
+    // and the tag of the sponge
+    assert_eq!(
+        pattern_digest(SpecTagged::default(), iopattern, 42)?,
+        expected
+    );
+    assert_ne!(
+        pattern_digest(DigestTagged::default(), iopattern, 42)?,
+        expected
+    );
+
+    Ok(())
+}
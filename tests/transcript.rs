@@ -0,0 +1,96 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_bls12_381::BlsScalar;
+use dusk_safe::{transcript, Call, Error, Safe, Sponge};
+
+const W: usize = 7;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct Rotate();
+
+impl Safe<BlsScalar, W> for Rotate {
+    // rotate every item one item to the left, first item becomes last
+    fn permute(&mut self, state: &mut [BlsScalar; W]) {
+        state.rotate_left(1);
+    }
+
+    // Setting the tag to a constant zero here so that the sponge output
+    // is predictable, this should *not* be done in production as it makes the
+    // resulting hash vulnerable to collisions attacks.
+    fn tag(&mut self, _input: &[u8]) -> BlsScalar {
+        BlsScalar::zero()
+    }
+
+    fn add(&mut self, right: &BlsScalar, left: &BlsScalar) -> BlsScalar {
+        right + left
+    }
+}
+
+transcript! {
+    /// The IO-pattern of the schnorr transcript.
+    const SCHNORR_IO_PATTERN;
+    /// The transcript of a schnorr signature.
+    struct Schnorr: BlsScalar {
+        absorb public_key: [BlsScalar; 2];
+        absorb commitment: [BlsScalar; 2];
+        squeeze challenge: [BlsScalar; 1];
+        absorb response: [BlsScalar; 1];
+        squeeze output: [BlsScalar; 2];
+    }
+}
+
+#[test]
+fn iopattern() {
+    assert_eq!(
+        SCHNORR_IO_PATTERN,
+        [
+            Call::Absorb(2),
+            Call::Absorb(2),
+            Call::Squeeze(1),
+            Call::Absorb(1),
+            Call::Squeeze(2),
+        ]
+    );
+}
+
+#[test]
+fn transcript() -> Result<(), Error> {
+    // pick a domain-separator
+    let domain_sep = 42;
+
+    let public_key = [BlsScalar::from(1), BlsScalar::from(2)];
+    let commitment = [BlsScalar::from(3), BlsScalar::from(4)];
+    let response = [BlsScalar::from(5)];
+
+    let mut transcript = Schnorr::start(Rotate(), domain_sep)?;
+    transcript.public_key(&public_key)?;
+    transcript.commitment(&commitment)?;
+    let challenge = transcript.challenge()?;
+    transcript.response(&response)?;
+    let output = transcript.output()?;
+    let all = transcript.finish()?;
+
+    // the transcript behaves like the sponge with the same IO-pattern
+    let mut sponge = Sponge::start(Rotate(), SCHNORR_IO_PATTERN, domain_sep)?;
+    sponge.absorb(2, public_key)?;
+    sponge.absorb(2, commitment)?;
+    sponge.squeeze(1)?;
+    sponge.absorb(1, response)?;
+    sponge.squeeze(2)?;
+    let expected = sponge.finish()?;
+
+    assert_eq!(all, expected);
+    assert_eq!(challenge, [expected[0]]);
+    assert_eq!(output, [expected[1], expected[2]]);
+
+    // calling the steps out of order fails
+    let mut transcript = Schnorr::start(Rotate(), domain_sep)?;
+    transcript.public_key(&public_key)?;
+    assert_eq!(transcript.challenge(), Err(Error::IOPatternViolation));
+
+    Ok(())
+}